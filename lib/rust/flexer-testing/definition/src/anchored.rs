@@ -0,0 +1,228 @@
+//! This module contains the code defining a lexer whose rules are anchored to the starts and ends
+//! of lines. For the actual tests of this code, please see `flexer-testing/generation`.
+//!
+//! The language is being defined as follows, where `^` and `$` stand for the start and end of a
+//! line respectively:
+//!
+//! word     = ('a' | 'b')+;
+//! comment  = ^, '#', (any - newline)*;
+//! indent   = ^, ' '+;
+//! trailing = ' '+, $;
+//! language = (comment | indent | trailing | word | ' ' | newline)*;
+
+use flexer::prelude::*;
+
+use flexer::*;
+use flexer;
+use flexer::automata::pattern::Pattern;
+use flexer::group::Registry;
+use flexer::prelude::logger::Disabled;
+use flexer::prelude::reader::BookmarkManager;
+
+
+
+// ====================
+// === Type Aliases ===
+// ====================
+
+type Logger = Disabled;
+
+
+
+// ===========
+// === AST ===
+// ===========
+
+/// The tokens of the language.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Token {
+    /// A word from the input.
+    Word(String),
+    /// A comment, which must start at the beginning of a line.
+    Comment(String),
+    /// The number of spaces at the beginning of a line.
+    Indent(usize),
+    /// The number of spaces at the end of a line.
+    Trailing(usize),
+    /// A token that the lexer is unable to recognise.
+    Unrecognized(String),
+}
+
+/// A representation of a stream of tokens.
+#[allow(missing_docs)]
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct TokenStream {
+    pub tokens:Vec<Token>
+}
+
+
+
+// ======================
+// === Anchored Lexer ===
+// ======================
+
+/// The definition of a lexer with rules anchored to lines.
+#[derive(Debug)]
+pub struct AnchoredLexer {
+    lexer:Flexer<AnchoredState,TokenStream,Logger>
+}
+
+impl Deref for AnchoredLexer {
+    type Target = Flexer<AnchoredState,TokenStream,Logger>;
+    fn deref(&self) -> &Self::Target {
+        &self.lexer
+    }
+}
+
+impl DerefMut for AnchoredLexer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lexer
+    }
+}
+
+impl AnchoredLexer {
+    /// Creates a new instance of this lexer.
+    pub fn new() -> Self {
+        let logger = Logger::new("AnchoredLexer");
+        let lexer  = Flexer::new(logger);
+        AnchoredLexer{lexer}
+    }
+}
+
+/// Rules for the root state.
+#[allow(dead_code,missing_docs)]
+impl AnchoredLexer {
+    fn on_word<R:LazyReader>(&mut self, _reader:&mut R) {
+        let word = Token::Word(self.current_match.to_string());
+        self.output.tokens.push(word);
+    }
+
+    fn on_comment<R:LazyReader>(&mut self, _reader:&mut R) {
+        let comment = Token::Comment(self.current_match.to_string());
+        self.output.tokens.push(comment);
+    }
+
+    fn on_indent<R:LazyReader>(&mut self, _reader:&mut R) {
+        let indent = Token::Indent(self.current_match.len());
+        self.output.tokens.push(indent);
+    }
+
+    fn on_trailing<R:LazyReader>(&mut self, _reader:&mut R) {
+        let trailing = Token::Trailing(self.current_match.len());
+        self.output.tokens.push(trailing);
+    }
+
+    fn on_unrecognized<R:LazyReader>(&mut self, _reader:&mut R) {
+        let token = Token::Unrecognized(self.current_match.to_string());
+        self.output.tokens.push(token);
+    }
+
+    fn on_skip<R:LazyReader>(&mut self, _reader:&mut R) {}
+
+    fn rules_in_root(lexer:&mut AnchoredLexer) {
+        let word     = (Pattern::char('a') | Pattern::char('b')).many1();
+        let text     = Pattern::none_of("\r\n").many();
+        let comment  = Pattern::line_start() >> Pattern::char('#') >> text;
+        let spaces   = Pattern::char(' ').many1();
+        let indent   = Pattern::line_start() >> &spaces;
+        let trailing = &spaces >> Pattern::line_end();
+        let space    = Pattern::char(' ');
+        let newline  = Pattern::any_of("\r\n");
+        let any      = Pattern::any();
+        let end      = Pattern::eof();
+
+        let root_group_id = lexer.initial_state;
+        let root_group    = lexer.groups_mut().group_mut(root_group_id);
+
+        root_group.create_rule(&word,"self.on_word(reader)");
+        root_group.create_rule(&comment,"self.on_comment(reader)");
+        root_group.create_rule(&indent,"self.on_indent(reader)");
+        root_group.create_rule(&trailing,"self.on_trailing(reader)");
+        root_group.create_rule(&space,"self.on_skip(reader)");
+        root_group.create_rule(&newline,"self.on_skip(reader)");
+        root_group.create_rule(&end,"self.on_skip(reader)");
+        root_group.create_rule(&any,"self.on_unrecognized(reader)");
+    }
+}
+
+
+// === Trait Impls ===
+
+impl flexer::Definition for AnchoredLexer {
+    fn define() -> Self {
+        let mut lexer = AnchoredLexer::new();
+        AnchoredLexer::rules_in_root(&mut lexer);
+        lexer
+    }
+
+    fn groups(&self) -> &Registry {
+        self.lexer.groups()
+    }
+
+    fn set_up(&mut self) {}
+
+    fn tear_down(&mut self) {}
+
+    fn reset(&mut self) {
+        self.lexer.reset()
+    }
+}
+
+impl Default for AnchoredLexer {
+    fn default() -> Self {
+        AnchoredLexer::new()
+    }
+}
+
+
+
+// ======================
+// === Anchored State ===
+// ======================
+
+/// The stateful components of the lexer.
+#[derive(Debug)]
+pub struct AnchoredState {
+    /// The registry for groups in the lexer.
+    lexer_states:group::Registry,
+    /// The initial state of the lexer.
+    initial_state:group::Identifier,
+    /// The bookmarks for this lexer.
+    bookmarks:BookmarkManager
+}
+
+
+// === Trait Impls ===
+
+impl flexer::State for AnchoredState {
+    fn new(_logger:&impl AnyLogger) -> Self {
+        let mut lexer_states = group::Registry::default();
+        let initial_state    = lexer_states.define_group("ROOT",None);
+        let bookmarks        = BookmarkManager::new();
+        Self{lexer_states,initial_state,bookmarks}
+    }
+
+    fn initial_state(&self) -> group::Identifier {
+        self.initial_state
+    }
+
+    fn groups(&self) -> &group::Registry {
+        &self.lexer_states
+    }
+
+    fn groups_mut(&mut self) -> &mut group::Registry {
+        &mut self.lexer_states
+    }
+
+    fn bookmarks(&self) -> &BookmarkManager {
+        &self.bookmarks
+    }
+
+    fn bookmarks_mut(&mut self) -> &mut BookmarkManager {
+        &mut self.bookmarks
+    }
+
+    fn specialize(&self) -> Result<String,GenError> {
+        generate::specialize(self,"AnchoredLexer","TokenStream")
+    }
+}
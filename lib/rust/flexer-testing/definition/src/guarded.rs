@@ -0,0 +1,232 @@
+//! This module contains the code defining a lexer with guarded rules, whose guards can be switched
+//! off to exercise the rules that the lexer falls back to. For the actual tests of this code,
+//! please see `flexer-testing/generation`.
+//!
+//! The language is being defined as follows, where the rules marked with `?` only apply while
+//! words are enabled:
+//!
+//! word     = ? 'a', 'b' | ? 'b', 'a' | ? 'a', 'a', 'b';
+//! reversed = 'b', 'a';
+//! letter   = 'a' | 'b';
+//! toggle   = '!';
+//! language = (word | reversed | letter | toggle)*;
+
+use flexer::prelude::*;
+
+use flexer::*;
+use flexer;
+use flexer::automata::pattern::Pattern;
+use flexer::group::Registry;
+use flexer::prelude::logger::Disabled;
+use flexer::prelude::reader::BookmarkManager;
+
+
+
+// ====================
+// === Type Aliases ===
+// ====================
+
+type Logger = Disabled;
+
+
+
+// ===========
+// === AST ===
+// ===========
+
+/// The tokens of the language.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Token {
+    /// A word, matched while words are enabled.
+    Word(String),
+    /// The word `ba`, matched while words are disabled.
+    Reversed(String),
+    /// A single letter.
+    Letter(String),
+    /// A token that the lexer is unable to recognise.
+    Unrecognized(String),
+}
+
+/// A representation of a stream of tokens.
+#[allow(missing_docs)]
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct TokenStream {
+    pub tokens:Vec<Token>
+}
+
+
+
+// =====================
+// === Guarded Lexer ===
+// =====================
+
+/// The definition of a lexer with guarded rules.
+#[derive(Debug)]
+pub struct GuardedLexer {
+    lexer:Flexer<GuardedState,TokenStream,Logger>,
+    /// Whether the guarded rules for words apply.
+    words_enabled:bool,
+}
+
+impl Deref for GuardedLexer {
+    type Target = Flexer<GuardedState,TokenStream,Logger>;
+    fn deref(&self) -> &Self::Target {
+        &self.lexer
+    }
+}
+
+impl DerefMut for GuardedLexer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lexer
+    }
+}
+
+impl GuardedLexer {
+    /// Creates a new instance of this lexer.
+    pub fn new() -> Self {
+        let logger        = Logger::new("GuardedLexer");
+        let lexer         = Flexer::new(logger);
+        let words_enabled = true;
+        GuardedLexer{lexer,words_enabled}
+    }
+}
+
+/// Rules for the root state.
+#[allow(dead_code,missing_docs)]
+impl GuardedLexer {
+    fn words_enabled(&self) -> bool {
+        self.words_enabled
+    }
+
+    fn on_word<R:LazyReader>(&mut self, _reader:&mut R) {
+        let word = Token::Word(self.current_match.to_string());
+        self.output.tokens.push(word);
+    }
+
+    fn on_reversed<R:LazyReader>(&mut self, _reader:&mut R) {
+        let reversed = Token::Reversed(self.current_match.to_string());
+        self.output.tokens.push(reversed);
+    }
+
+    fn on_letter<R:LazyReader>(&mut self, _reader:&mut R) {
+        let letter = Token::Letter(self.current_match.to_string());
+        self.output.tokens.push(letter);
+    }
+
+    fn on_toggle<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.words_enabled = !self.words_enabled;
+    }
+
+    fn on_unrecognized<R:LazyReader>(&mut self, _reader:&mut R) {
+        let token = Token::Unrecognized(self.current_match.to_string());
+        self.output.tokens.push(token);
+    }
+
+    fn on_skip<R:LazyReader>(&mut self, _reader:&mut R) {}
+
+    fn rules_in_root(lexer:&mut GuardedLexer) {
+        let ab     = Pattern::all_of("ab");
+        let ba     = Pattern::all_of("ba");
+        let aab    = Pattern::all_of("aab");
+        let letter = Pattern::any_of("ab");
+        let toggle = Pattern::char('!');
+        let any    = Pattern::any();
+        let end    = Pattern::eof();
+
+        let root_group_id = lexer.initial_state;
+        let root_group    = lexer.groups_mut().group_mut(root_group_id);
+
+        let enabled = "self.words_enabled()";
+        root_group.create_guarded_rule(&ab,enabled,"self.on_word(reader)");
+        root_group.create_guarded_rule(&ba,enabled,"self.on_word(reader)");
+        root_group.create_guarded_rule(&aab,enabled,"self.on_word(reader)");
+        root_group.create_rule(&ba,"self.on_reversed(reader)");
+        root_group.create_rule(&letter,"self.on_letter(reader)");
+        root_group.create_rule(&toggle,"self.on_toggle(reader)");
+        root_group.create_rule(&end,"self.on_skip(reader)");
+        root_group.create_rule(&any,"self.on_unrecognized(reader)");
+    }
+}
+
+
+// === Trait Impls ===
+
+impl flexer::Definition for GuardedLexer {
+    fn define() -> Self {
+        let mut lexer = GuardedLexer::new();
+        GuardedLexer::rules_in_root(&mut lexer);
+        lexer
+    }
+
+    fn groups(&self) -> &Registry {
+        self.lexer.groups()
+    }
+
+    fn set_up(&mut self) {}
+
+    fn tear_down(&mut self) {}
+
+    fn reset(&mut self) {
+        self.words_enabled = true;
+        self.lexer.reset()
+    }
+}
+
+impl Default for GuardedLexer {
+    fn default() -> Self {
+        GuardedLexer::new()
+    }
+}
+
+
+
+// =====================
+// === Guarded State ===
+// =====================
+
+/// The stateful components of the lexer.
+#[derive(Debug)]
+pub struct GuardedState {
+    /// The registry for groups in the lexer.
+    lexer_states:group::Registry,
+    /// The initial state of the lexer.
+    initial_state:group::Identifier,
+    /// The bookmarks for this lexer.
+    bookmarks:BookmarkManager
+}
+
+
+// === Trait Impls ===
+
+impl flexer::State for GuardedState {
+    fn new(_logger:&impl AnyLogger) -> Self {
+        let mut lexer_states = group::Registry::default();
+        let initial_state    = lexer_states.define_group("ROOT",None);
+        let bookmarks        = BookmarkManager::new();
+        Self{lexer_states,initial_state,bookmarks}
+    }
+
+    fn initial_state(&self) -> group::Identifier {
+        self.initial_state
+    }
+
+    fn groups(&self) -> &group::Registry {
+        &self.lexer_states
+    }
+
+    fn groups_mut(&mut self) -> &mut group::Registry {
+        &mut self.lexer_states
+    }
+
+    fn bookmarks(&self) -> &BookmarkManager {
+        &self.bookmarks
+    }
+
+    fn bookmarks_mut(&mut self) -> &mut BookmarkManager {
+        &mut self.bookmarks
+    }
+
+    fn specialize(&self) -> Result<String,GenError> {
+        generate::specialize(self,"GuardedLexer","TokenStream")
+    }
+}
@@ -0,0 +1,371 @@
+//! This module contains the code defining a pair of lexers, one of which delegates part of its
+//! input to the other. For the actual tests of this code, please see `flexer-testing/generation`.
+//!
+//! The outer language is being defined as follows, where `numbers` is lexed by the inner lexer:
+//!
+//! word     = ('a' | 'b')+;
+//! number   = ('0'..'9')+;
+//! numbers  = '[', (number | ',')*, ']';
+//! language = (word | numbers | ' ')*;
+
+use flexer::prelude::*;
+
+use flexer::*;
+use flexer;
+use flexer::automata::pattern::Pattern;
+use flexer::group::Registry;
+use flexer::group::rule::Rule;
+use flexer::prelude::logger::Disabled;
+use flexer::prelude::reader::BookmarkManager;
+
+
+
+// ====================
+// === Type Aliases ===
+// ====================
+
+type Logger = Disabled;
+
+
+
+// ===========
+// === AST ===
+// ===========
+
+/// The tokens of the outer language.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Token {
+    /// A word from the input.
+    Word(String),
+    /// The numbers between a pair of square brackets, as lexed by the inner lexer.
+    Numbers(NumberStream),
+    /// A token that the lexer is unable to recognise.
+    Unrecognized(String),
+}
+
+/// A representation of a stream of tokens of the outer language.
+#[allow(missing_docs)]
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct TokenStream {
+    pub tokens:Vec<Token>
+}
+
+/// A representation of a stream of the numbers lexed by the inner lexer.
+#[allow(missing_docs)]
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct NumberStream {
+    pub numbers:Vec<u32>
+}
+
+
+
+// ===================
+// === Outer Lexer ===
+// ===================
+
+/// The definition of the lexer for the outer language.
+#[derive(Debug)]
+pub struct OuterLexer {
+    lexer:Flexer<OuterState,TokenStream,Logger>
+}
+
+impl Deref for OuterLexer {
+    type Target = Flexer<OuterState,TokenStream,Logger>;
+    fn deref(&self) -> &Self::Target {
+        &self.lexer
+    }
+}
+
+impl DerefMut for OuterLexer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lexer
+    }
+}
+
+impl OuterLexer {
+    /// Creates a new instance of this lexer.
+    pub fn new() -> Self {
+        let logger = Logger::new("OuterLexer");
+        let lexer  = Flexer::new(logger);
+        OuterLexer{lexer}
+    }
+}
+
+/// Rules for the root state.
+#[allow(dead_code,missing_docs)]
+impl OuterLexer {
+    fn on_word<R:LazyReader>(&mut self, _reader:&mut R) {
+        let word = Token::Word(self.current_match.to_string());
+        self.output.tokens.push(word);
+    }
+
+    fn on_numbers<R:LazyReader>(&mut self, _reader:&mut R, nested:LexingResult<NumberStream>) {
+        let token = match nested.kind {
+            ResultKind::Success => Token::Numbers(nested.tokens),
+            _                   => Token::Unrecognized(self.current_match.to_string()),
+        };
+        self.output.tokens.push(token);
+    }
+
+    fn on_unrecognized<R:LazyReader>(&mut self, _reader:&mut R) {
+        let token = Token::Unrecognized(self.current_match.to_string());
+        self.output.tokens.push(token);
+    }
+
+    fn on_skip<R:LazyReader>(&mut self, _reader:&mut R) {}
+
+    fn rules_in_root(lexer:&mut OuterLexer) {
+        let word    = (Pattern::char('a') | Pattern::char('b')).many1();
+        let open    = Pattern::char('[');
+        let space   = Pattern::char(' ');
+        let any     = Pattern::any();
+        let end     = Pattern::eof();
+        let numbers = Rule::new(open,"self.on_numbers(reader,nested)").delegating_to("self.inner");
+
+        let root_group_id = lexer.initial_state;
+        let root_group    = lexer.groups_mut().group_mut(root_group_id);
+
+        root_group.create_rule(&word,"self.on_word(reader)");
+        root_group.add_rule(numbers);
+        root_group.create_rule(&space,"self.on_skip(reader)");
+        root_group.create_rule(&end,"self.on_skip(reader)");
+        root_group.create_rule(&any,"self.on_unrecognized(reader)");
+    }
+}
+
+
+// === Trait Impls ===
+
+impl flexer::Definition for OuterLexer {
+    fn define() -> Self {
+        let mut lexer = OuterLexer::new();
+        OuterLexer::rules_in_root(&mut lexer);
+        lexer
+    }
+
+    fn groups(&self) -> &Registry {
+        self.lexer.groups()
+    }
+
+    fn set_up(&mut self) {}
+
+    fn tear_down(&mut self) {}
+
+    fn reset(&mut self) {
+        self.lexer.reset()
+    }
+}
+
+impl Default for OuterLexer {
+    fn default() -> Self {
+        OuterLexer::new()
+    }
+}
+
+
+
+// ===================
+// === Outer State ===
+// ===================
+
+/// The stateful components of the outer lexer.
+#[derive(Debug)]
+pub struct OuterState {
+    /// The registry for groups in the lexer.
+    lexer_states:group::Registry,
+    /// The initial state of the lexer.
+    initial_state:group::Identifier,
+    /// The lexer that the numbers between square brackets are delegated to.
+    inner:InnerLexer,
+    /// The bookmarks for this lexer.
+    bookmarks:BookmarkManager
+}
+
+
+// === Trait Impls ===
+
+impl flexer::State for OuterState {
+    fn new(_logger:&impl AnyLogger) -> Self {
+        let mut lexer_states = group::Registry::default();
+        let initial_state    = lexer_states.define_group("ROOT",None);
+        let inner            = InnerLexer::new();
+        let bookmarks        = BookmarkManager::new();
+        Self{lexer_states,initial_state,inner,bookmarks}
+    }
+
+    fn initial_state(&self) -> group::Identifier {
+        self.initial_state
+    }
+
+    fn groups(&self) -> &group::Registry {
+        &self.lexer_states
+    }
+
+    fn groups_mut(&mut self) -> &mut group::Registry {
+        &mut self.lexer_states
+    }
+
+    fn bookmarks(&self) -> &BookmarkManager {
+        &self.bookmarks
+    }
+
+    fn bookmarks_mut(&mut self) -> &mut BookmarkManager {
+        &mut self.bookmarks
+    }
+
+    fn reset(&mut self) {
+        self.bookmarks = default();
+        self.inner.reset();
+    }
+
+    fn specialize(&self) -> Result<String,GenError> {
+        generate::specialize(self,"OuterLexer","TokenStream")
+    }
+}
+
+
+
+// ===================
+// === Inner Lexer ===
+// ===================
+
+/// The definition of the lexer for the numbers between square brackets.
+#[derive(Debug)]
+pub struct InnerLexer {
+    lexer:Flexer<InnerState,NumberStream,Logger>
+}
+
+impl Deref for InnerLexer {
+    type Target = Flexer<InnerState,NumberStream,Logger>;
+    fn deref(&self) -> &Self::Target {
+        &self.lexer
+    }
+}
+
+impl DerefMut for InnerLexer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lexer
+    }
+}
+
+impl InnerLexer {
+    /// Creates a new instance of this lexer.
+    pub fn new() -> Self {
+        let logger = Logger::new("InnerLexer");
+        let lexer  = Flexer::new(logger);
+        InnerLexer{lexer}
+    }
+}
+
+/// Rules for the root state.
+#[allow(dead_code,missing_docs)]
+impl InnerLexer {
+    fn on_number<R:LazyReader>(&mut self, _reader:&mut R) {
+        let number = self.current_match.to_string().parse().unwrap();
+        self.output.numbers.push(number);
+    }
+
+    fn on_close<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.finish_nested();
+    }
+
+    fn on_skip<R:LazyReader>(&mut self, _reader:&mut R) {}
+
+    fn rules_in_root(lexer:&mut InnerLexer) {
+        let number = Pattern::range('0'..='9').many1();
+        let comma  = Pattern::char(',');
+        let close  = Pattern::char(']');
+        let end    = Pattern::eof();
+
+        let root_group_id = lexer.initial_state;
+        let root_group    = lexer.groups_mut().group_mut(root_group_id);
+
+        root_group.create_rule(&number,"self.on_number(reader)");
+        root_group.create_rule(&comma,"self.on_skip(reader)");
+        root_group.create_rule(&close,"self.on_close(reader)");
+        root_group.create_rule(&end,"self.on_skip(reader)");
+    }
+}
+
+
+// === Trait Impls ===
+
+impl flexer::Definition for InnerLexer {
+    fn define() -> Self {
+        let mut lexer = InnerLexer::new();
+        InnerLexer::rules_in_root(&mut lexer);
+        lexer
+    }
+
+    fn groups(&self) -> &Registry {
+        self.lexer.groups()
+    }
+
+    fn set_up(&mut self) {}
+
+    fn tear_down(&mut self) {}
+
+    fn reset(&mut self) {
+        self.lexer.reset()
+    }
+}
+
+impl Default for InnerLexer {
+    fn default() -> Self {
+        InnerLexer::new()
+    }
+}
+
+
+
+// ===================
+// === Inner State ===
+// ===================
+
+/// The stateful components of the inner lexer.
+#[derive(Debug)]
+pub struct InnerState {
+    /// The registry for groups in the lexer.
+    lexer_states:group::Registry,
+    /// The initial state of the lexer.
+    initial_state:group::Identifier,
+    /// The bookmarks for this lexer.
+    bookmarks:BookmarkManager
+}
+
+
+// === Trait Impls ===
+
+impl flexer::State for InnerState {
+    fn new(_logger:&impl AnyLogger) -> Self {
+        let mut lexer_states = group::Registry::default();
+        let initial_state    = lexer_states.define_group("ROOT",None);
+        let bookmarks        = BookmarkManager::new();
+        Self{lexer_states,initial_state,bookmarks}
+    }
+
+    fn initial_state(&self) -> group::Identifier {
+        self.initial_state
+    }
+
+    fn groups(&self) -> &group::Registry {
+        &self.lexer_states
+    }
+
+    fn groups_mut(&mut self) -> &mut group::Registry {
+        &mut self.lexer_states
+    }
+
+    fn bookmarks(&self) -> &BookmarkManager {
+        &self.bookmarks
+    }
+
+    fn bookmarks_mut(&mut self) -> &mut BookmarkManager {
+        &mut self.bookmarks
+    }
+
+    fn specialize(&self) -> Result<String,GenError> {
+        let options = generate::CodegenOptions::default().with_nestable(true);
+        generate::specialize_with(self,"InnerLexer","NumberStream",&options)
+    }
+}
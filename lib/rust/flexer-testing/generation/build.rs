@@ -1,26 +1,110 @@
 use std::fs::File;
 use std::io::prelude::*;
-use flexer_test_definition::TestLexer;
+use flexer_test_definition::anchored::AnchoredLexer;
+use flexer_test_definition::guarded::GuardedLexer;
+use flexer_test_definition::nested::InnerLexer;
+use flexer_test_definition::nested::InnerState;
+use flexer_test_definition::nested::OuterLexer;
+use flexer_test_definition::nested::OuterState;
+use flexer_test_definition::test_lexer::TestLexer;
+use flexer_test_definition::test_lexer::TestState;
 use flexer::Definition;
 use flexer::State;
+use flexer::generate;
+use flexer::generate::CodegenOptions;
+use flexer::generate::Encoding;
 
 
 
-/// Generates the lexer engine and saves the result into the file `src/engine.rs`.
+/// The path of the definition of the test lexer.
+const TEST_LEXER_PATH:&str = "../definition/src/test_lexer.rs";
+
+/// The path of the definitions of the outer and inner lexers.
+const NESTED_PATH:&str = "../definition/src/nested.rs";
+
+/// The path of the definition of the lexer with anchored rules.
+const ANCHORED_PATH:&str = "../definition/src/anchored.rs";
+
+/// The path of the definition of the lexer with guarded rules.
+const GUARDED_PATH:&str = "../definition/src/guarded.rs";
+
+/// Generates the lexer engine and saves the result into the file `src/generated/engine.rs`.
 ///
 /// The content of the generated file can be used with the `include!` macro.
 fn generate_engine() -> std::io::Result<()> {
-    let definition_path  = "../definition/src/lib.rs";
+    let lexer  = TestLexer::define();
+    let engine = lexer.specialize().unwrap();
+    write_engine(TEST_LEXER_PATH,"src/generated/engine.rs",&engine)
+}
+
+/// Generates the lexer engine running over UTF-8 input, and saves the result into the file
+/// `src/generated/engine_utf8.rs`.
+fn generate_utf8_engine() -> std::io::Result<()> {
+    let lexer            = TestLexer::define();
+    let state:&TestState = &lexer;
+    let engine           = generate::specialize_with_encoding
+        (state,"TestLexer","TokenStream",Encoding::Utf8).unwrap();
+    write_engine(TEST_LEXER_PATH,"src/generated/engine_utf8.rs",&engine)
+}
+
+/// Generates the lexer engine with rule coverage enabled, and saves the result into the file
+/// `src/generated/engine_coverage.rs`.
+fn generate_coverage_engine() -> std::io::Result<()> {
+    let lexer            = TestLexer::define();
+    let state:&TestState = &lexer;
+    let options          = CodegenOptions::default().with_coverage(true);
+    let engine           = generate::specialize_with(state,"TestLexer","TokenStream",&options);
+    write_engine(TEST_LEXER_PATH,"src/generated/engine_coverage.rs",&engine.unwrap())
+}
+
+/// Generates the lexer engine with profiling enabled, and saves the result into the file
+/// `src/generated/engine_profiling.rs`.
+fn generate_profiling_engine() -> std::io::Result<()> {
+    let lexer            = TestLexer::define();
+    let state:&TestState = &lexer;
+    let options          = CodegenOptions::default().with_profiling(true);
+    let engine           = generate::specialize_with(state,"TestLexer","TokenStream",&options);
+    write_engine(TEST_LEXER_PATH,"src/generated/engine_profiling.rs",&engine.unwrap())
+}
+
+/// Generates the engines of the outer lexer and of the inner lexer that it delegates to, and saves
+/// the result into the file `src/generated/engine_nested.rs`.
+fn generate_nested_engine() -> std::io::Result<()> {
+    let outer             = OuterLexer::define();
+    let outer:&OuterState = &outer;
+    let inner             = InnerLexer::define();
+    let inner:&InnerState = &inner;
+    let outer_engine      = outer.specialize().unwrap();
+    let inner_engine      = inner.specialize().unwrap();
+    write_engine(NESTED_PATH,"src/generated/engine_nested.rs",&(outer_engine + &inner_engine))
+}
+
+/// Generates the engine of the lexer with anchored rules, and saves the result into the file
+/// `src/generated/engine_anchored.rs`.
+fn generate_anchored_engine() -> std::io::Result<()> {
+    let lexer  = AnchoredLexer::define();
+    let engine = lexer.specialize().unwrap();
+    write_engine(ANCHORED_PATH,"src/generated/engine_anchored.rs",&engine)
+}
+
+/// Generates the engine of the lexer with guarded rules, and saves the result into the file
+/// `src/generated/engine_guarded.rs`.
+fn generate_guarded_engine() -> std::io::Result<()> {
+    let lexer  = GuardedLexer::define();
+    let engine = lexer.specialize().unwrap();
+    write_engine(GUARDED_PATH,"src/generated/engine_guarded.rs",&engine)
+}
+
+/// Writes the lexer definition at `definition_path` followed by its specialized `engine` to
+/// `output_path`.
+fn write_engine(definition_path:&str, output_path:&str, engine:&str) -> std::io::Result<()> {
     let output_directory = "src/generated";
     let _                = std::fs::create_dir(output_directory);
-    let output_path      = "src/generated/engine.rs";
     let definition_error = format!("The lexer definition should exist at {}.",definition_path);
     let output_error     = format!("Cannot open output file at {}.",output_path);
     let mut lexer_def    = File::open(definition_path).expect(definition_error.as_str());
     let mut contents     = String::new();
     let mut file         = File::create(output_path).expect(output_error.as_str());
-    let lexer            = TestLexer::define();
-    let engine           = lexer.specialize().unwrap();
     lexer_def.read_to_string(&mut contents).expect("Unable to read lexer definition.");
     file.write_all(contents.as_bytes()).expect("Unable to write lexer definition.");
     file.write_all(engine.as_bytes()).expect("Unable to write lexer specialization.");
@@ -28,5 +112,11 @@ fn generate_engine() -> std::io::Result<()> {
 }
 
 fn main() -> std::io::Result<()> {
-    generate_engine()
+    generate_engine()?;
+    generate_utf8_engine()?;
+    generate_coverage_engine()?;
+    generate_profiling_engine()?;
+    generate_nested_engine()?;
+    generate_anchored_engine()?;
+    generate_guarded_engine()
 }
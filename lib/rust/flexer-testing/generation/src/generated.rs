@@ -1,3 +1,9 @@
 //! This module serves to re-export the generated lexer.
 
 pub mod engine;
+pub mod engine_anchored;
+pub mod engine_coverage;
+pub mod engine_guarded;
+pub mod engine_nested;
+pub mod engine_profiling;
+pub mod engine_utf8;
@@ -0,0 +1,110 @@
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for a generated lexer with rules anchored to the starts and ends of
+//! lines.
+
+use flexer::prelude::*;
+
+use flexer::prelude::reader::decoder::DecoderUTF8;
+use flexer_test_generation::generated::engine_anchored::AnchoredLexer;
+use flexer_test_generation::generated::engine_anchored::Token;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Lex `input` from memory, asserting that lexing succeeds.
+fn lex(input:&str) -> Vec<Token> {
+    let mut lexer = AnchoredLexer::new();
+    let result    = lexer.run(StrReader::new(input));
+    assert_eq!(result.kind, flexer::ResultKind::Success);
+    result.tokens.tokens
+}
+
+/// Construct a word token.
+fn word(word:&str) -> Token {
+    Token::Word(word.into())
+}
+
+/// Construct a comment token.
+fn comment(comment:&str) -> Token {
+    Token::Comment(comment.into())
+}
+
+/// Construct an unrecognized token.
+fn unrecognized(text:&str) -> Token {
+    Token::Unrecognized(text.into())
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn test_line_start_at_start_of_input() {
+    assert_eq!(lex("# ab"), vec![comment("# ab")]);
+    assert_eq!(lex("  ab"), vec![Token::Indent(2), word("ab")]);
+}
+
+#[test]
+fn test_line_start_after_line_endings() {
+    let expected = vec![word("a"), comment("#b"), comment("#c"), comment("#d")];
+    assert_eq!(lex("a\n#b\r\n#c\r#d"), expected);
+    let expected = vec![word("a"), Token::Indent(1), word("b"), Token::Indent(3), word("ab")];
+    assert_eq!(lex("a\n b\r\n   ab"), expected);
+}
+
+#[test]
+fn test_line_start_within_line() {
+    let expected = vec![word("ab"), unrecognized("#"), word("b")];
+    assert_eq!(lex("ab #b"), expected);
+    assert_eq!(lex("ab  ba"), vec![word("ab"), word("ba")]);
+}
+
+#[test]
+fn test_line_end() {
+    let expected = vec![word("ab"), Token::Trailing(2), word("ba"), Token::Trailing(1)];
+    assert_eq!(lex("ab  \nba "), expected);
+    assert_eq!(lex("ab \r\nb"), vec![word("ab"), Token::Trailing(1), word("b")]);
+}
+
+#[test]
+fn test_blank_line() {
+    assert_eq!(lex("a\n   \nb"), vec![word("a"), Token::Indent(3), word("b")]);
+}
+
+#[test]
+fn test_anchors_across_buffer_refills() {
+    let input         = "ab  \n#c\n  ba\n".repeat(1000);
+    let mut lexer     = AnchoredLexer::new();
+    let reader        = Reader::new(input.as_bytes(), DecoderUTF8());
+    let result        = lexer.run(reader);
+    let mut expected  = vec![];
+    for _ in 0..1000 {
+        expected.extend(vec![word("ab"),Token::Trailing(2),comment("#c"),Token::Indent(2)]);
+        expected.push(word("ba"));
+    }
+    assert_eq!(result.kind, flexer::ResultKind::Success);
+    assert_eq!(result.tokens.tokens, expected);
+}
+
+#[test]
+fn test_reset() {
+    let mut lexer = AnchoredLexer::new();
+    let result    = lexer.run(StrReader::new("ab"));
+    assert_eq!(result.tokens.tokens, vec![word("ab")]);
+    lexer.reset();
+    let result    = lexer.run(StrReader::new("#c"));
+    assert_eq!(result.tokens.tokens, vec![comment("#c")]);
+}
@@ -0,0 +1,73 @@
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for the code generated for the flexer-testing lexer.
+//!
+//! The generated engine is compared against a snapshot, so that any change to the generated code
+//! shows up as a diff of `tests/snapshots/engine.rs.snap`. Run the tests with the environment
+//! variable `UPDATE_SNAPSHOTS` set to update the snapshot after an intended change.
+
+use flexer::Definition;
+use flexer::State;
+use flexer_test_definition::test_lexer::TestLexer;
+use std::path::PathBuf;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// The code generated by the build script for the flexer-testing lexer.
+const ENGINE:&str = include_str!("../src/generated/engine.rs");
+
+/// The path of the snapshot of the generated engine.
+fn snapshot_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/engine.rs.snap")
+}
+
+/// Describe the first point at which `actual` differs from `expected`, if any.
+///
+/// The generated code is printed on a single line, so the description shows a window of the
+/// code around the difference rather than the whole line.
+fn first_difference(expected:&str, actual:&str) -> Option<String> {
+    let offset = expected.chars().zip(actual.chars()).take_while(|(e,a)| e == a).count();
+    if offset == expected.chars().count() && offset == actual.chars().count() { return None }
+    let start  = offset.saturating_sub(40);
+    let window = |code:&str| code.chars().skip(start).take(80).collect::<String>();
+    let (expected,actual) = (window(expected),window(actual));
+    Some(format!("At character {}:\n  expected: {:?}\n  actual:   {:?}",offset,expected,actual))
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn generated_engine_matches_snapshot() {
+    let path = snapshot_path();
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path,ENGINE).unwrap();
+    }
+    let snapshot = std::fs::read_to_string(&path).unwrap();
+    if let Some(difference) = first_difference(&snapshot,ENGINE) {
+        panic!("The generated engine differs from {}.\n{}",path.display(),difference);
+    }
+}
+
+#[test]
+fn generated_engine_is_deterministic() {
+    let first  = TestLexer::define().specialize().unwrap();
+    let second = TestLexer::define().specialize().unwrap();
+    assert_eq!(first,second);
+    assert!(ENGINE.ends_with(&first));
+}
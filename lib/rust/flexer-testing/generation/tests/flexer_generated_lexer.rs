@@ -13,9 +13,12 @@
 use flexer::prelude::*;
 
 use flexer::prelude::reader::decoder::DecoderUTF8;
+use flexer_test_generation::generated::engine_coverage;
+use flexer_test_generation::generated::engine_profiling;
 use flexer_test_generation::generated::engine::TestLexer;
 use flexer_test_generation::generated::engine::Token;
 use flexer_test_generation::generated::engine::TokenStream;
+use flexer_test_generation::generated::engine_utf8;
 
 
 
@@ -24,11 +27,23 @@ use flexer_test_generation::generated::engine::TokenStream;
 // =============
 
 /// Executes the test on the provided input string slice.
+///
+/// The input is lexed as a stream, from memory, and as bytes by the UTF-8 engine, and the results
+/// must agree.
 fn run_test_on(str:impl AsRef<str>) -> TokenStream {
     // Hardcoded for ease of use here.
-    let reader     = Reader::new(str.as_ref().as_bytes(), DecoderUTF8());
-    let mut lexer  = TestLexer::new();
-    let run_result = lexer.run(reader);
+    let reader        = Reader::new(str.as_ref().as_bytes(), DecoderUTF8());
+    let mut lexer     = TestLexer::new();
+    let run_result    = lexer.run(reader);
+    let mut lexer     = TestLexer::new();
+    let shared_result = lexer.run(StrReader::new(str.as_ref()));
+    let mut lexer     = engine_utf8::TestLexer::new();
+    let utf8_result   = lexer.run(ByteReader::new(str.as_ref()));
+    assert_eq!(run_result.kind, shared_result.kind);
+    assert_eq!(run_result.tokens, shared_result.tokens);
+    assert_eq!(run_result.kind, utf8_result.kind);
+    // The engines define distinct, but identical, token types.
+    assert_eq!(format!("{:?}",run_result.tokens), format!("{:?}",utf8_result.tokens));
 
     match run_result.kind {
         flexer::ResultKind::Success => run_result.tokens,
@@ -108,3 +123,99 @@ fn test_end_invalid() {
     let result = run_test_on(input);
     assert_eq!(result, expected_output);
 }
+
+#[test]
+fn test_non_ascii_invalid() {
+    let input           = "aa λ😀 b";
+    let expected_output = TokenStream::from(vec![
+        Token::word("aa"),
+        Token::unrecognized(" "),
+        Token::unrecognized("λ"),
+        Token::unrecognized("😀"),
+        Token::unrecognized(" "),
+        Token::word("b"),
+    ]);
+    let result = run_test_on(input);
+    assert_eq!(result, expected_output);
+}
+
+#[test]
+fn test_state_stack_overflow() {
+    let input             = "aaaaa bbb";
+    let mut lexer         = TestLexer::new();
+    lexer.max_state_depth = 1;
+    let result            = lexer.run(StrReader::new(input));
+    let overflowed        = match result.error {
+        Some(flexer::LexingError::StateStackOverflow{max_depth:1,..}) => true,
+        _                                                            => false,
+    };
+    assert_eq!(result.kind, flexer::ResultKind::Failure);
+    assert!(overflowed);
+    assert_eq!(result.tokens, TokenStream::from(vec![Token::word("aaaaa")]));
+}
+
+#[test]
+fn test_rule_coverage() {
+    let mut lexer = engine_coverage::TestLexer::new();
+    let result    = lexer.run(StrReader::new("aaaaa bbb"));
+    assert_eq!(result.kind, flexer::ResultKind::Success);
+    let report = engine_coverage::TestLexer::rule_coverage();
+    let unhit  = report.unhit().into_iter().map(|rule| rule.rule.as_str()).collect_vec();
+    assert_eq!(report.rules.len(), 8);
+    assert_eq!(unhit, vec![
+        "group_0_rule_1",
+        "group_0_rule_2",
+        "group_0_rule_3",
+        "group_1_rule_0",
+        "group_1_rule_3",
+    ]);
+}
+
+#[test]
+fn test_profiling() {
+    let mut lexer = engine_profiling::TestLexer::new();
+    let result    = lexer.run(StrReader::new("aaaaa bbb"));
+    assert_eq!(result.kind, flexer::ResultKind::Success);
+    let root = lexer.profile.group("ROOT").unwrap();
+    let seen = lexer.profile.group("SEEN FIRST WORD").unwrap();
+    assert_eq!((root.characters, root.rules_fired), (5, 1));
+    assert_eq!((seen.characters, seen.rules_fired), (4, 2));
+    assert!(root.dfa_steps > root.characters);
+    assert!(seen.callback_time <= seen.step_time);
+    lexer.reset();
+    lexer.run(StrReader::new("aaaaa bbb"));
+    assert_eq!(lexer.profile.group("ROOT").unwrap().rules_fired, 2);
+}
+
+
+
+// ==================
+// === Benchmarks ===
+// ==================
+
+extern crate test;
+use test::Bencher;
+
+/// Many small inputs, as when lexing each file of a project.
+fn bench_files() -> Vec<String> {
+    (0..1000).map(|ix| vec!["aaaa bb";ix % 8 + 1].join(" ")).collect()
+}
+
+#[bench]
+fn bench_run_files_new(bencher:&mut Bencher) {
+    let files = bench_files();
+    bencher.iter(|| files.iter().map(|file| {
+        let mut lexer = TestLexer::new();
+        lexer.run(StrReader::new(file.as_str()))
+    }).collect_vec());
+}
+
+#[bench]
+fn bench_run_files_reset(bencher:&mut Bencher) {
+    let files     = bench_files();
+    let mut lexer = TestLexer::new();
+    bencher.iter(|| files.iter().map(|file| {
+        lexer.reset();
+        lexer.run(StrReader::new(file.as_str()))
+    }).collect_vec());
+}
@@ -0,0 +1,88 @@
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for a generated lexer with guarded rules.
+
+use flexer::prelude::*;
+
+use flexer::Definition;
+use flexer_test_generation::generated::engine_guarded::GuardedLexer;
+use flexer_test_generation::generated::engine_guarded::Token;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Lex `input` from memory, asserting that lexing succeeds.
+fn lex(input:&str) -> Vec<Token> {
+    let mut lexer = GuardedLexer::new();
+    let result    = lexer.run(StrReader::new(input));
+    assert_eq!(result.kind, flexer::ResultKind::Success);
+    result.tokens.tokens
+}
+
+/// Construct a word token.
+fn word(word:&str) -> Token {
+    Token::Word(word.into())
+}
+
+/// Construct a letter token.
+fn letter(letter:&str) -> Token {
+    Token::Letter(letter.into())
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn test_guards_hold() {
+    assert_eq!(lex("ab"), vec![word("ab")]);
+    assert_eq!(lex("ba"), vec![word("ba")]);
+    assert_eq!(lex("aab"), vec![word("aab")]);
+    assert_eq!(lex("aa"), vec![letter("a"), letter("a")]);
+}
+
+#[test]
+fn test_fallback_to_lower_priority_match() {
+    assert_eq!(lex("!ba"), vec![Token::Reversed("ba".into())]);
+}
+
+#[test]
+fn test_fallback_to_shorter_match() {
+    assert_eq!(lex("!ab"), vec![letter("a"), letter("b")]);
+    assert_eq!(lex("!abb"), vec![letter("a"), letter("b"), letter("b")]);
+}
+
+#[test]
+fn test_fallback_to_shorter_match_at_rule_bookmark() {
+    let expected = vec![letter("a"), letter("a"), letter("b")];
+    assert_eq!(lex("!aab"), expected);
+}
+
+#[test]
+fn test_guards_switched_during_input() {
+    let expected = vec![word("ab"), letter("a"), letter("b"), Token::Reversed("ba".into())];
+    assert_eq!(lex("ab!ab!!ba"), expected);
+    assert_eq!(lex("!ab!aab"), vec![letter("a"), letter("b"), word("aab")]);
+}
+
+#[test]
+fn test_reset() {
+    let mut lexer = GuardedLexer::new();
+    let result    = lexer.run(StrReader::new("!ab"));
+    assert_eq!(result.tokens.tokens, vec![letter("a"), letter("b")]);
+    lexer.reset();
+    let result    = lexer.run(StrReader::new("ab"));
+    assert_eq!(result.tokens.tokens, vec![word("ab")]);
+}
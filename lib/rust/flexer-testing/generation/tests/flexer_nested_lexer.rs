@@ -0,0 +1,79 @@
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for a generated lexer that delegates part of its input to another.
+
+use flexer::prelude::*;
+
+use flexer::prelude::reader::decoder::DecoderUTF8;
+use flexer_test_generation::generated::engine_nested::NumberStream;
+use flexer_test_generation::generated::engine_nested::OuterLexer;
+use flexer_test_generation::generated::engine_nested::Token;
+use flexer_test_generation::generated::engine_nested::TokenStream;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Lex `input` from memory, asserting that lexing succeeds.
+fn lex(input:&str) -> Vec<Token> {
+    let mut lexer = OuterLexer::new();
+    let result    = lexer.run(StrReader::new(input));
+    assert_eq!(result.kind, flexer::ResultKind::Success);
+    result.tokens.tokens
+}
+
+/// Construct a token for the `numbers` lexed by the inner lexer.
+fn numbers(numbers:&[u32]) -> Token {
+    Token::Numbers(NumberStream{numbers:numbers.to_vec()})
+}
+
+/// Construct a word token.
+fn word(word:&str) -> Token {
+    Token::Word(word.into())
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn test_delegated_region() {
+    let expected = vec![word("ab"), numbers(&[1, 23]), word("ba")];
+    assert_eq!(lex("ab [1,23] ba"), expected);
+}
+
+#[test]
+fn test_repeated_delegation() {
+    let expected = vec![numbers(&[1]), word("a"), numbers(&[]), numbers(&[2, 3])];
+    assert_eq!(lex("[1] a [] [2,3]"), expected);
+}
+
+#[test]
+fn test_delegation_until_end_of_input() {
+    assert_eq!(lex("ab [4,5"), vec![word("ab"), numbers(&[4, 5])]);
+}
+
+#[test]
+fn test_delegation_across_buffer_refills() {
+    let input         = "ab [12,345] ba ".repeat(1000);
+    let mut lexer     = OuterLexer::new();
+    let reader        = Reader::new(input.as_bytes(), DecoderUTF8());
+    let result        = lexer.run(reader);
+    let mut expected  = TokenStream::default();
+    for _ in 0..1000 {
+        expected.tokens.extend(vec![word("ab"), numbers(&[12, 345]), word("ba")]);
+    }
+    assert_eq!(result.kind, flexer::ResultKind::Success);
+    assert_eq!(result.tokens, expected);
+}
@@ -7,3 +7,4 @@ pub mod nfa;
 pub mod pattern;
 pub mod state;
 pub mod symbol;
+pub mod utf8;
@@ -1,9 +1,12 @@
 //! The structure for defining deterministic finite automata.
 
 use crate::automata::alphabet;
+use crate::automata::state::AnchoredLink;
 use crate::automata::state;
 use crate::data::matrix::Matrix;
 
+use std::collections::BTreeMap;
+
 
 
 // =====================================
@@ -43,6 +46,13 @@ pub struct DFA {
     pub links:Matrix<state::Identifier>,
     /// A collection of callbacks for each state (indexable in order)
     pub callbacks:Vec<Option<RuleExecutable>>,
+    /// The transitions that trigger without consuming a symbol where their anchor holds, for each
+    /// state that has any.
+    ///
+    /// The target of such a transition is the state that the DFA is in after crossing the anchored
+    /// links of the NFA from its source state, which it keeps alongside the source state's own
+    /// NFA states.
+    pub anchored_links:BTreeMap<state::Identifier,Vec<AnchoredLink>>,
 }
 
 impl DFA {
@@ -56,6 +66,58 @@ impl DFA {
     pub fn has_rule_for(&self, target_state:state::Identifier) -> bool {
         self.callbacks.get(target_state.id).unwrap().is_some()
     }
+
+    /// The anchored transitions from `state`, in the order in which they are tested.
+    pub fn anchored_links_from(&self, state:state::Identifier) -> &[AnchoredLink] {
+        self.anchored_links.get(&state).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Makes the rules of each state, where they may all be rejected by their guards, fall back to
+    /// the rules of the shorter match that the DFA accepted on its way into that state.
+    ///
+    /// That match ends where the DFA last moved out of a state with rules of its own into a state
+    /// with different rules. A state only falls back to it if it is accepted by the same rules on
+    /// every path into the state, and if the state cannot be entered through an anchored link.
+    pub fn fall_back_to_shorter_matches(&mut self) {
+        let initial     = state::Identifier::new(0);
+        let mut shorter = BTreeMap::<state::Identifier,Option<RuleExecutable>>::new();
+        let mut pending = vec![initial];
+        shorter.insert(initial,None);
+        while let Some(source) = pending.pop() {
+            let mut entered = Vec::new();
+            for ix in 0..self.links.columns() {
+                let target = self.links[(source.id,ix)];
+                if target != state::Identifier::INVALID {
+                    let accepted = match &self.callbacks[source.id] {
+                        Some(rules) if self.callbacks[target.id].as_ref() != Some(rules) =>
+                            Some(rules.clone()),
+                        _ => shorter.get(&source).cloned().unwrap_or(None),
+                    };
+                    entered.push((target,accepted));
+                }
+            }
+            for link in self.anchored_links_from(source) {
+                entered.push((link.target_state,None));
+            }
+            for (target,accepted) in entered {
+                let joined = match shorter.get(&target) {
+                    None           => accepted,
+                    Some(previous) => if *previous == accepted { accepted } else { None },
+                };
+                if shorter.get(&target) != Some(&joined) {
+                    shorter.insert(target,joined);
+                    pending.push(target);
+                }
+            }
+        }
+        for (state,accepted) in shorter {
+            if let (Some(rules),Some(accepted)) = (self.callbacks[state.id].as_ref(),accepted) {
+                if rules.may_reject() {
+                    self.callbacks[state.id] = Some(rules.clone().with_shorter_fallback(accepted));
+                }
+            }
+        }
+    }
 }
 
 
@@ -92,13 +154,76 @@ pub struct RuleExecutable {
     pub priority:usize,
     /// The rust code that will be executed when running this callback.
     pub code:String,
+    /// The rust code that decides whether this callback applies, if it is guarded.
+    pub guard:Option<String>,
+    /// The callback to try instead when the guard does not hold.
+    ///
+    /// It is the callback of the next rule, in priority order, that accepts the same input, or
+    /// otherwise the callback of a rule that accepts a shorter match.
+    pub fallback:Option<Box<RuleExecutable>>,
+    /// Whether this callback applies to the shorter match that ends at the rule bookmark, rather
+    /// than to the input matched so far. The rest of its chain of fallbacks applies to that match
+    /// too.
+    pub shorter:bool,
 }
 
 impl RuleExecutable {
     /// Creates a new rule executable with the provided `priority` and `code`.
     pub fn new(priority:usize, code_str:impl Into<String>) -> RuleExecutable {
-        let code = code_str.into();
-        RuleExecutable{priority,code}
+        let code     = code_str.into();
+        let guard    = None;
+        let fallback = None;
+        let shorter  = false;
+        RuleExecutable{priority,code,guard,fallback,shorter}
+    }
+
+    /// Updater for field `guard`. Returns updated executable.
+    pub fn guarded_by(mut self, guard:impl Into<String>, fallback:Option<RuleExecutable>) -> Self {
+        self.guard    = Some(guard.into());
+        self.fallback = fallback.map(Box::new);
+        self
+    }
+
+    /// Makes the chain of callbacks fall back to the chain of `shorter`, which applies to the
+    /// shorter match that ends at the rule bookmark, if none of its own guards hold.
+    pub fn with_shorter_fallback(mut self, mut shorter:RuleExecutable) -> Self {
+        self.fallback = match (&self.guard,self.fallback.take()) {
+            (None,fallback)          => fallback,
+            (Some(_),Some(fallback)) => Some(Box::new(fallback.with_shorter_fallback(shorter))),
+            (Some(_),None)           => {
+                shorter.shorter = true;
+                Some(Box::new(shorter))
+            },
+        };
+        self
+    }
+
+    /// The chain of callbacks without its fallback to the callbacks of a shorter match.
+    pub fn without_shorter_fallback(&self) -> Self {
+        let mut executable = self.clone();
+        executable.fallback = match &self.fallback {
+            Some(fallback) if !fallback.shorter => Some(fallback.without_shorter_fallback()),
+            _                                   => None,
+        }.map(Box::new);
+        executable
+    }
+
+    /// Whether the guards of every callback in the chain, up to any fallback to the callbacks of a
+    /// shorter match, may reject the input.
+    pub fn may_reject(&self) -> bool {
+        match (&self.guard,&self.fallback) {
+            (None,_)                 => false,
+            (Some(_),None)           => true,
+            (Some(_),Some(fallback)) => fallback.shorter || fallback.may_reject(),
+        }
+    }
+
+    /// Whether the chain of callbacks falls back to the callbacks of a shorter match.
+    pub fn has_shorter_fallback(&self) -> bool {
+        match &self.fallback {
+            None           => false,
+            Some(fallback) => fallback.shorter || fallback.has_shorter_fallback(),
+        }
     }
 }
 
@@ -123,8 +248,9 @@ pub mod tests {
             links:Matrix::from(vec![vec![INVALID,1,INVALID], vec![INVALID,INVALID,INVALID]]),
             callbacks:vec![
                 None,
-                Some(RuleExecutable {priority:2, code:"group_0_rule_0".into()}),
+                Some(RuleExecutable::new(2,"group_0_rule_0")),
             ],
+            anchored_links:BTreeMap::new(),
         }
     }
 
@@ -135,8 +261,9 @@ pub mod tests {
             links:Matrix::from(vec![vec![INVALID,1,INVALID], vec![INVALID,INVALID,INVALID]]),
             callbacks:vec![
                 None,
-                Some(RuleExecutable {priority:2, code:"group_0_rule_0".into()}),
+                Some(RuleExecutable::new(2,"group_0_rule_0")),
             ],
+            anchored_links:BTreeMap::new(),
         }
     }
 
@@ -151,9 +278,10 @@ pub mod tests {
             ]),
             callbacks:vec![
                 None,
-                Some(RuleExecutable {priority:3, code:"group_0_rule_0".into()}),
-                Some(RuleExecutable {priority:3, code:"group_0_rule_0".into()}),
+                Some(RuleExecutable::new(3,"group_0_rule_0")),
+                Some(RuleExecutable::new(3,"group_0_rule_0")),
             ],
+            anchored_links:BTreeMap::new(),
         }
     }
 
@@ -169,10 +297,11 @@ pub mod tests {
             ]),
             callbacks:vec![
                 None,
-                Some(RuleExecutable {priority:4, code:"group_0_rule_1".into()}),
-                Some(RuleExecutable {priority:4, code:"group_0_rule_0".into()}),
-                Some(RuleExecutable {priority:4, code:"group_0_rule_1".into()}),
+                Some(RuleExecutable::new(4,"group_0_rule_1")),
+                Some(RuleExecutable::new(4,"group_0_rule_0")),
+                Some(RuleExecutable::new(4,"group_0_rule_1")),
             ],
+            anchored_links:BTreeMap::new(),
         }
     }
 }
@@ -3,7 +3,9 @@
 use crate::automata::alphabet;
 use crate::automata::dfa::DFA;
 use crate::automata::dfa::RuleExecutable;
+use crate::automata::pattern::Anchor;
 use crate::automata::pattern::Pattern;
+use crate::automata::state::AnchoredLink;
 use crate::automata::state::State;
 use crate::automata::state::Transition;
 use crate::automata::state;
@@ -11,8 +13,8 @@ use crate::automata::symbol::Symbol;
 use crate::data::matrix::Matrix;
 
 use itertools::Itertools;
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
-use std::collections::HashMap;
 use std::ops::RangeInclusive;
 
 use crate::prelude::*;
@@ -66,6 +68,15 @@ impl NFA {
         self.states[source.id].epsilon_links.push(target);
     }
 
+    /// Creates an epsilon transition between two states that only triggers where `anchor` holds.
+    ///
+    /// Whenever the automaton happens to be in `source` state at such a position in the input, it
+    /// can immediately transition to the `target` state.
+    pub fn connect_anchored
+    (&mut self, source:state::Identifier, target_state:state::Identifier, anchor:Anchor) {
+        self.states[source.id].anchored_links.push(AnchoredLink{anchor,target_state});
+    }
+
     /// Creates an ordinary transition for a range of symbols.
     ///
     /// If any symbol from such range happens to be the input when the automaton is in the `source`
@@ -113,7 +124,20 @@ impl NFA {
                 }
                 end
             },
+            Pattern::Lazy(body) => {
+                let first = self.states.len();
+                let end   = self.new_pattern(current,body);
+                for id in (first..self.states.len()).filter(|&id| id != end.id) {
+                    self.states[id].lazy = true;
+                }
+                end
+            },
             Pattern::Always => current,
+            Pattern::Anchor(anchor) => {
+                let state = self.new_state();
+                self.connect_anchored(current,state,*anchor);
+                state
+            },
         }
     }
 
@@ -147,6 +171,69 @@ impl NFA {
         states
     }
 
+    /// Computes the set of states that the automaton can be in after crossing any of the anchored
+    /// links for `anchor` from the `states`, including the `states` themselves.
+    ///
+    /// Links are crossed repeatedly, as an anchor that holds at a position keeps holding there.
+    fn anchor_closure
+    (&self, eps_mat:&[StateSetId], states:&StateSetId, anchor:Anchor) -> StateSetId {
+        let mut closure = states.clone();
+        let mut pending = states.iter().copied().collect_vec();
+        while let Some(state) = pending.pop() {
+            for link in &self.states[state.id].anchored_links {
+                if link.anchor == anchor {
+                    for &target in &eps_mat[link.target_state.id] {
+                        if closure.insert(target) {
+                            pending.push(target);
+                        }
+                    }
+                }
+            }
+        }
+        closure
+    }
+
+    /// Computes, for each [lazy](State::lazy) state, the named states that can be reached from it.
+    ///
+    /// These are the states in which the rules that the lazy state is part of have matched.
+    fn lazy_owners(&self) -> BTreeMap<state::Identifier,StateSetId> {
+        let lazy_states = self.states.iter().enumerate().filter(|(_,state)| state.lazy);
+        lazy_states.map(|(id,_)| {
+            let source      = state::Identifier::new(id);
+            let mut visited = StateSetId::new();
+            let mut pending = vec![source];
+            let mut owners  = StateSetId::new();
+            while let Some(state) = pending.pop() {
+                if visited.insert(state) {
+                    let state_data = &self.states[state.id];
+                    if state_data.name.is_some() {
+                        owners.insert(state);
+                    }
+                    pending.extend(state_data.epsilon_links.iter().copied());
+                    pending.extend(state_data.links.iter().map(|link| link.target_state));
+                    pending.extend(state_data.anchored_links.iter().map(|link| link.target_state));
+                }
+            }
+            (source,owners)
+        }).collect()
+    }
+
+    /// Removes from the `states` the [lazy](State::lazy) states of every rule that has matched in
+    /// them, so that lazy patterns stop consuming input once their rule has matched.
+    fn prune_lazy
+    (&self, owners:&BTreeMap<state::Identifier,StateSetId>, states:StateSetId) -> StateSetId {
+        let is_named = |state:&&state::Identifier| self.states[state.id].name.is_some();
+        let matched  = states.iter().filter(is_named).collect_vec();
+        if matched.is_empty() {
+            return states
+        }
+        let has_matched = |state:&state::Identifier| match owners.get(state) {
+            Some(rules) => matched.iter().any(|&rule| rules.contains(rule)),
+            None        => false,
+        };
+        states.iter().copied().filter(|state| !has_matched(state)).collect()
+    }
+
     /// Computes a transition matrix `(state, symbol) => state` for the NFA, ignoring epsilon links.
     fn nfa_matrix(&self) -> Matrix<state::Identifier> {
         let mut matrix = Matrix::new(self.states.len(),self.alphabet_segmentation.divisions.len());
@@ -159,6 +246,26 @@ impl NFA {
         }
         matrix
     }
+
+    /// Computes the callback for a DFA state that accepts through the `named` states, which are
+    /// ordered by the priority of their rules.
+    ///
+    /// The callback of a guarded state falls back to the callback of the next state, so that the
+    /// chain ends at the first unguarded state.
+    fn rule_executable
+    (&self, named:&[state::Identifier], priority:usize) -> Option<RuleExecutable> {
+        let (first,rest) = named.split_first()?;
+        let state        = &self.states[first.id];
+        let code         = state.name.as_ref().cloned().unwrap();
+        let executable   = RuleExecutable::new(priority,code);
+        match state.guard_name() {
+            None        => Some(executable),
+            Some(guard) => {
+                let fallback = self.rule_executable(rest,priority);
+                Some(executable.guarded_by(guard,fallback))
+            }
+        }
+    }
 }
 
 
@@ -170,14 +277,33 @@ impl From<&NFA> for DFA {
     /// [here](https://www.youtube.com/watch?v=taClnxU-nao).
     /// The asymptotic complexity is quadratic in number of states.
     fn from(nfa:&NFA) -> Self {
-        let     nfa_mat     = nfa.nfa_matrix();
-        let     eps_mat     = nfa.eps_matrix();
-        let mut dfa_mat     = Matrix::new(0,nfa.alphabet_segmentation.divisions.len());
-        let mut dfa_eps_ixs = Vec::<StateSetId>::new();
-        let mut dfa_eps_map = HashMap::<StateSetId,state::Identifier>::new();
+        fn intern
+        ( dfa_eps_ixs : &mut Vec<StateSetId>
+        , dfa_eps_map : &mut BTreeMap<StateSetId,state::Identifier>
+        , eps_set     : StateSetId
+        ) -> state::Identifier {
+            match dfa_eps_map.get(&eps_set) {
+                Some(&id) => id,
+                None => {
+                    let id = state::Identifier::new(dfa_eps_ixs.len());
+                    dfa_eps_ixs.push(eps_set.clone());
+                    dfa_eps_map.insert(eps_set,id);
+                    id
+                },
+            }
+        }
 
-        dfa_eps_ixs.push(eps_mat[0].clone());
-        dfa_eps_map.insert(eps_mat[0].clone(),state::Identifier::from(0));
+        let     nfa_mat        = nfa.nfa_matrix();
+        let     eps_mat        = nfa.eps_matrix();
+        let     lazy_owners    = nfa.lazy_owners();
+        let     initial        = nfa.prune_lazy(&lazy_owners,eps_mat[0].clone());
+        let mut dfa_mat        = Matrix::new(0,nfa.alphabet_segmentation.divisions.len());
+        let mut dfa_eps_ixs    = Vec::<StateSetId>::new();
+        let mut dfa_eps_map    = BTreeMap::<StateSetId,state::Identifier>::new();
+        let mut anchored_links = BTreeMap::<state::Identifier,Vec<AnchoredLink>>::new();
+
+        dfa_eps_ixs.push(initial.clone());
+        dfa_eps_map.insert(initial,state::Identifier::from(0));
 
         let mut i = 0;
         while i < dfa_eps_ixs.len()  {
@@ -190,16 +316,19 @@ impl From<&NFA> for DFA {
                         eps_set.extend(eps_mat[tgt.id].iter());
                     }
                 }
+                let eps_set = nfa.prune_lazy(&lazy_owners,eps_set);
                 if !eps_set.is_empty() {
-                    dfa_mat[(i,voc_ix)] = match dfa_eps_map.get(&eps_set) {
-                        Some(&id) => id,
-                        None => {
-                            let id = state::Identifier::new(dfa_eps_ixs.len());
-                            dfa_eps_ixs.push(eps_set.clone());
-                            dfa_eps_map.insert(eps_set,id);
-                            id
-                        },
-                    };
+                    dfa_mat[(i,voc_ix)] = intern(&mut dfa_eps_ixs,&mut dfa_eps_map,eps_set);
+                }
+            }
+            for &anchor in &Anchor::ALL {
+                let eps_set = nfa.anchor_closure(&eps_mat,&dfa_eps_ixs[i],anchor);
+                let eps_set = nfa.prune_lazy(&lazy_owners,eps_set);
+                if eps_set != dfa_eps_ixs[i] {
+                    let target_state = intern(&mut dfa_eps_ixs,&mut dfa_eps_map,eps_set);
+                    let source       = state::Identifier::new(i);
+                    let link         = AnchoredLink{anchor,target_state};
+                    anchored_links.entry(source).or_default().push(link);
                 }
             }
             i += 1;
@@ -208,17 +337,15 @@ impl From<&NFA> for DFA {
         let mut callbacks = vec![None; dfa_eps_ixs.len()];
         let     priority  = dfa_eps_ixs.len();
         for (dfa_ix, epss) in dfa_eps_ixs.into_iter().enumerate() {
-            let has_name = |&key:&state::Identifier| nfa.states[key.id].name.is_some();
-            if let Some(eps) = epss.into_iter().find(has_name) {
-                let code          = nfa.states[eps.id].name.as_ref().cloned().unwrap();
-                callbacks[dfa_ix] = Some(RuleExecutable {code,priority});
-            }
+            let has_name = |key:&state::Identifier| nfa.states[key.id].name.is_some();
+            let named    = epss.into_iter().filter(has_name).collect_vec();
+            callbacks[dfa_ix] = nfa.rule_executable(&named,priority);
         }
 
         let alphabet_segmentation = nfa.alphabet_segmentation.clone();
         let links = dfa_mat;
 
-        DFA{alphabet_segmentation,links,callbacks}
+        DFA{alphabet_segmentation,links,callbacks,anchored_links}
     }
 }
 
@@ -233,6 +360,7 @@ pub mod tests {
     extern crate test;
 
     use crate::automata::dfa;
+    use crate::group;
 
     use super::*;
     use test::Bencher;
@@ -303,6 +431,40 @@ pub mod tests {
         }
     }
 
+    /// NFA for a group with a rule for each of the `patterns`, in order of priority.
+    fn nfa_for_rules(patterns:&[Pattern]) -> NFA {
+        let mut registry = group::Registry::default();
+        let root         = registry.define_group("ROOT",None);
+        for pattern in patterns {
+            registry.group_mut(root).create_rule(pattern,"self.on_match(reader)");
+        }
+        registry.to_nfa_from(root)
+    }
+
+    /// Find the longest prefix of the `symbols` that the `dfa` accepts, returning its length and
+    /// the index of the rule that matches it.
+    fn longest_match(dfa:&DFA, symbols:impl Iterator<Item=u32>) -> Option<(usize,usize)> {
+        let rule_in     = |state:state::Identifier| dfa.callbacks[state.id].as_ref().map(|rule| {
+            rule.code.rsplit('_').next().unwrap().parse().unwrap()
+        });
+        let mut state   = state::Identifier::new(0);
+        let mut matched = rule_in(state).map(|rule| (0,rule));
+        for (length,symbol) in symbols.enumerate() {
+            let divisions = dfa.alphabet_segmentation.divisions.range(..=Symbol::from(symbol));
+            state         = dfa.links[(state.id,divisions.count() - 1)];
+            if state == state::Identifier::INVALID {
+                break
+            }
+            matched = rule_in(state).map(|rule| (length + 1,rule)).or(matched);
+        }
+        matched
+    }
+
+    /// Find the longest prefix of `input` that the rules for the `patterns` match.
+    fn match_prefix(patterns:&[Pattern], input:&str) -> Option<(usize,usize)> {
+        longest_match(&DFA::from(&nfa_for_rules(patterns)),input.chars().map(u32::from))
+    }
+
     #[test]
     fn test_to_dfa_newline() {
         assert_eq!(DFA::from(&newline()),dfa::tests::newline());
@@ -323,6 +485,76 @@ pub mod tests {
         assert_eq!(DFA::from(&letter_and_spaces()),dfa::tests::letter_and_spaces());
     }
 
+    #[test]
+    fn test_to_dfa_anchored() {
+        let mut nfa   = NFA::default();
+        let start     = nfa.new_state();
+        let pattern   = Pattern::line_start() >> Pattern::char('#') >> Pattern::line_end();
+        let end       = nfa.new_pattern(start,&pattern);
+        nfa.states[end.id].name = Some("group_0_rule_0".into());
+        let dfa       = DFA::from(&nfa);
+        let initial   = state::Identifier::new(0);
+        let links     = dfa.anchored_links_from(initial);
+        assert_eq!(links.iter().map(|link| link.anchor).collect_vec(),vec![Anchor::LineStart]);
+        let line      = links[0].target_state;
+        assert!(dfa.anchored_links_from(line).is_empty());
+        let mut row   = (0..dfa.links.columns()).map(|column| dfa.links[(line.id,column)]);
+        let hash      = row.find(|&target| target != state::Identifier::INVALID).unwrap();
+        let end_links = dfa.anchored_links_from(hash);
+        assert_eq!(end_links.iter().map(|link| link.anchor).collect_vec(),vec![Anchor::LineEnd]);
+        let end       = end_links[0].target_state;
+        assert_eq!(dfa.callbacks[hash.id],None);
+        assert_eq!(dfa.callbacks[end.id],Some(RuleExecutable::new(4,"group_0_rule_0")));
+    }
+
+    #[test]
+    fn test_lazy_repetition() {
+        let quote  = Pattern::char('"');
+        let string = [&quote >> Pattern::any().many_lazy() >> &quote];
+        assert_eq!(match_prefix(&string,r#""a" "b""#),Some((3,0)));
+        assert_eq!(match_prefix(&string,r#""" """#),Some((2,0)));
+        assert_eq!(match_prefix(&string,r#""ab"#),None);
+        let comment = Pattern::all_of("/*") >> Pattern::any().many_lazy() >> Pattern::all_of("*/");
+        assert_eq!(match_prefix(&[comment],"/* a */ b */"),Some((7,0)));
+        let greedy = &quote >> Pattern::any().many() >> &quote;
+        assert_eq!(match_prefix(&[greedy],r#""a" "b""#),Some((7,0)));
+    }
+
+    #[test]
+    fn test_lazy_repetition_before_greedy_repetition() {
+        let pattern = [Pattern::char('a').many_lazy() >> Pattern::char('b').many1()];
+        assert_eq!(match_prefix(&pattern,"aabbb"),Some((5,0)));
+        assert_eq!(match_prefix(&pattern,"aaa"),None);
+        let pattern = Pattern::char('a').many_lazy() >> Pattern::char('b').many();
+        assert_eq!(match_prefix(&[pattern],"aabbb"),Some((0,0)));
+    }
+
+    #[test]
+    fn test_lazy_repetition_leaves_other_rules() {
+        let quote  = Pattern::char('"');
+        let lazy   = &quote >> Pattern::any().many_lazy() >> &quote;
+        let greedy = &quote >> Pattern::any().many() >> &quote;
+        assert_eq!(match_prefix(&[lazy,greedy],r#""a" "b""#),Some((7,1)));
+    }
+
+    #[test]
+    fn test_lazy_option() {
+        let optional = Pattern::char('a') >> Pattern::char('b').opt_lazy();
+        assert_eq!(match_prefix(&[optional],"ab"),Some((1,0)));
+        let required = [Pattern::char('a') >> Pattern::char('b').opt_lazy() >> Pattern::char('c')];
+        assert_eq!(match_prefix(&required,"abc"),Some((3,0)));
+        assert_eq!(match_prefix(&required,"ac"),Some((2,0)));
+    }
+
+    #[test]
+    fn test_lazy_repetition_in_utf8() {
+        let quote  = Pattern::char('"');
+        let string = &quote >> Pattern::any().many_lazy() >> &quote;
+        let dfa    = DFA::from(&nfa_for_rules(&[string]).to_utf8());
+        let input  = "\"ł\" \"".bytes().map(u32::from);
+        assert_eq!(longest_match(&dfa,input),Some((4,0)));
+    }
+
     #[bench]
     fn bench_to_dfa_newline(bencher:&mut Bencher) {
         bencher.iter(|| DFA::from(&newline()))
@@ -30,8 +30,13 @@ pub enum Pattern {
     Seq(Vec<Pattern>),
     /// The pattern that triggers on 0..N repetitions of given pattern.
     Many(Box<Pattern>),
+    /// The pattern that triggers on the given pattern, but that stops consuming input as soon as
+    /// the rule that it is part of has matched.
+    Lazy(Box<Pattern>),
     /// The pattern that always triggers.
     Always,
+    /// The pattern that triggers, without consuming a symbol, when the anchor holds.
+    Anchor(Anchor),
 }
 
 impl Pattern {
@@ -46,6 +51,16 @@ impl Pattern {
         Pattern::Always
     }
 
+    /// A pattern that triggers at the start of a line, without consuming any input.
+    pub fn line_start() -> Self {
+        Pattern::Anchor(Anchor::LineStart)
+    }
+
+    /// A pattern that triggers at the end of a line, without consuming any input.
+    pub fn line_end() -> Self {
+        Pattern::Anchor(Anchor::LineEnd)
+    }
+
     /// A pattern that triggers on any character.
     pub fn any() -> Self {
         Pattern::symbols(Symbol::from(0)..=Symbol::from(u32::max_value()))
@@ -66,6 +81,26 @@ impl Pattern {
         self.clone() | Self::always()
     }
 
+    /// A pattern that triggers on 0..N repetitions of the pattern described by `self`, but that
+    /// repeats it only until the rule that it is part of matches.
+    ///
+    /// Of the inputs that a rule matches, the lexer usually takes the longest. The repetitions of
+    /// a lazy pattern are instead cut short at the first point at which the whole rule matches, so
+    /// that `c!('"') >> Pattern::any().many_lazy() >> c!('"')` matches `"a"` rather than all of
+    /// `"a" "b"`. Parts of the rule that follow the lazy pattern, and other rules, may still
+    /// consume more input.
+    pub fn many_lazy(&self) -> Self {
+        Lazy(Box::new(self.many()))
+    }
+
+    /// A pattern that triggers on 0..=1 repetitions of the pattern described by `self`, but that
+    /// only triggers on `self` if the rule that it is part of does not match without it.
+    ///
+    /// See [`Pattern::many_lazy`] for how lazy patterns stop consuming input.
+    pub fn opt_lazy(&self) -> Self {
+        Lazy(Box::new(self.opt()))
+    }
+
     /// A pattern that triggers on the given character.
     pub fn char(character:char) -> Self {
         Self::symbol(Symbol::from(character))
@@ -136,6 +171,16 @@ impl Pattern {
     pub fn repeat_between(pat:Pattern, min:usize, max:usize) -> Self {
         (min..max).fold(Self::never(),|p,n| p | Self::repeat(pat.clone(),n))
     }
+
+    /// Check whether the pattern contains an [`Anchor`].
+    pub fn has_anchor(&self) -> bool {
+        match self {
+            Range(_) | Always            => false,
+            Pattern::Anchor(_)           => true,
+            Or(patterns) | Seq(patterns) => patterns.iter().any(Pattern::has_anchor),
+            Many(body) | Lazy(body)      => body.has_anchor(),
+        }
+    }
 }
 
 
@@ -169,6 +214,30 @@ gen_ref_versions!(Pattern,Shr,shr);
 
 
 
+// ==============
+// === Anchor ===
+// ==============
+
+/// A zero-width assertion about the position of the lexer in its input.
+///
+/// An anchor consumes no input, and holds based only on the character preceding the current
+/// position and the character at it.
+#[derive(Clone,Copy,Debug,Eq,Hash,Ord,PartialEq,PartialOrd)]
+pub enum Anchor {
+    /// Holds at the start of the input, and after a line ending (`\n`, `\r\n`, or a `\r` that
+    /// is not followed by `\n`).
+    LineStart,
+    /// Holds at the end of the input, and before a line ending (`\n` or `\r`).
+    LineEnd,
+}
+
+impl Anchor {
+    /// All of the anchors, in the order in which generated lexers test them.
+    pub const ALL:[Anchor;2] = [Anchor::LineStart,Anchor::LineEnd];
+}
+
+
+
 // =================
 // === Utilities ===
 // =================
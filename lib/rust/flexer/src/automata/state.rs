@@ -1,7 +1,9 @@
 //! This module exports State implementation for Nondeterministic Finite Automata.
 
 use crate::automata::alphabet;
+use crate::automata::pattern::Anchor;
 use crate::automata::symbol::Symbol;
+use crate::group::rule;
 
 use crate::prelude::*;
 
@@ -16,6 +18,9 @@ use crate::prelude::*;
 pub struct State {
     /// A set of transitions that can trigger without consuming a symbol (ε-transitions).
     pub epsilon_links:Vec<Identifier>,
+    /// A set of transitions that can trigger without consuming a symbol, but only where their
+    /// anchor holds.
+    pub anchored_links:Vec<AnchoredLink>,
     /// The set of transitions that trigger while consuming a specific symbol.
     ///
     /// When triggered, the automaton will transition to the [`Transition::target_state`].
@@ -25,7 +30,16 @@ pub struct State {
     /// This is used to auto-generate a call to the rust method of the same name.
     pub name:Option<String>,
     /// The function to call when evaluating the state.
-    pub callback:String
+    pub callback:String,
+    /// The condition under which the callback applies, if it is conditional.
+    pub guard:Option<String>,
+    /// The change to the stack of groups to apply after the callback, if any.
+    pub group_transition:Option<rule::Transition>,
+    /// The nested lexer to delegate the input to before the callback, if any.
+    pub nested:Option<String>,
+    /// Whether the state is part of a [lazy](super::pattern::Pattern::Lazy) pattern, which the
+    /// automaton leaves once the rule that the pattern is part of has matched.
+    pub lazy:bool,
 }
 
 impl State {
@@ -35,6 +49,11 @@ impl State {
         self
     }
 
+    /// The name of the method that evaluates the guard of the state, if it is named and guarded.
+    pub fn guard_name(&self) -> Option<String> {
+        self.guard.as_ref().and(self.name.as_ref()).map(|name| format!("{}_guard",name))
+    }
+
     /// Returns transition (next state) for each symbol in alphabet.
     pub fn targets(&self, alphabet:&alphabet::Segmentation) -> Vec<Identifier> {
         let mut targets = vec![];
@@ -134,3 +153,19 @@ pub struct Transition {
     /// The state that is entered after the transition has triggered.
     pub target_state:Identifier,
 }
+
+
+
+// ====================
+// === AnchoredLink ===
+// ====================
+
+/// A transition between states in a finite automaton that triggers without consuming a symbol, but
+/// only at the positions in the input where its anchor holds.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct AnchoredLink {
+    /// The condition on the position in the input under which this transition will trigger.
+    pub anchor:Anchor,
+    /// The state that is entered after the transition has triggered.
+    pub target_state:Identifier,
+}
@@ -0,0 +1,278 @@
+//! Lowering of automata over unicode code points into automata over UTF-8 code units.
+//!
+//! An automaton over code points needs its input to be decoded before every transition. Lowering
+//! each code-point range into the equivalent set of UTF-8 byte sequences produces an automaton
+//! that accepts exactly the same inputs, but that can run directly over the encoded bytes.
+
+use crate::prelude::*;
+
+use crate::automata::nfa::NFA;
+use crate::automata::state::State;
+use crate::automata::state;
+use crate::automata::symbol::Symbol;
+
+use std::ops::RangeInclusive;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The largest valid unicode code point.
+pub const MAX_CODE_POINT:u32 = 0x10FFFF;
+
+/// The range of code points reserved for UTF-16 surrogates, which cannot be encoded in UTF-8.
+pub const SURROGATES:RangeInclusive<u32> = 0xD800..=0xDFFF;
+
+/// The largest code points that are encoded with one, two and three bytes respectively.
+const ENCODED_LENGTH_BOUNDARIES:[u32;3] = [0x7F,0x7FF,0xFFFF];
+
+
+
+// ================
+// === Sequence ===
+// ================
+
+/// A sequence of byte ranges, matching any UTF-8 encoded code point whose `n`th byte is in the
+/// `n`th range of the sequence.
+pub type Sequence = Vec<RangeInclusive<u8>>;
+
+/// Lower the provided range of code points into the UTF-8 byte sequences that encode it.
+///
+/// The sequences are disjoint, and are returned in ascending order. Surrogates are skipped, as
+/// they cannot occur in UTF-8 input. Symbols above [`MAX_CODE_POINT`] (such as
+/// [`Symbol::EOF_CODE`]) are not code points, and are hence returned unchanged as the second
+/// element of the result.
+pub fn sequences_for
+(range:&RangeInclusive<Symbol>) -> (Vec<Sequence>,Option<RangeInclusive<Symbol>>) {
+    let start         = range.start().value;
+    let end           = range.end().value;
+    let mut sequences = Vec::new();
+    if start <= MAX_CODE_POINT {
+        push_sequences(start,end.min(MAX_CODE_POINT),&mut sequences);
+    }
+    let raw = if end > MAX_CODE_POINT {
+        Some(Symbol::from(start.max(MAX_CODE_POINT + 1))..=Symbol::from(end))
+    } else {
+        None
+    };
+    (sequences,raw)
+}
+
+/// Push the sequences encoding the code points in `start..=end` into `sequences`.
+///
+/// The range is split until each part can be represented by a single sequence, using the same
+/// approach as RE2 and [utf8-ranges](https://github.com/BurntSushi/utf8-ranges).
+fn push_sequences(start:u32, end:u32, sequences:&mut Vec<Sequence>) {
+    if start > end {
+        return
+    }
+    if start <= *SURROGATES.end() && end >= *SURROGATES.start() {
+        if start < *SURROGATES.start() {
+            push_sequences(start,SURROGATES.start() - 1,sequences);
+        }
+        if end > *SURROGATES.end() {
+            push_sequences(SURROGATES.end() + 1,end,sequences);
+        }
+        return
+    }
+    for &boundary in ENCODED_LENGTH_BOUNDARIES.iter() {
+        if start <= boundary && end > boundary {
+            push_sequences(start,boundary,sequences);
+            push_sequences(boundary + 1,end,sequences);
+            return
+        }
+    }
+    let start_bytes = encode(start);
+    for continuation_bytes in 1..start_bytes.len() {
+        let mask = (1u32 << (6 * continuation_bytes)) - 1;
+        if start & !mask != end & !mask {
+            if start & mask != 0 {
+                push_sequences(start,start | mask,sequences);
+                push_sequences((start | mask) + 1,end,sequences);
+                return
+            }
+            if end & mask != mask {
+                push_sequences(start,(end & !mask) - 1,sequences);
+                push_sequences(end & !mask,end,sequences);
+                return
+            }
+        }
+    }
+    let end_bytes = encode(end);
+    sequences.push(start_bytes.into_iter().zip(end_bytes).map(|(s,e)| s..=e).collect());
+}
+
+/// Encode the provided code point into its UTF-8 bytes.
+fn encode(code_point:u32) -> Vec<u8> {
+    let char       = std::char::from_u32(code_point).expect("A valid unicode code point.");
+    let mut buffer = [0;4];
+    char.encode_utf8(&mut buffer).as_bytes().to_vec()
+}
+
+
+
+// ================
+// === Lowering ===
+// ================
+
+impl NFA {
+    /// Lower this automaton over code points into an equivalent automaton over UTF-8 bytes.
+    ///
+    /// Every transition on a range of code points is replaced by transitions through a chain of
+    /// fresh, unnamed states for each of the byte sequences that encode the range. The states of
+    /// `self` keep their identifiers, names and callbacks in the result.
+    ///
+    /// The sequences for a range may share their leading bytes, but each state of an NFA may only
+    /// have a single transition on any given symbol. Each chain is hence entered through its own
+    /// epsilon link.
+    pub fn to_utf8(&self) -> NFA {
+        let states = self.states.iter().map(|state| {
+            let epsilon_links  = state.epsilon_links.clone();
+            let anchored_links = state.anchored_links.clone();
+            let name           = state.name.clone();
+            let callback       = state.callback.clone();
+            let guard          = state.guard.clone();
+            let transition     = state.group_transition;
+            let nested         = state.nested.clone();
+            let lazy           = state.lazy;
+            State{epsilon_links,anchored_links,name,callback,guard,group_transition:transition,
+                nested,lazy,..default()}
+        }).collect();
+        let mut nfa = NFA{states,..default()};
+        for (id,state) in self.states.iter().enumerate() {
+            let source = state::Identifier::new(id);
+            for link in &state.links {
+                let first           = nfa.states.len();
+                let (sequences,raw) = sequences_for(&link.symbols);
+                for sequence in sequences {
+                    let entry = nfa.new_state();
+                    nfa.connect(source,entry);
+                    nfa.connect_via_sequence(entry,link.target_state,&sequence);
+                }
+                if let Some(raw) = raw {
+                    let entry = nfa.new_state();
+                    nfa.connect(source,entry);
+                    nfa.connect_via(entry,link.target_state,&raw);
+                }
+                for chain_state in &mut nfa.states[first..] {
+                    chain_state.lazy = state.lazy;
+                }
+            }
+        }
+        nfa
+    }
+
+    /// Connect `source` to `target` through a chain of transitions on the byte ranges of
+    /// `sequence`.
+    fn connect_via_sequence
+    ( &mut self
+    , source   : state::Identifier
+    , target   : state::Identifier
+    , sequence : &[RangeInclusive<u8>]
+    ) {
+        let last     = sequence.len() - 1;
+        let mut from = source;
+        for (ix,bytes) in sequence.iter().enumerate() {
+            let to      = if ix == last { target } else { self.new_state() };
+            let symbols = Symbol::from(*bytes.start() as u32)..=Symbol::from(*bytes.end() as u32);
+            self.connect_via(from,to,&symbols);
+            from = to;
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::automata::dfa::DFA;
+    use crate::automata::pattern::Pattern;
+
+    /// Get the sequences for the code points in `start..=end`.
+    fn sequences(start:u32, end:u32) -> Vec<Sequence> {
+        sequences_for(&(Symbol::from(start)..=Symbol::from(end))).0
+    }
+
+    /// Check whether `sequence` matches `bytes`.
+    fn matches(sequence:&[RangeInclusive<u8>], bytes:&[u8]) -> bool {
+        sequence.len() == bytes.len() && sequence.iter().zip(bytes).all(|(r,b)| r.contains(b))
+    }
+
+    /// Run the `dfa` over `bytes`, returning whether it accepts them.
+    fn dfa_accepts(dfa:&DFA, bytes:&[u8]) -> bool {
+        let divisions = dfa.alphabet_segmentation.divisions_as_vec();
+        let mut state = state::Identifier::new(0);
+        for &byte in bytes {
+            let division = divisions.iter().rev().find(|d| d.symbol.value <= byte as u32).unwrap();
+            state = dfa.links[(state.id,division.position)];
+            if state == state::Identifier::INVALID {
+                return false
+            }
+        }
+        dfa.callbacks[state.id].is_some()
+    }
+
+    #[test]
+    fn ascii_range() {
+        assert_eq!(sequences(0x61,0x7A),vec![vec![0x61..=0x7A]]);
+    }
+
+    #[test]
+    fn range_across_lengths() {
+        let expected = vec![
+            vec![0x00..=0x7F],
+            vec![0xC2..=0xDF,0x80..=0xBF],
+            vec![0xE0..=0xE0,0xA0..=0xBF,0x80..=0xBF],
+        ];
+        assert_eq!(sequences(0x00,0x0FFF),expected);
+    }
+
+    #[test]
+    fn surrogates_are_skipped() {
+        let expected = vec![
+            vec![0xED..=0xED,0x80..=0x9F,0x80..=0xBF],
+            vec![0xEE..=0xEF,0x80..=0xBF,0x80..=0xBF],
+        ];
+        assert_eq!(sequences(0xD000,0xFFFF),expected);
+    }
+
+    #[test]
+    fn non_code_points_are_raw() {
+        let range     = Symbol::from(0x10FFFF)..=Symbol::EOF_CODE;
+        let (seq,raw) = sequences_for(&range);
+        assert_eq!(seq,vec![vec![0xF4..=0xF4,0x8F..=0x8F,0xBF..=0xBF,0xBF..=0xBF]]);
+        assert_eq!(raw,Some(Symbol::from(0x110000)..=Symbol::EOF_CODE));
+    }
+
+    #[test]
+    fn sequences_cover_all_code_points() {
+        let all = sequences(0,MAX_CODE_POINT);
+        for code_point in (0..=MAX_CODE_POINT).step_by(97).filter(|c| !SURROGATES.contains(c)) {
+            let bytes    = encode(code_point);
+            let matching = all.iter().filter(|seq| matches(seq,&bytes)).count();
+            assert_eq!(matching,1,"Code point {:#X} should match exactly once.",code_point);
+        }
+    }
+
+    #[test]
+    fn lowered_dfa_accepts_encoded_input() {
+        let pattern   = Pattern::range('a'..='z') | Pattern::range('α'..='ω') | Pattern::char('😀');
+        let mut nfa   = NFA::default();
+        let start     = nfa.new_state();
+        let end       = nfa.new_pattern(start,&pattern.many1());
+        nfa.states[end.id].name = Some("word".into());
+        let dfa = DFA::from(&nfa.to_utf8());
+        assert!(dfa_accepts(&dfa,b"abc"));
+        assert!(dfa_accepts(&dfa,"aλb😀".as_bytes()));
+        assert!(!dfa_accepts(&dfa,b"aAb"));
+        assert!(!dfa_accepts(&dfa,&"λ".as_bytes()[..1]));
+    }
+}
@@ -0,0 +1,121 @@
+//! Reporting of the rules of a lexer that have fired while lexing.
+//!
+//! Lexers generated with [`crate::generate::CodegenOptions::with_coverage`] count the number of
+//! times that each of their rules fires, and expose these counts through a generated
+//! `rule_coverage()` function that returns a [`Report`].
+
+use crate::prelude::*;
+
+
+
+// ================
+// === RuleHits ===
+// ================
+
+/// The number of times that a single rule of a lexer has fired.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct RuleHits {
+    /// The name of the group that the rule was matched in.
+    pub group : String,
+    /// The name of the function generated for the rule.
+    pub rule : String,
+    /// The code that the rule executes when it fires.
+    pub callback : String,
+    /// The number of times that the rule has fired.
+    pub hits : usize,
+}
+
+impl RuleHits {
+    /// Create a new record of the `hits` of `rule` in `group`.
+    pub fn new
+    ( group    : impl Into<String>
+    , rule     : impl Into<String>
+    , callback : impl Into<String>
+    , hits     : usize
+    ) -> Self {
+        let group    = group.into();
+        let rule     = rule.into();
+        let callback = callback.into();
+        RuleHits{group,rule,callback,hits}
+    }
+}
+
+
+
+// ==============
+// === Report ===
+// ==============
+
+/// A report of the number of times that each rule of a lexer has fired.
+///
+/// Rules inherited by a group from its parent are reported separately for each group, as they are
+/// matched separately in each.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct Report {
+    /// The hits for each rule of the lexer, in the order that the rules are defined.
+    pub rules : Vec<RuleHits>,
+}
+
+impl Report {
+    /// Create a new report from the hits of each rule.
+    pub fn new(rules:Vec<RuleHits>) -> Self {
+        Report{rules}
+    }
+
+    /// The rules that have never fired.
+    pub fn unhit(&self) -> Vec<&RuleHits> {
+        self.rules.iter().filter(|rule| rule.hits == 0).collect()
+    }
+
+    /// Check whether every rule of the lexer has fired.
+    pub fn is_complete(&self) -> bool {
+        self.rules.iter().all(|rule| rule.hits > 0)
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for Report {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unhit = self.unhit();
+        let fired = self.rules.len() - unhit.len();
+        writeln!(f,"{} of {} rules fired.",fired,self.rules.len())?;
+        if !unhit.is_empty() {
+            writeln!(f,"Rules that never fired:")?;
+        }
+        for rule in unhit {
+            writeln!(f,"  {} ({}): {}",rule.group,rule.rule,rule.callback)?;
+        }
+        Ok(())
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report::new(vec![
+            RuleHits::new("ROOT","group_0_rule_0","self.on_word(reader)",3),
+            RuleHits::new("ROOT","group_0_rule_1","self.on_space(reader)",0),
+        ])
+    }
+
+    #[test]
+    fn unhit_rules() {
+        let report = report();
+        let unhit  = report.unhit().into_iter().map(|rule| rule.rule.as_str()).collect_vec();
+        assert_eq!(unhit,vec!["group_0_rule_1"]);
+        assert!(!report.is_complete());
+        let expected = "1 of 2 rules fired.\nRules that never fired:\n  \
+            ROOT (group_0_rule_1): self.on_space(reader)\n";
+        assert_eq!(report.to_string(),expected);
+    }
+}
@@ -7,14 +7,17 @@ use syn::*;
 
 use crate::automata::dfa::DFA;
 use crate::automata::dfa::RuleExecutable;
+use crate::automata::nfa::NFA;
+use crate::automata::pattern::Anchor;
 use crate::automata::state::Identifier;
 use crate::automata::state::State;
 use crate::group::Group;
+use crate::group::rule::Transition;
 use crate::group;
 
 use enso_macro_utils::repr;
 use proc_macro2::Literal;
-use std::hash::BuildHasher;
+use std::collections::BTreeMap;
 use std::result::Result;
 use std::fmt;
 
@@ -35,15 +38,60 @@ pub fn specialize
 ( definition       : &impl flexer::State
 , state_type_name  : impl Str
 , output_type_name : impl Str
+) -> Result<String,GenError> {
+    specialize_with(definition,state_type_name,output_type_name,&default())
+}
+
+/// Generate specialized code for the provided lexer `definition`, running over input in the
+/// provided `encoding`.
+///
+/// See [`Encoding`] for the readers that the generated lexer accepts in each case.
+pub fn specialize_with_encoding
+( definition       : &impl flexer::State
+, state_type_name  : impl Str
+, output_type_name : impl Str
+, encoding         : Encoding
+) -> Result<String,GenError> {
+    let options = CodegenOptions::default().with_encoding(encoding);
+    specialize_with(definition,state_type_name,output_type_name,&options)
+}
+
+/// Generate specialized code for the provided lexer `definition`, as configured by `options`.
+pub fn specialize_with
+( definition       : &impl flexer::State
+, state_type_name  : impl Str
+, output_type_name : impl Str
+, options          : &CodegenOptions
 ) -> Result<String,GenError> {
     let group_registry = definition.groups();
+    let tracks_lines   = group_registry.all().iter().any(|group| {
+        group.rules.iter().any(|rule| rule.pattern.has_anchor())
+    });
     let mut body_items = Vec::new();
-    body_items.push(run_function(output_type_name)?);
-    body_items.push(run_current_state_function());
+    body_items.push(run_current_state_function(options,tracks_lines));
     body_items.push(step(group_registry));
     for group in group_registry.all().iter() {
-        body_items.extend(automaton_for_group(group,group_registry)?)
+        body_items.extend(automaton_for_group(group,group_registry,options.encoding)?)
+    }
+    if let Some(hint) = options.inline.attribute() {
+        for item in body_items.iter_mut() {
+            if let ImplItem::Method(method) = item {
+                method.attrs.push(hint.clone())
+            }
+        }
     }
+    if options.profiling {
+        profile_rules(&mut body_items,group_registry);
+    }
+    if options.coverage {
+        let rules = coverage_table(group_registry);
+        instrument_rules(&mut body_items,&rules);
+        body_items.extend(coverage_functions(&rules,options));
+    }
+    if options.nestable {
+        body_items.insert(0,run_nested_function(output_type_name.as_ref(),options)?);
+    }
+    body_items.insert(0,run_function(output_type_name,options)?);
     let result = wrap_in_impl_for(state_type_name,body_items)?;
     let code   = show_code(&result);
     Ok(code)
@@ -68,19 +116,74 @@ pub fn wrap_in_impl_for
 
 /// Generate the `run` function for the specialized lexer.
 ///
-/// This function is what the user of the lexer will call to begin execution.
-pub fn run_function(output_type_name:impl Str) -> Result<ImplItem,GenError> {
+/// This function is what the user of the lexer will call to begin execution. Its name and
+/// visibility are taken from the `options`.
+pub fn run_function
+(output_type_name:impl Str, options:&CodegenOptions) -> Result<ImplItem,GenError> {
     let output_type_name = str_to_path(output_type_name)?;
+    let reader_bound     = options.encoding.reader_bound();
+    let run_name         = str_to_ident(options.run_name.as_str())?;
+    let visibility       = options.visibility;
     let tree:ImplItem    = parse_quote! {
-        pub fn run<R:LazyReader>(&mut self, mut reader:R) -> LexingResult<#output_type_name> {
+        #visibility fn #run_name<R:#reader_bound>
+        (&mut self, mut reader:R) -> LexingResult<#output_type_name> {
             self.set_up();
             reader.advance_char(&mut self.bookmarks);
-            while self.run_current_state(&mut reader) == StageStatus::ExitSuccess {}
-            let result = match self.status {
-                StageStatus::ExitFinished => LexingResult::success(
+            while self.run_current_state(&mut reader) == StageStatus::ExitSuccess
+                && self.error.is_none() {}
+            let result = match (self.error.take(),self.status) {
+                (Some(error),_) => LexingResult::error(
+                    error,
+                    mem::take(&mut self.output)
+                ),
+                (None,StageStatus::ExitFinished) => LexingResult::success(
                     mem::take(&mut self.output)
                 ),
-                StageStatus::ExitFail => LexingResult::failure(
+                (None,StageStatus::ExitFail) => LexingResult::failure(
+                    mem::take(&mut self.output)
+                ),
+                _ => LexingResult::partial(mem::take(&mut self.output))
+            };
+            self.tear_down();
+            result
+        }
+    };
+    Ok(tree)
+}
+
+/// Generate the `run_nested` function for the specialized lexer.
+///
+/// This function is called by the code generated for a rule of another lexer that delegates the
+/// input following its match to this lexer. It lexes from the current position of the reader until
+/// a rule calls [`flexer::Flexer::finish_nested`], or until the input ends, leaving the reader at
+/// the character following the last match.
+pub fn run_nested_function
+(output_type_name:impl Str, options:&CodegenOptions) -> Result<ImplItem,GenError> {
+    let output_type_name = str_to_path(output_type_name)?;
+    let visibility       = options.visibility;
+    let tree:ImplItem    = parse_quote! {
+        #visibility fn run_nested<R:MatchReader>
+        (&mut self, reader:&mut R) -> LexingResult<#output_type_name> {
+            self.set_up();
+            let matched_bookmark = self.bookmarks.matched_bookmark;
+            let rule_bookmark    = self.bookmarks.rule_bookmark;
+            self.bookmarks.bookmark(matched_bookmark,reader);
+            self.bookmarks.bookmark(rule_bookmark,reader);
+            loop {
+                let status   = self.run_current_state(reader);
+                let finished = self.take_finished_nested();
+                if status != StageStatus::ExitSuccess || self.error.is_some() || finished {
+                    break
+                }
+            }
+            let result = match (self.error.take(),self.status) {
+                (Some(error),_) => LexingResult::error(
+                    error,
+                    mem::take(&mut self.output)
+                ),
+                (None,StageStatus::ExitSuccess) | (None,StageStatus::ExitFinished) =>
+                    LexingResult::success(mem::take(&mut self.output)),
+                (None,StageStatus::ExitFail) => LexingResult::failure(
                     mem::take(&mut self.output)
                 ),
                 _ => LexingResult::partial(mem::take(&mut self.output))
@@ -93,20 +196,63 @@ pub fn run_function(output_type_name:impl Str) -> Result<ImplItem,GenError> {
 }
 
 /// Generate the function responsible for executing the lexer in its current state.
-pub fn run_current_state_function() -> ImplItem {
+///
+/// The progress of the lexer through its input is only logged if `options` enable tracing, and only
+/// recorded in the lexer's profile if they enable profiling. The character preceding the reader's
+/// position, which [anchors](Anchor) depend on, is only tracked if `tracks_lines` is set.
+pub fn run_current_state_function(options:&CodegenOptions, tracks_lines:bool) -> ImplItem {
+    let trace   = |stmts:Vec<Stmt>| if options.tracing { stmts } else { vec![] };
+    let profile = |stmts:Vec<Stmt>| if options.profiling { stmts } else { vec![] };
+    let track   = |stmts:Vec<Stmt>| if tracks_lines { stmts } else { vec![] };
+    let track_match_start = track(parse_quote! {
+        let match_start_char = self.previous_char;
+    });
+    let track_character = track(parse_quote! {
+        self.previous_char = Some(char);
+    });
+    let track_match_end = track(parse_quote! {
+        if self.status == StageStatus::ExitSuccess {
+            self.previous_char = self.current_match.chars().last().or(match_start_char);
+        }
+    });
+    let profile_step_start = profile(parse_quote! {
+        let profiled_group = self.current_state();
+        let step_start     = std::time::Instant::now();
+    });
+    let profile_step_end = profile(parse_quote! {
+        self.profile_step(profiled_group,step_start.elapsed());
+    });
+    let profile_character = profile(parse_quote! {
+        self.profile_character(profiled_group);
+    });
+    let trace_step = trace(parse_quote! {
+        self.logger.debug(||format!("Current character is {:?}.",reader.character().char));
+        self.logger.debug(||format!("Continuing in {:?}.",next_state));
+    });
+    let trace_finished = trace(parse_quote! {
+        self.logger.info("Input finished.");
+    });
+    let trace_match = trace(parse_quote! {
+        self.logger.info(||format!("Result is {:?}.",reader.result()));
+    });
+    let trace_eof = trace(parse_quote! {
+        self.logger.info("Reached EOF.");
+    });
     let tree:ImplItem = parse_quote! {
-        fn run_current_state<R:LazyReader>(&mut self, reader:&mut R) -> StageStatus {
+        fn run_current_state<R:MatchReader>(&mut self, reader:&mut R) -> StageStatus {
             self.status = StageStatus::Initial;
             let mut finished = false;
+            #(#track_match_start)*
 
             // Runs until reaching a state that no longer says to continue.
             while let Some(next_state) = self.status.continue_as() {
-                self.logger.debug(||format!("Current character is {:?}.",reader.character().char));
-                self.logger.debug(||format!("Continuing in {:?}.",next_state));
+                #(#trace_step)*
+                #(#profile_step_start)*
                 self.status = self.step(next_state,reader);
+                #(#profile_step_end)*
 
                 if finished && reader.finished(self.bookmarks()) {
-                    self.logger.info("Input finished.");
+                    #(#trace_finished)*
                     self.status = StageStatus::ExitFinished
                 }
                 finished = reader.character().is_eof();
@@ -114,11 +260,13 @@ pub fn run_current_state_function() -> ImplItem {
                 if self.status.should_continue() {
                     match reader.character().char {
                         Ok(char) => {
-                            reader.append_result(char);
-                            self.logger.info(||format!("Result is {:?}.",reader.result()));
+                            reader.append_match(char);
+                            #(#track_character)*
+                            #(#profile_character)*
+                            #(#trace_match)*
                         },
                         Err(flexer::prelude::reader::Error::EOF) => {
-                            self.logger.info("Reached EOF.");
+                            #(#trace_eof)*
                         },
                         Err(flexer::prelude::reader::Error::EndOfGroup) => {
                             let current_state = self.current_state();
@@ -135,6 +283,7 @@ pub fn run_current_state_function() -> ImplItem {
                     reader.advance_char(&mut self.bookmarks);
                 }
             }
+            #(#track_match_end)*
 
             self.status
         }
@@ -149,7 +298,7 @@ pub fn run_current_state_function() -> ImplItem {
 pub fn step(groups:&group::Registry) -> ImplItem {
     let arms = groups.all().iter().map(|g| step_match_arm(g.id.into())).collect_vec();
     parse_quote! {
-        fn step<R:LazyReader>(&mut self, next_state:SubStateId, reader:&mut R) -> StageStatus {
+        fn step<R:MatchReader>(&mut self, next_state:SubStateId, reader:&mut R) -> StageStatus {
             let current_state:usize = self.current_state().into();
             match current_state {
                 #(#arms)*
@@ -173,21 +322,126 @@ pub fn step_match_arm(number:usize) -> Arm {
 }
 
 
+// === Coverage Instrumentation ===
+
+/// A rule of the lexer whose hits are counted, as its group name, function name, and callback.
+type CoveredRule = (String,String,String);
+
+/// Collect the rules of every group in `registry`, in the order that their hits are counted.
+pub fn coverage_table(registry:&group::Registry) -> Vec<CoveredRule> {
+    let mut rules = Vec::new();
+    for group in registry.all().iter() {
+        for (ix,rule) in registry.rules_for(group.id).into_iter().enumerate() {
+            rules.push((group.name.clone(),group.callback_name(ix),rule.callback.clone()));
+        }
+    }
+    rules
+}
+
+/// Make each of the generated rule functions in `items` count its hits in the counter for its
+/// position in `rules`.
+pub fn instrument_rules(items:&mut [ImplItem], rules:&[CoveredRule]) {
+    let indices:BTreeMap<&str,usize> =
+        rules.iter().enumerate().map(|(ix,(_,name,_))| (name.as_str(),ix)).collect();
+    for item in items.iter_mut() {
+        if let ImplItem::Method(method) = item {
+            if let Some(&ix) = indices.get(method.sig.ident.to_string().as_str()) {
+                let ix = Literal::usize_unsuffixed(ix);
+                let count_hit:Stmt = parse_quote! {
+                    Self::coverage_rule_hits()[#ix].fetch_add(1,std::sync::atomic::Ordering::Relaxed);
+                };
+                method.block.stmts.insert(0,count_hit);
+            }
+        }
+    }
+}
+
+/// Generate the counters for the hits of each of the `rules`, and the `rule_coverage` function
+/// that reports them.
+///
+/// The counters are shared by all instances of the lexer, so that the report covers every input
+/// lexed by the process.
+pub fn coverage_functions(rules:&[CoveredRule], options:&CodegenOptions) -> Vec<ImplItem> {
+    let count      = Literal::usize_unsuffixed(rules.len());
+    let visibility = options.visibility;
+    let entries    = rules.iter().enumerate().map(|(ix,(group,name,callback))| {
+        let ix = Literal::usize_unsuffixed(ix);
+        quote! {
+            flexer::coverage::RuleHits::new(
+                #group,
+                #name,
+                #callback,
+                hits[#ix].load(std::sync::atomic::Ordering::Relaxed),
+            )
+        }
+    });
+    let counters:ImplItem = parse_quote! {
+        fn coverage_rule_hits() -> &'static [std::sync::atomic::AtomicUsize] {
+            const UNHIT:std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+            static HITS:[std::sync::atomic::AtomicUsize;#count] = [UNHIT;#count];
+            &HITS
+        }
+    };
+    let report:ImplItem = parse_quote! {
+        #visibility fn rule_coverage() -> flexer::coverage::Report {
+            let hits = Self::coverage_rule_hits();
+            flexer::coverage::Report::new(vec![#(#entries),*])
+        }
+    };
+    vec![counters,report]
+}
+
+
+// === Profiling Instrumentation ===
+
+/// Make each of the generated rule functions in `items` record its firing, and the time taken by
+/// its callback, in the profile of the group from `registry` that it belongs to.
+pub fn profile_rules(items:&mut [ImplItem], registry:&group::Registry) {
+    let mut groups = BTreeMap::new();
+    for group in registry.all().iter() {
+        let id:usize = group.id.into();
+        for ix in 0..registry.rules_for(group.id).len() {
+            groups.insert(group.callback_name(ix),id);
+        }
+    }
+    for item in items.iter_mut() {
+        if let ImplItem::Method(method) = item {
+            if let Some(&group) = groups.get(&method.sig.ident.to_string()) {
+                let group = Literal::usize_unsuffixed(group);
+                let stmts = method.block.stmts.drain(..).map(|stmt| match stmt {
+                    Stmt::Expr(expr) => Stmt::Semi(expr,default()),
+                    stmt             => stmt,
+                }).collect_vec();
+                method.block = parse_quote! {{
+                    let callback_start = std::time::Instant::now();
+                    #(#stmts)*
+                    let group = flexer::group::Identifier::from(#group);
+                    self.profile_rule(group,callback_start.elapsed());
+                }};
+            }
+        }
+    }
+}
+
+
 // === Generation for a Specific Lexer State ===
 
 /// Generate the functions that implement the lexer automaton for a given lexer state.
 pub fn automaton_for_group
 ( group    : &Group
 , registry : &group::Registry
+, encoding : Encoding
 ) -> Result<Vec<ImplItem>,GenError> {
-    let nfa       = registry.to_nfa_from(group.id);
+    let nfa       = encoding.lower(registry.to_nfa_from(group.id));
     let mut rules = Vec::with_capacity(nfa.states.len());
     for state in nfa.states.iter() {
         if state.name.is_some() {
             rules.push(rule_for_state(state)?);
+            rules.extend(guard_for_state(state)?);
         }
     }
     let mut dfa             = DFA::from(&nfa);
+    dfa.fall_back_to_shorter_matches();
     let dispatch_for_dfa    = dispatch_in_state(&dfa,group.id.into())?;
     let mut dfa_transitions = transitions_for_dfa(&mut dfa,group.id.into())?;
     dfa_transitions.push(dispatch_for_dfa);
@@ -197,27 +451,71 @@ pub fn automaton_for_group
 
 /// Generate a set of transition functions for the provided `dfa`, with identifier `id`.
 pub fn transitions_for_dfa(dfa:&mut DFA, id:usize) -> Result<Vec<ImplItem>,GenError> {
-    let mut state_has_overlapping_rules:HashMap<usize,bool> = HashMap::new();
+    let mut state_has_overlapping_rules:BTreeMap<usize,bool> = BTreeMap::new();
     state_has_overlapping_rules.insert(0,false);
     let state_names:Vec<_> = dfa.links.row_indices().map(|ix| (ix, name_for_step(id, ix))).collect();
     let mut transitions    = Vec::with_capacity(state_names.len());
     for (ix,name) in state_names.into_iter() {
-        transitions.push(transition_for_dfa(dfa,name,ix,&mut state_has_overlapping_rules)?)
+        let overlaps       = &mut state_has_overlapping_rules;
+        let anchored       = anchored_transitions(dfa,id,ix,overlaps);
+        let mut transition = transition_for_dfa(dfa,name,ix,overlaps)?;
+        if let ImplItem::Method(method) = &mut transition {
+            method.block.stmts.splice(..0,anchored);
+        }
+        transitions.push(transition)
     }
     Ok(transitions)
 }
 
+/// Generate the statements that move the DFA with identifier `id` from the state `state_ix` across
+/// its anchored links, wherever their anchors hold.
+///
+/// The target of an anchored link consumes no input, so it inherits the rule of its source if it
+/// has none of its own.
+pub fn anchored_transitions
+( dfa          : &mut DFA
+, id           : usize
+, state_ix     : usize
+, has_overlaps : &mut BTreeMap<usize,bool>
+) -> Vec<Stmt> {
+    let overlaps = *has_overlaps.get(&state_ix).unwrap_or(&false);
+    let links    = dfa.anchored_links_from(Identifier::new(state_ix)).to_vec();
+    links.into_iter().map(|link| {
+        let target = link.target_state;
+        if let Some(rule) = dfa.callbacks[state_ix].clone() {
+            if !dfa.has_rule_for(target) {
+                dfa.callbacks[target.id] = Some(rule);
+                has_overlaps.insert(target.id,overlaps);
+            }
+        }
+        let condition   = anchor_condition(link.anchor);
+        let target_name = name_for_step(id,target.id);
+        parse_quote! {
+            if #condition {
+                return self.#target_name(reader)
+            }
+        }
+    }).collect()
+}
+
+/// Generate the expression that checks whether `anchor` holds at the current position.
+pub fn anchor_condition(anchor:Anchor) -> Expr {
+    match anchor {
+        Anchor::LineStart => parse_quote!(self.at_line_start(reader)),
+        Anchor::LineEnd   => parse_quote!(self.at_line_end(reader)),
+    }
+}
+
 /// Generate a specific transition function for
-#[allow(clippy::implicit_hasher)]
-pub fn transition_for_dfa<S:BuildHasher>
+pub fn transition_for_dfa
 ( dfa             : &mut DFA
 , transition_name : Ident
 , state_ix        : usize
-, has_overlaps    : &mut HashMap<usize,bool,S>
+, has_overlaps    : &mut BTreeMap<usize,bool>
 ) -> Result<ImplItem,GenError> {
     let match_expr:Expr   = match_for_transition(dfa,state_ix,has_overlaps)?;
     let function:ImplItem = parse_quote! {
-        fn #transition_name<R:LazyReader>(&mut self, reader:&mut R) -> StageStatus {
+        fn #transition_name<R:MatchReader>(&mut self, reader:&mut R) -> StageStatus {
             #match_expr
         }
     };
@@ -225,10 +523,10 @@ pub fn transition_for_dfa<S:BuildHasher>
 }
 
 /// Generate the pattern match for a given transition function.
-pub fn match_for_transition<S:BuildHasher>
+pub fn match_for_transition
 ( dfa          : &mut DFA
 , state_ix     : usize
-, has_overlaps : &mut HashMap<usize,bool,S>
+, has_overlaps : &mut BTreeMap<usize,bool>
 ) -> Result<Expr,GenError> {
     let overlaps          = *has_overlaps.get(&state_ix).unwrap_or(&false);
     let state             = dfa.callbacks.get(state_ix).expect("Internal error.").clone();
@@ -265,11 +563,11 @@ pub fn match_for_transition<S:BuildHasher>
 }
 
 /// Generate the branch body for a transition in the DFA.
-pub fn branch_body<S:BuildHasher>
+pub fn branch_body
 ( dfa           : &mut DFA
 , target_state  : Identifier
 , maybe_state   : &Option<RuleExecutable>
-, has_overlaps  : &mut HashMap<usize,bool,S>
+, has_overlaps  : &mut BTreeMap<usize,bool>
 , rules_overlap : bool
 ) -> Result<Block,GenError> {
     if target_state == Identifier::INVALID {
@@ -279,6 +577,13 @@ pub fn branch_body<S:BuildHasher>
                     StageStatus::ExitFail
                 }})
             },
+            Some(rule_exec) if rule_exec.guard.is_some() => {
+                let run_rule = guarded_rule_call(rule_exec,rules_overlap)?;
+                Ok(parse_quote! {{
+                    let matched_bookmark = self.bookmarks.matched_bookmark;
+                    #run_rule
+                }})
+            },
             Some(rule_exec) => {
                 let rule:Expr = match parse_str(rule_exec.code.as_str()) {
                     Ok(rule) => rule,
@@ -289,7 +594,7 @@ pub fn branch_body<S:BuildHasher>
                         let rule_bookmark    = self.bookmarks.rule_bookmark;
                         let matched_bookmark = self.bookmarks.matched_bookmark;
                         self.bookmarks.rewind(rule_bookmark,reader);
-                        self.current_match = reader.pop_result();
+                        self.take_match(reader);
                         self.#rule(reader);
                         self.bookmarks.bookmark(matched_bookmark,reader);
                         StageStatus::ExitSuccess
@@ -297,7 +602,7 @@ pub fn branch_body<S:BuildHasher>
                 } else {
                     Ok(parse_quote! {{
                         let matched_bookmark = self.bookmarks.matched_bookmark;
-                        self.current_match   = reader.pop_result();
+                        self.take_match(reader);
                         self.#rule(reader);
                         self.bookmarks.bookmark(matched_bookmark,reader);
                         StageStatus::ExitSuccess
@@ -306,23 +611,31 @@ pub fn branch_body<S:BuildHasher>
             }
         }
     } else {
+        // The rule bookmark must be set on _every_ transition from a state with a rule into one
+        // that inherits it, not only on the transition that discovered the overlap. The same holds
+        // for the transitions into a state that falls back to the rule of a shorter match.
         let target_state_has_no_rule = match maybe_state {
             Some(state) => if !dfa.has_rule_for(target_state) {
-                dfa.callbacks[target_state.id] = Some(state.clone());
+                dfa.callbacks[target_state.id] = Some(state.without_shorter_fallback());
                 has_overlaps.insert(target_state.id,true);
                 true
             } else {
-                false
+                *has_overlaps.get(&target_state.id).unwrap_or(&false)
             },
             None => false
         };
+        let target_state_falls_back = match (maybe_state,&dfa.callbacks[target_state.id]) {
+            (Some(state),Some(target)) => target.has_shorter_fallback() &&
+                target.without_shorter_fallback() != state.without_shorter_fallback(),
+            _ => false
+        };
 
         let state_id = Literal::usize_unsuffixed(target_state.id);
         let ret:Expr = parse_quote! {
             StageStatus::ContinueWith(#state_id.into())
         };
 
-        if target_state_has_no_rule && !rules_overlap {
+        if (target_state_has_no_rule || target_state_falls_back) && !rules_overlap {
             Ok(parse_quote! {{
                 let rule_bookmark = self.bookmarks.rule_bookmark;
                 self.bookmarks.bookmark(rule_bookmark,reader);
@@ -336,6 +649,47 @@ pub fn branch_body<S:BuildHasher>
     }
 }
 
+/// Generate an expression that runs the first rule in the chain of `rule_exec` whose guard holds,
+/// and evaluates to the resulting stage status.
+///
+/// The rules apply to the match that ends at the rule bookmark if `at_rule_bookmark` is set, and
+/// from the first fallback to the rules of a shorter match onwards. The expression evaluates to
+/// `StageStatus::ExitFail` if no rule applies.
+pub fn guarded_rule_call
+(rule_exec:&RuleExecutable, at_rule_bookmark:bool) -> Result<Expr,GenError> {
+    let rule:Expr = match parse_str(rule_exec.code.as_str()) {
+        Ok(rule) => rule,
+        Err(_)   => return Err(GenError::BadExpression(rule_exec.code.clone()))
+    };
+    let at_rule_bookmark = at_rule_bookmark || rule_exec.shorter;
+    let rewind:Vec<Stmt> = if at_rule_bookmark {
+        parse_quote! {
+            let rule_bookmark = self.bookmarks.rule_bookmark;
+            self.bookmarks.rewind(rule_bookmark,reader);
+        }
+    } else {
+        vec![]
+    };
+    let run_rule:Block = parse_quote! {{
+        #(#rewind)*
+        self.take_match(reader);
+        self.#rule(reader);
+        self.bookmarks.bookmark(matched_bookmark,reader);
+        StageStatus::ExitSuccess
+    }};
+    let guard = match &rule_exec.guard {
+        None        => return Ok(parse_quote!(#run_rule)),
+        Some(guard) => str_to_ident(guard)?,
+    };
+    let fallback:Expr = match &rule_exec.fallback {
+        None           => parse_quote!(StageStatus::ExitFail),
+        Some(fallback) => guarded_rule_call(fallback,at_rule_bookmark)?,
+    };
+    Ok(parse_quote! {
+        if self.#guard() #run_rule else { #fallback }
+    })
+}
+
 /// Generate the dispatch function for a given lexer state.
 ///
 /// This dispatch function is responsible for dispatching based on the sub-state of any given lexer
@@ -359,7 +713,7 @@ pub fn dispatch_in_state(dfa:&DFA, id:usize) -> Result<ImplItem,GenError> {
         }
     };
     let func:ImplItem = parse_quote! {
-        fn #dispatch_name<R:LazyReader>
+        fn #dispatch_name<R:MatchReader>
         ( &mut self
         , new_state_index:SubStateId
         , reader:&mut R
@@ -390,14 +744,101 @@ pub fn rule_for_state(state:&State) -> Result<ImplItem,GenError> {
             if !has_reader_arg(&code) {
                 return Err(GenError::BadCallbackArgument)
             }
+            let (reader_bound,delegation):(Path,Vec<Stmt>) = match &state.nested {
+                None         => (parse_quote!(LazyReader),vec![]),
+                Some(nested) => (parse_quote!(MatchReader),delegation_statements(nested)?),
+            };
+
+            let tree:ImplItem = match &state.group_transition {
+                None => parse_quote! {
+                    fn #rule_name<R:#reader_bound>(&mut self, reader:&mut R) {
+                        #(#delegation)*
+                        #code
+                    }
+                },
+                Some(transition) => {
+                    let transition = transition_statements(transition);
+                    parse_quote! {
+                        fn #rule_name<R:#reader_bound>(&mut self, reader:&mut R) {
+                            #(#delegation)*
+                            #code;
+                            #(#transition)*
+                        }
+                    }
+                }
+            };
+            Ok(tree)
+        }
+    }
+}
+
+/// Generate the statements that delegate the input following a match to the `nested` lexer,
+/// binding its result to `nested`.
+///
+/// The nested lexer advances the reader without updating the bookmarks of this lexer, and so the
+/// rule bookmark is moved to where the nested lexer stopped. The matched bookmark is moved there
+/// after the callback in any case.
+pub fn delegation_statements(nested:&str) -> Result<Vec<Stmt>,GenError> {
+    let lexer:Expr = match parse_str(nested) {
+        Ok(lexer) => lexer,
+        Err(_)    => return Err(GenError::BadExpression(nested.into()))
+    };
+    Ok(parse_quote! {
+        let nested        = #lexer.run_nested(reader);
+        let rule_bookmark = self.bookmarks.rule_bookmark;
+        self.bookmarks.bookmark(rule_bookmark,reader);
+    })
+}
+
+/// Generate the statements that apply `transition` to the lexer's stack of groups.
+pub fn transition_statements(transition:&Transition) -> Vec<Stmt> {
+    let group_literal = |group:&group::Identifier| {
+        let id:usize = (*group).into();
+        Literal::usize_unsuffixed(id)
+    };
+    match transition {
+        Transition::Push(group) => {
+            let group = group_literal(group);
+            parse_quote! {
+                self.push_state(flexer::group::Identifier::from(#group));
+            }
+        }
+        Transition::Pop => parse_quote! {
+            self.pop_state();
+        },
+        Transition::PopUntil(group) => {
+            let group = group_literal(group);
+            parse_quote! {
+                self.pop_states_until(flexer::group::Identifier::from(#group));
+            }
+        }
+        Transition::Replace(group) => {
+            let group = group_literal(group);
+            parse_quote! {
+                self.pop_state();
+                self.push_state(flexer::group::Identifier::from(#group));
+            }
+        }
+    }
+}
 
+/// Generate the guard function for a given lexer state, if its rule is guarded.
+pub fn guard_for_state(state:&State) -> Result<Option<ImplItem>,GenError> {
+    match (state.guard_name(),state.guard.as_ref()) {
+        (Some(name),Some(guard)) => {
+            let guard_name = str_to_ident(name)?;
+            let code:Expr  = match parse_str(guard.as_str()) {
+                Ok(expr) => expr,
+                Err(_)   => return Err(GenError::BadExpression(guard.clone()))
+            };
             let tree:ImplItem = parse_quote! {
-                fn #rule_name<R:LazyReader>(&mut self, reader:&mut R) {
+                fn #guard_name(&self) -> bool {
                     #code
                 }
             };
-            Ok(tree)
+            Ok(Some(tree))
         }
+        _ => Ok(None)
     }
 }
 
@@ -433,6 +874,207 @@ pub fn has_reader_arg(expr:&Expr) -> bool {
 
 
 
+// ================
+// === Encoding ===
+// ================
+
+/// The encoding of the input that a generated lexer runs over.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum Encoding {
+    /// The lexer runs over decoded unicode code points, and accepts any [`MatchReader`].
+    Unicode,
+    /// The lexer runs directly over UTF-8 code units, and only accepts a [`Utf8Reader`].
+    ///
+    /// The automata for each group are lowered into automata over bytes, so no decoding takes
+    /// place while lexing. The text of each match is still valid UTF-8, as the lexer can only
+    /// split its input between encoded code points.
+    Utf8,
+}
+
+impl Encoding {
+    /// The trait bound on the reader accepted by a lexer running over this encoding.
+    fn reader_bound(self) -> Path {
+        match self {
+            Encoding::Unicode => parse_quote!(MatchReader),
+            Encoding::Utf8    => parse_quote!(Utf8Reader),
+        }
+    }
+
+    /// Lower the `nfa` over code points to an automaton over this encoding.
+    fn lower(self, nfa:NFA) -> NFA {
+        match self {
+            Encoding::Unicode => nfa,
+            Encoding::Utf8    => nfa.to_utf8(),
+        }
+    }
+}
+
+
+
+// ======================
+// === CodegenOptions ===
+// ======================
+
+/// Options that control the code generated by [`specialize_with`].
+///
+/// The default options generate a lexer over unicode code points, with tracing enabled, whose
+/// entry point is `pub fn run`.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct CodegenOptions {
+    /// The encoding of the input that the generated lexer runs over.
+    pub encoding : Encoding,
+    /// Whether the generated lexer logs its progress through the input, character by character.
+    pub tracing : bool,
+    /// The inlining hint attached to the generated functions other than the entry point.
+    pub inline : Inline,
+    /// The visibility of the entry point of the generated lexer.
+    pub visibility : Visibility,
+    /// The name of the entry point of the generated lexer.
+    pub run_name : String,
+    /// Whether the generated lexer counts the hits of each of its rules.
+    ///
+    /// See [`crate::coverage`] for the report of these counts.
+    pub coverage : bool,
+    /// Whether the generated lexer records the work that it does in each group.
+    ///
+    /// See [`crate::profile`] for what is recorded.
+    pub profiling : bool,
+    /// Whether the generated lexer can be run nested in another lexer.
+    ///
+    /// See [`crate::group::rule::Rule::nested`] for how input is delegated to a nested lexer.
+    pub nestable : bool,
+}
+
+impl CodegenOptions {
+    /// Set the encoding of the input that the generated lexer runs over.
+    pub fn with_encoding(mut self, encoding:Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Set whether the generated lexer logs its progress through the input.
+    pub fn with_tracing(mut self, tracing:bool) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
+    /// Set the inlining hint attached to the generated functions.
+    pub fn with_inline(mut self, inline:Inline) -> Self {
+        self.inline = inline;
+        self
+    }
+
+    /// Set the visibility of the entry point of the generated lexer.
+    pub fn with_visibility(mut self, visibility:Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Set the name of the entry point of the generated lexer.
+    pub fn with_run_name(mut self, run_name:impl Into<String>) -> Self {
+        self.run_name = run_name.into();
+        self
+    }
+
+    /// Set whether the generated lexer counts the hits of each of its rules.
+    pub fn with_coverage(mut self, coverage:bool) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    /// Set whether the generated lexer records the work that it does in each group.
+    pub fn with_profiling(mut self, profiling:bool) -> Self {
+        self.profiling = profiling;
+        self
+    }
+
+    /// Set whether the generated lexer can be run nested in another lexer.
+    pub fn with_nestable(mut self, nestable:bool) -> Self {
+        self.nestable = nestable;
+        self
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        let encoding   = Encoding::Unicode;
+        let tracing    = true;
+        let inline     = Inline::Auto;
+        let visibility = Visibility::Public;
+        let run_name   = "run".into();
+        let coverage   = false;
+        let profiling  = false;
+        let nestable   = false;
+        CodegenOptions{encoding,tracing,inline,visibility,run_name,coverage,profiling,nestable}
+    }
+}
+
+
+
+// ==============
+// === Inline ===
+// ==============
+
+/// The inlining hint attached to generated functions.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum Inline {
+    /// No hint is given, leaving the decision to the compiler.
+    Auto,
+    /// The functions are marked `#[inline]`.
+    Hint,
+    /// The functions are marked `#[inline(always)]`.
+    Always,
+    /// The functions are marked `#[inline(never)]`.
+    Never,
+}
+
+impl Inline {
+    /// The attribute that gives this hint, if any.
+    fn attribute(self) -> Option<Attribute> {
+        match self {
+            Inline::Auto   => None,
+            Inline::Hint   => Some(parse_quote!(#[inline])),
+            Inline::Always => Some(parse_quote!(#[inline(always)])),
+            Inline::Never  => Some(parse_quote!(#[inline(never)])),
+        }
+    }
+}
+
+
+
+// ==================
+// === Visibility ===
+// ==================
+
+/// The visibility of the entry point of a generated lexer.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum Visibility {
+    /// The entry point is `pub`.
+    Public,
+    /// The entry point is `pub(crate)`.
+    Crate,
+    /// The entry point is private to the module containing the lexer.
+    Private,
+}
+
+
+// === Trait Impls ===
+
+impl ToTokens for Visibility {
+    fn to_tokens(&self, tokens:&mut proc_macro2::TokenStream) {
+        match self {
+            Visibility::Public  => quote!(pub).to_tokens(tokens),
+            Visibility::Crate   => quote!(pub(crate)).to_tokens(tokens),
+            Visibility::Private => (),
+        }
+    }
+}
+
+
+
 // ================
 // === GenError ===
 // ================
@@ -539,3 +1181,194 @@ pub fn show_code(tokens:&impl ToTokens) -> String {
 }
 
 
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Visibility;
+
+    use crate::automata::pattern::Pattern;
+    use crate::prelude::reader::BookmarkManager;
+
+    /// A lexer state with a single group, matching a single word.
+    struct WordState {
+        registry  : group::Registry,
+        bookmarks : BookmarkManager,
+    }
+
+    impl flexer::State for WordState {
+        fn new(_logger:&impl AnyLogger) -> Self {
+            let mut registry = group::Registry::default();
+            let root         = registry.define_group("ROOT",None);
+            let word         = Pattern::range('a'..='z').many1();
+            registry.group_mut(root).create_rule(&word,"self.on_word(reader)");
+            let bookmarks = default();
+            WordState{registry,bookmarks}
+        }
+
+        fn initial_state(&self) -> group::Identifier {
+            self.registry.all()[0].id
+        }
+
+        fn groups(&self) -> &group::Registry {
+            &self.registry
+        }
+
+        fn groups_mut(&mut self) -> &mut group::Registry {
+            &mut self.registry
+        }
+
+        fn bookmarks(&self) -> &BookmarkManager {
+            &self.bookmarks
+        }
+
+        fn bookmarks_mut(&mut self) -> &mut BookmarkManager {
+            &mut self.bookmarks
+        }
+
+        fn specialize(&self) -> Result<String,GenError> {
+            specialize(self,"WordLexer","Output")
+        }
+    }
+
+    fn word_state() -> WordState {
+        <WordState as flexer::State>::new(&crate::prelude::logger::Silent)
+    }
+
+    fn run_function_for(options:&CodegenOptions) -> ImplItemMethod {
+        match run_function("Output",options).unwrap() {
+            ImplItem::Method(method) => method,
+            _                        => panic!("The run function should be a method."),
+        }
+    }
+
+    fn has_visibility(run:&ImplItemMethod, visibility:Visibility) -> bool {
+        run.vis.to_token_stream().to_string() == visibility.to_token_stream().to_string()
+    }
+
+    #[test]
+    fn test_default_entry_point() {
+        let run = run_function_for(&default());
+        assert_eq!(run.sig.ident,"run");
+        assert!(has_visibility(&run,Visibility::Public));
+    }
+
+    #[test]
+    fn test_configured_entry_point() {
+        let options = CodegenOptions::default()
+            .with_visibility(Visibility::Crate)
+            .with_run_name("lex");
+        let run = run_function_for(&options);
+        assert_eq!(run.sig.ident,"lex");
+        assert!(has_visibility(&run,Visibility::Crate));
+        let options = options.with_visibility(Visibility::Private);
+        let run     = run_function_for(&options);
+        assert!(has_visibility(&run,Visibility::Private));
+    }
+
+    #[test]
+    fn test_bad_entry_point_name() {
+        let options = CodegenOptions::default().with_run_name("not a name");
+        let result  = run_function("Output",&options);
+        assert_eq!(result,Err(GenError::BadIdentifier("not a name".into())));
+    }
+
+    #[test]
+    fn test_tracing() {
+        let traced   = show_code(&run_current_state_function(&default(),false));
+        let options  = CodegenOptions::default().with_tracing(false);
+        let untraced = show_code(&run_current_state_function(&options,false));
+        assert!(traced.contains("logger . debug"));
+        assert!(!untraced.contains("logger . debug"));
+        assert!(!untraced.contains("logger . info"));
+    }
+
+    #[test]
+    fn test_line_tracking() {
+        let tracked   = show_code(&run_current_state_function(&default(),true));
+        let untracked = show_code(&run_current_state_function(&default(),false));
+        assert!(tracked.contains("self . previous_char = Some (char) ;"));
+        assert!(!untracked.contains("previous_char"));
+    }
+
+    #[test]
+    fn test_anchored_transitions() {
+        let mut registry = group::Registry::default();
+        let root         = registry.define_group("ROOT",None);
+        let pattern      = Pattern::line_start() >> Pattern::char('#');
+        registry.group_mut(root).create_rule(&pattern,"self.on_comment(reader)");
+        let nfa     = registry.to_nfa_from(root);
+        let mut dfa = DFA::from(&nfa);
+        let code    = transitions_for_dfa(&mut dfa,0).unwrap().iter().map(show_code).join("\n");
+        let jump    = "if self . at_line_start (reader) { return self . state_0_to_1 (reader) }";
+        assert!(code.contains(jump));
+        assert!(!code.contains("at_line_end"));
+    }
+
+    #[test]
+    fn test_inline_hints() {
+        let state    = word_state();
+        let options  = CodegenOptions::default().with_inline(Inline::Always);
+        let code     = specialize_with(&state,"WordLexer","Output",&options).unwrap();
+        let hints    = code.matches("# [inline (always)]").count();
+        let methods  = code.matches(" fn ").count();
+        assert_eq!(hints,methods - 1);
+        let code = specialize_with(&state,"WordLexer","Output",&default()).unwrap();
+        assert!(!code.contains("inline"));
+    }
+
+    #[test]
+    fn test_coverage() {
+        let state = word_state();
+        let rules = coverage_table(flexer::State::groups(&state));
+        let rule  = ("ROOT".to_string(),"group_0_rule_0".to_string(),"self.on_word(reader)".into());
+        assert_eq!(rules,vec![rule]);
+        let options = CodegenOptions::default().with_coverage(true);
+        let code    = specialize_with(&state,"WordLexer","Output",&options).unwrap();
+        assert!(code.contains("Self :: coverage_rule_hits () [0] . fetch_add"));
+        assert!(code.contains("pub fn rule_coverage () -> flexer :: coverage :: Report"));
+        let code = specialize_with(&state,"WordLexer","Output",&default()).unwrap();
+        assert!(!code.contains("coverage"));
+    }
+
+    #[test]
+    fn test_profiling() {
+        let state   = word_state();
+        let options = CodegenOptions::default().with_profiling(true);
+        let code    = specialize_with(&state,"WordLexer","Output",&options).unwrap();
+        assert!(code.contains("self . profile_step (profiled_group , step_start . elapsed ()) ;"));
+        assert!(code.contains("self . profile_character (profiled_group) ;"));
+        assert!(code.contains("self . profile_rule (group , callback_start . elapsed ()) ;"));
+        let code = specialize_with(&state,"WordLexer","Output",&default()).unwrap();
+        assert!(!code.contains("profile"));
+    }
+
+    #[test]
+    fn test_nestable() {
+        let state   = word_state();
+        let options = CodegenOptions::default().with_nestable(true);
+        let code    = specialize_with(&state,"WordLexer","Output",&options).unwrap();
+        assert!(code.contains("pub fn run_nested < R : MatchReader >"));
+        let code = specialize_with(&state,"WordLexer","Output",&default()).unwrap();
+        assert!(!code.contains("run_nested"));
+    }
+
+    #[test]
+    fn test_delegation() {
+        let mut rule  = State::default().named("group_0_rule_0");
+        rule.callback = "self.on_numbers(reader,nested)".into();
+        rule.nested   = Some("self.inner".into());
+        let code      = show_code(&rule_for_state(&rule).unwrap());
+        assert!(code.contains("R : MatchReader"));
+        assert!(code.contains("let nested = self . inner . run_nested (reader) ;"));
+        rule.nested = Some("not an expression +".into());
+        let result  = rule_for_state(&rule);
+        assert_eq!(result,Err(GenError::BadExpression("not an expression +".into())));
+    }
+}
@@ -57,6 +57,21 @@ impl Registry {
         group.create_rule(pattern,callback.as_ref());
     }
 
+    /// Creates a rule that matches `pattern` for the group identified by `group_id`, but that only
+    /// applies when `guard` holds.
+    ///
+    /// Panics if `group_id` refers to a nonexistent group.
+    pub fn create_guarded_rule
+    ( &mut self
+    , group    : Identifier
+    , pattern  : &Pattern
+    , guard    : impl AsRef<str>
+    , callback : impl AsRef<str>
+    ) {
+        let group = self.group_mut(group);
+        group.create_guarded_rule(pattern,guard.as_ref(),callback.as_ref());
+    }
+
     /// Associates the provided `rule` with the group identified by `group_id`.
     ///
     /// Panics if `group_id` refers to a nonexistent group.
@@ -103,22 +118,48 @@ impl Registry {
     ///
     /// Returns `None` if the group does not exist, or if the conversion fails.
     pub fn to_nfa_from(&self, group:Identifier) -> NFA {
-        let group     = self.group(group);
-        let mut nfa   = NFA::default();
-        let start     = nfa.new_state();
-        let build     = |rule:&Rule| nfa.new_pattern(start,&rule.pattern);
-        let rules     = self.rules_for(group.id);
-        let callbacks = rules.iter().map(|r| r.callback.clone()).collect_vec();
-        let states    = rules.into_iter().map(build).collect_vec();
-        let end       = nfa.new_state();
+        let group       = self.group(group);
+        let mut nfa     = NFA::default();
+        let start       = nfa.new_state();
+        let build       = |rule:&Rule| nfa.new_pattern(start,&rule.pattern);
+        let rules       = self.rules_for(group.id);
+        let callbacks   = rules.iter().map(|r| r.callback.clone()).collect_vec();
+        let guards      = rules.iter().map(|r| r.guard.clone()).collect_vec();
+        let transitions = rules.iter().map(|r| r.transition).collect_vec();
+        let nested      = rules.iter().map(|r| r.nested.clone()).collect_vec();
+        let states      = rules.into_iter().map(build).collect_vec();
+        let end         = nfa.new_state();
         for (ix,state) in states.into_iter().enumerate() {
-            nfa.states[state.id].name     = Some(group.callback_name(ix));
-            nfa.states[state.id].callback = callbacks.get(ix).unwrap().clone();
+            nfa.states[state.id].name             = Some(group.callback_name(ix));
+            nfa.states[state.id].callback         = callbacks.get(ix).unwrap().clone();
+            nfa.states[state.id].guard            = guards.get(ix).unwrap().clone();
+            nfa.states[state.id].group_transition = *transitions.get(ix).unwrap();
+            nfa.states[state.id].nested           = nested.get(ix).unwrap().clone();
             nfa.connect(state,end);
         }
         nfa
     }
 
+    /// Collects the groups that can become active from the group identified by `group`, through the
+    /// declarative [`Rule::transition`]s of their rules.
+    ///
+    /// The groups are returned in the order in which they are discovered, starting with `group`
+    /// itself. Transitions made directly by the callbacks of rules are not taken into account.
+    pub fn reachable_from(&self, group:Identifier) -> Vec<Identifier> {
+        let mut reachable = vec![group];
+        let mut ix        = 0;
+        while let Some(&current) = reachable.get(ix) {
+            let transitions = self.rules_for(current).into_iter().filter_map(|r| r.transition);
+            for target in transitions.filter_map(|t| t.target()) {
+                if !reachable.contains(&target) {
+                    reachable.push(target);
+                }
+            }
+            ix += 1;
+        }
+        reachable
+    }
+
     /// Generates the next group identifier for this registry.
     fn next_id(&self) -> Identifier {
         let val = self.groups.len();
@@ -182,10 +223,12 @@ impl Into<usize> for Identifier {
 /// the rules are matched, with the first callback being triggered.
 ///
 /// Whenever a [`rule.pattern`](Rule::pattern) from the active group is matched against part of the
-/// input, the associated [`rule.callback`](Rule::callback) is executed. This callback may exit the
-/// current group or even enter a new one. As a result, groups allow us to elegantly model a
-/// situation where certain parts of a program (e.g. within a string literal) have very different
-/// lexing rules than other portions of a program (e.g. the body of a function).
+/// input, the associated [`rule.callback`](Rule::callback) is executed, provided that the
+/// [`rule.guard`](Rule::guard) (if any) holds. This callback may exit the current group or even
+/// enter a new one. As a result, groups allow us to elegantly model a situation where certain parts
+/// of a program (e.g. within a string literal) have very different lexing rules than other portions
+/// of a program (e.g. the body of a function). Guards instead allow a single group to depend on
+/// smaller pieces of the lexer's state, without introducing a group for each combination.
 #[derive(Clone,Debug,Default)]
 pub struct Group {
     /// A unique identifier for the group.
@@ -220,6 +263,13 @@ impl Group {
         self.rules.push(rule)
     }
 
+    /// Creates a new rule that only applies when `guard` holds.
+    pub fn create_guarded_rule(&mut self, pattern:&Pattern, guard:&str, code:&str) {
+        let pattern_clone = pattern.clone();
+        let rule          = Rule::new(pattern_clone,code).guarded_by(guard);
+        self.rules.push(rule)
+    }
+
     /// The canonical name for a given rule.
     pub fn callback_name(&self, rule_ix:usize) -> String {
         format!("group_{}_rule_{}",self.id.0,rule_ix)
@@ -252,11 +302,14 @@ impl Display for Group {
 pub mod tests {
     extern crate test;
 
+    use crate::automata::dfa::DFA;
     use crate::automata::nfa;
     use crate::automata::pattern::Pattern;
     use crate::group::Group;
+    use crate::group::Identifier;
     use crate::group::Registry;
     use crate::group::rule::Rule;
+    use crate::group::rule::Transition;
 
     use std::default::Default;
     use test::Bencher;
@@ -294,6 +347,38 @@ pub mod tests {
         group.into()
     }
 
+    fn guarded_letters() -> Registry {
+        let     letter = Pattern::range('a'..='z');
+        let mut group  = Group::default();
+        group.add_rule(Rule::new(letter.clone(),"").guarded_by("self.guard()"));
+        group.add_rule(Rule::new(letter,""));
+        group.into()
+    }
+
+    fn guarded_word() -> Registry {
+        let     letter = Pattern::char('a');
+        let     word   = Pattern::all_of("ab");
+        let mut group  = Group::default();
+        group.add_rule(Rule::new(letter,""));
+        group.add_rule(Rule::new(word,"").guarded_by("self.guard()"));
+        group.into()
+    }
+
+    fn nested_groups() -> (Registry,Vec<Identifier>) {
+        let mut registry = Registry::default();
+        let root         = registry.define_group("ROOT",None);
+        let parent       = registry.define_group("PARENT",None);
+        let child        = registry.define_group("CHILD",Some(parent));
+        let last         = registry.define_group("LAST",None);
+        let unreachable  = registry.define_group("UNREACHABLE",None);
+        let rule         = |transition| Rule::new(Pattern::char('a'),"").with_transition(transition);
+        registry.add_rule(root,rule(Transition::Push(child)));
+        registry.add_rule(parent,rule(Transition::Replace(last)));
+        registry.add_rule(last,rule(Transition::Pop));
+        registry.add_rule(unreachable,rule(Transition::Push(root)));
+        (registry,vec![root,child,last])
+    }
+
     fn complex_rules(count:usize) -> Registry {
         let mut group   = Group::default();
         for ix in 0..count {
@@ -329,6 +414,39 @@ pub mod tests {
         assert_eq!(letter_and_spaces().to_nfa_from(default()),expected);
     }
 
+    #[test]
+    fn test_to_dfa_guarded() {
+        let nfa      = guarded_letters().to_nfa_from(default());
+        let dfa      = DFA::from(&nfa);
+        let callback = dfa.callbacks.iter().flatten().next().expect("A callback for the letter.");
+        let fallback = callback.fallback.as_ref().expect("A fallback for the guarded rule.");
+        assert_eq!(callback.code,"group_0_rule_0");
+        assert_eq!(callback.guard,Some("group_0_rule_0_guard".into()));
+        assert_eq!(fallback.code,"group_0_rule_1");
+        assert_eq!(fallback.guard,None);
+        assert_eq!(fallback.fallback,None);
+    }
+
+    #[test]
+    fn test_to_dfa_guarded_shorter_match() {
+        let nfa     = guarded_word().to_nfa_from(default());
+        let mut dfa = DFA::from(&nfa);
+        dfa.fall_back_to_shorter_matches();
+        let guarded  = dfa.callbacks.iter().flatten().find(|callback| callback.guard.is_some());
+        let callback = guarded.expect("A callback for the word.");
+        let fallback = callback.fallback.as_ref().expect("A fallback to the letter.");
+        assert_eq!(callback.code,"group_0_rule_1");
+        assert_eq!(fallback.code,"group_0_rule_0");
+        assert_eq!(fallback.guard,None);
+        assert!(fallback.shorter);
+    }
+
+    #[test]
+    fn test_reachable_from() {
+        let (registry,expected) = nested_groups();
+        assert_eq!(registry.reachable_from(expected[0]),expected);
+    }
+
     #[bench]
     fn bench_to_nfa_newline(bencher:&mut Bencher) {
         bencher.iter(|| newline().to_nfa_from(default()))
@@ -4,6 +4,7 @@
 //! callback.
 
 use crate::automata::pattern::Pattern;
+use crate::group;
 
 
 
@@ -24,11 +25,86 @@ pub struct Rule {
     /// a [`crate::Flexer`] instance. To this end, the code you provide as a string must be valid in
     /// that context.
     pub callback:String,
+
+    /// An optional guard on the rule, containing a rust expression of type `bool` as a
+    /// [`std::string::String`].
+    ///
+    /// The guard is evaluated in a method taking `&self` on your lexer whenever
+    /// [`Rule::pattern`] matches, before the match is taken. If it evaluates to `false`, the rule
+    /// is skipped in favour of the next rule of the group that matches the same input, or
+    /// otherwise of the rules that matched a shorter prefix of it.
+    pub guard:Option<String>,
+
+    /// An optional change to the lexer's stack of groups, applied after [`Rule::callback`] has
+    /// been executed.
+    ///
+    /// Unlike changes made by the callback itself, these transitions are visible to static analyses
+    /// of the groups, such as [`group::Registry::reachable_from`].
+    pub transition:Option<Transition>,
+
+    /// An optional nested lexer that the input following the match is delegated to before
+    /// [`Rule::callback`] is executed, containing a rust expression that evaluates to the nested
+    /// lexer as a [`std::string::String`].
+    ///
+    /// The nested lexer must be generated with [`crate::generate::CodegenOptions::with_nestable`].
+    /// It lexes the input until it calls [`crate::Flexer::finish_nested`], or until the input ends,
+    /// after which the callback can refer to its [`crate::LexingResult`] as `nested`.
+    pub nested:Option<String>,
 }
 
 impl Rule {
     /// Creates a new rule.
     pub fn new(pattern:Pattern, callback:impl Into<String>) -> Self {
-        Rule{pattern,callback:callback.into()}
+        Rule{pattern,callback:callback.into(),guard:None,transition:None,nested:None}
+    }
+
+    /// Updater for field `guard`. Returns updated rule.
+    pub fn guarded_by(mut self, guard:impl Into<String>) -> Self {
+        self.guard = Some(guard.into());
+        self
+    }
+
+    /// Updater for field `transition`. Returns updated rule.
+    pub fn with_transition(mut self, transition:Transition) -> Self {
+        self.transition = Some(transition);
+        self
+    }
+
+    /// Updater for field `nested`. Returns updated rule.
+    pub fn delegating_to(mut self, nested:impl Into<String>) -> Self {
+        self.nested = Some(nested.into());
+        self
+    }
+}
+
+
+
+// ==================
+// === Transition ===
+// ==================
+
+/// A declarative change to the lexer's stack of groups.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum Transition {
+    /// Enter the provided group.
+    Push(group::Identifier),
+    /// Leave the current group.
+    Pop,
+    /// Leave groups until the provided group is the current one.
+    PopUntil(group::Identifier),
+    /// Leave the current group, entering the provided group in its place.
+    Replace(group::Identifier),
+}
+
+impl Transition {
+    /// The group that this transition enters, if it enters one.
+    ///
+    /// A transition that only leaves groups returns to a group that has already been entered.
+    pub fn target(&self) -> Option<group::Identifier> {
+        match self {
+            Transition::Push(group)    => Some(*group),
+            Transition::Replace(group) => Some(*group),
+            _                          => None,
+        }
     }
 }
@@ -0,0 +1,543 @@
+//! Input sources for the flexer that are able to expose matched text without copying it.
+//!
+//! The generated lexers read through the [`MatchReader`] interface, which extends the lazy reader
+//! with the ability to hand out the text of a match as [`MatchText`]. Readers backed by an
+//! in-memory buffer (such as [`StrReader`]) return slices of that buffer, while streaming readers
+//! fall back to accumulating the match character by character.
+//!
+//! Lexers generated for [`crate::generate::Encoding::Utf8`] run over the UTF-8 code units of their
+//! input rather than over decoded characters, and hence read through a [`Utf8Reader`] such as the
+//! [`ByteReader`].
+
+use crate::prelude::*;
+
+use crate::prelude::reader::BookmarkManager;
+use crate::prelude::reader::Error;
+use crate::prelude::reader::decoder;
+use crate::prelude::reader::decoder::Decoder;
+
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::sync::Arc;
+
+
+
+// =================
+// === MatchText ===
+// =================
+
+/// The text of a single match of the lexer.
+///
+/// Matches made on in-memory input share the input buffer, and only ever allocate when they are
+/// converted into an owned [`String`].
+#[derive(Clone)]
+pub enum MatchText {
+    /// A match that is a slice of a shared input buffer.
+    Shared {
+        /// The input buffer.
+        source : Arc<str>,
+        /// The byte range of the match in `source`.
+        range : Range<usize>,
+    },
+    /// A match that owns its text.
+    Owned(String),
+}
+
+impl MatchText {
+    /// Get the text of the match as a string slice.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Shared{source,range} => &source[range.clone()],
+            Self::Owned(text)          => text.as_str(),
+        }
+    }
+
+    /// Check whether the match shares its text with the input buffer.
+    pub fn is_shared(&self) -> bool {
+        match self {
+            Self::Shared{..} => true,
+            Self::Owned(_)   => false,
+        }
+    }
+
+    /// Get the text of the bytes in `range` of this match.
+    ///
+    /// The slice of a shared match shares the same input buffer, while the slice of an owned match
+    /// copies its text. Panics if `range` does not lie on character boundaries within the match.
+    pub fn slice(&self, range:Range<usize>) -> MatchText {
+        let text = &self.as_str()[range.clone()];
+        match self {
+            Self::Shared{source,range:whole} => {
+                let source = source.clone();
+                let start  = whole.start + range.start;
+                let end    = whole.start + range.end;
+                Self::Shared{source,range:start..end}
+            }
+            Self::Owned(_) => Self::Owned(text.into()),
+        }
+    }
+
+    /// Append the text of `other` to this match.
+    ///
+    /// When `other` directly follows this match in the same input buffer, the match is extended
+    /// to cover it without copying, and an empty match simply becomes `other`. Otherwise, the match
+    /// takes an owned copy of its text first.
+    pub fn push(&mut self, other:&MatchText) {
+        if other.is_empty() {
+            return
+        }
+        if self.is_empty() {
+            *self = other.clone();
+            return
+        }
+        if let (Self::Shared{source,range},Self::Shared{source:other_source,range:other_range}) =
+            (&mut *self,other) {
+            if Arc::ptr_eq(source,other_source) && range.end == other_range.start {
+                range.end = other_range.end;
+                return
+            }
+        }
+        let mut text = String::from(mem::take(self));
+        text.push_str(other);
+        *self = Self::Owned(text);
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Default for MatchText {
+    fn default() -> Self {
+        Self::Owned(default())
+    }
+}
+
+impl Deref for MatchText {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for MatchText {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Debug for MatchText {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(),f)
+    }
+}
+
+impl Display for MatchText {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self.as_str(),f)
+    }
+}
+
+impl PartialEq for MatchText {
+    fn eq(&self, other:&Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for MatchText {}
+
+impl PartialEq<str> for MatchText {
+    fn eq(&self, other:&str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for MatchText {
+    fn eq(&self, other:&&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl From<String> for MatchText {
+    fn from(text:String) -> Self {
+        Self::Owned(text)
+    }
+}
+
+impl From<&str> for MatchText {
+    fn from(text:&str) -> Self {
+        Self::Owned(text.into())
+    }
+}
+
+impl From<MatchText> for String {
+    fn from(text:MatchText) -> Self {
+        match text {
+            MatchText::Shared{source,range} => source[range].into(),
+            MatchText::Owned(text)          => text,
+        }
+    }
+}
+
+
+
+// ===================
+// === MatchReader ===
+// ===================
+
+/// A lazy reader that is able to expose the text of the current match.
+///
+/// The default implementation accumulates the match in the reader's result, and is hence suitable
+/// for any reader. Readers with access to the whole input should override both methods to avoid
+/// copying the input.
+pub trait MatchReader : LazyReader {
+    /// Record that `char` is part of the current match.
+    fn append_match(&mut self, char:char) {
+        self.append_result(char)
+    }
+
+    /// Return the text of the current match, and begin a new match at the current character.
+    fn pop_match(&mut self) -> MatchText {
+        MatchText::Owned(self.pop_result())
+    }
+}
+
+
+// === Trait Impls ===
+
+impl<D:Decoder,R:reader::Read<Item=D::Word>> MatchReader for Reader<D,R> {}
+
+
+
+// ====================
+// === MemoryReader ===
+// ====================
+
+/// The unit in which a [`MemoryReader`] presents its input to the lexer.
+pub trait ReadUnit {
+    /// Read the unit at the start of `bytes`, which begin at a unit boundary of a UTF-8 input.
+    ///
+    /// Returns `None` if `bytes` is empty.
+    fn read(bytes:&[u8]) -> Option<decoder::Char<Error>>;
+}
+
+/// A reader over a UTF-8 input that is held entirely in memory.
+///
+/// The reader walks over the bytes of its input in the units defined by `Unit`. Matches made on
+/// this reader are slices of its input, meaning that the flexer does not copy the text of a match
+/// unless it is explicitly asked to.
+#[derive(Clone,Debug)]
+pub struct MemoryReader<Unit> {
+    /// The input.
+    source : Arc<str>,
+    /// The byte offset just past the current unit.
+    offset : usize,
+    /// The byte offset at which the current match begins.
+    match_start : usize,
+    /// The current unit.
+    character : decoder::Char<Error>,
+    /// Always empty, as this reader takes its matches directly from `source`.
+    result : String,
+    /// The unit in which the input is read.
+    unit : PhantomData<Unit>,
+}
+
+impl<Unit:ReadUnit> MemoryReader<Unit> {
+    /// Create a new reader over `source`.
+    pub fn new(source:impl Into<Arc<str>>) -> Self {
+        let source      = source.into();
+        let offset      = 0;
+        let match_start = 0;
+        let character   = decoder::Char{char:Err(Error::EOF),size:0};
+        let result      = default();
+        let unit        = PhantomData;
+        Self{source,offset,match_start,character,result,unit}
+    }
+
+    /// Get the input of the reader.
+    pub fn source(&self) -> &Arc<str> {
+        &self.source
+    }
+
+    /// The byte offset of the current unit in the input.
+    fn current_position(&self) -> usize {
+        self.offset - self.character.size
+    }
+}
+
+
+// === Trait Impls ===
+
+impl<Unit:ReadUnit> LazyReader for MemoryReader<Unit> {
+    fn next_char(&mut self, _bookmarks:&mut BookmarkManager) -> Result<char,Error> {
+        let bytes      = &self.source.as_bytes()[self.offset..];
+        let eof        = decoder::Char{char:Err(Error::EOF),size:0};
+        self.character = Unit::read(bytes).unwrap_or(eof);
+        self.offset   += self.character.size;
+        self.character.char
+    }
+
+    fn advance_char(&mut self, bookmarks:&mut BookmarkManager) {
+        let _ = self.next_char(bookmarks);
+    }
+
+    fn character(&self) -> decoder::Char<Error> {
+        self.character
+    }
+
+    fn finished(&self, _bookmarks:&BookmarkManager) -> bool {
+        self.empty()
+    }
+
+    fn empty(&self) -> bool {
+        self.offset >= self.source.len()
+    }
+
+    fn fill(&mut self, _bookmarks:&mut BookmarkManager) {}
+
+    fn max_possible_rewind_len(&self, _bookmarks:&BookmarkManager) -> usize {
+        self.offset
+    }
+
+    fn append_result(&mut self, _char:char) {}
+
+    fn pop_result(&mut self) -> String {
+        self.pop_match().into()
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn result(&self) -> &String {
+        &self.result
+    }
+
+    fn result_mut(&mut self) -> &mut String {
+        &mut self.result
+    }
+
+    fn buffer_len(&self) -> usize {
+        self.source.len()
+    }
+
+    fn set_offset(&mut self, offset:usize) {
+        self.offset = offset;
+    }
+
+    fn truncate_match(&mut self, len:usize) {
+        // This is called after rewinding, so a rewind to before the start of the current match
+        // begins the match again from the rewound position.
+        self.match_start = self.match_start.min(self.offset);
+        self.result.truncate(len)
+    }
+}
+
+impl<Unit:ReadUnit> MatchReader for MemoryReader<Unit> {
+    fn append_match(&mut self, _char:char) {}
+
+    fn pop_match(&mut self) -> MatchText {
+        let end          = self.current_position();
+        let range        = self.match_start..end;
+        let source       = self.source.clone();
+        self.match_start = end;
+        MatchText::Shared{source,range}
+    }
+}
+
+
+
+// =================
+// === StrReader ===
+// =================
+
+/// A reader over a UTF-8 input that is held entirely in memory, presenting it as characters.
+pub type StrReader = MemoryReader<Characters>;
+
+/// The unit of a [`StrReader`], which reads its input one character at a time.
+#[derive(Clone,Copy,Debug)]
+pub struct Characters;
+
+
+// === Trait Impls ===
+
+impl ReadUnit for Characters {
+    fn read(bytes:&[u8]) -> Option<decoder::Char<Error>> {
+        let size = match *bytes.first()? {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            _           => 4,
+        };
+        let char = std::str::from_utf8(bytes.get(..size)?).ok()?.chars().next()?;
+        Some(decoder::Char{char:Ok(char),size})
+    }
+}
+
+
+
+// ==================
+// === Utf8Reader ===
+// ==================
+
+/// A reader that presents its input as UTF-8 code units instead of as characters.
+///
+/// Each byte of the input is presented as the character with the same value, so that the
+/// character's `u32` representation is the byte itself. Lexers generated for
+/// [`crate::generate::Encoding::Utf8`] only accept readers of this kind.
+pub trait Utf8Reader : MatchReader {}
+
+
+
+// ==================
+// === ByteReader ===
+// ==================
+
+/// A reader over the UTF-8 code units of an input that is held entirely in memory.
+///
+/// As the input is not decoded, the reader never produces [`Error::InvalidChar`]. Matches made on
+/// this reader are slices of its input, exactly as for the [`StrReader`].
+pub type ByteReader = MemoryReader<CodeUnits>;
+
+/// The unit of a [`ByteReader`], which reads its input one UTF-8 code unit at a time.
+#[derive(Clone,Copy,Debug)]
+pub struct CodeUnits;
+
+
+// === Trait Impls ===
+
+impl ReadUnit for CodeUnits {
+    fn read(bytes:&[u8]) -> Option<decoder::Char<Error>> {
+        let byte = *bytes.first()?;
+        Some(decoder::Char{char:Ok(char::from(byte)),size:1})
+    }
+}
+
+impl Utf8Reader for ByteReader {}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::prelude::reader::decoder::DecoderUTF8;
+
+    /// Consume `count` characters from `reader` as part of the current match.
+    fn consume(reader:&mut impl MatchReader, bookmarks:&mut BookmarkManager, count:usize) {
+        for _ in 0..count {
+            if let Ok(char) = reader.character().char {
+                reader.append_match(char);
+            }
+            reader.advance_char(bookmarks);
+        }
+    }
+
+    #[test]
+    fn str_reader_shares_matches() {
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = StrReader::new("ab λc");
+        reader.advance_char(&mut bookmarks);
+        consume(&mut reader,&mut bookmarks,2);
+        let first = reader.pop_match();
+        consume(&mut reader,&mut bookmarks,3);
+        let second = reader.pop_match();
+        assert!(first.is_shared());
+        assert_eq!(first,"ab");
+        assert_eq!(second," λc");
+        assert!(reader.character().is_eof());
+        assert!(reader.finished(&bookmarks));
+    }
+
+    #[test]
+    fn str_reader_rewinds_matches() {
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = StrReader::new("abcd");
+        let bookmark      = bookmarks.rule_bookmark;
+        reader.advance_char(&mut bookmarks);
+        consume(&mut reader,&mut bookmarks,2);
+        bookmarks.bookmark(bookmark,&mut reader);
+        consume(&mut reader,&mut bookmarks,1);
+        bookmarks.rewind(bookmark,&mut reader);
+        assert_eq!(reader.pop_match(),"ab");
+        consume(&mut reader,&mut bookmarks,2);
+        assert_eq!(reader.pop_match(),"cd");
+    }
+
+    #[test]
+    fn str_reader_rewinds_before_match() {
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = StrReader::new("abcd");
+        let bookmark      = bookmarks.matched_bookmark;
+        reader.advance_char(&mut bookmarks);
+        bookmarks.bookmark(bookmark,&mut reader);
+        consume(&mut reader,&mut bookmarks,2);
+        assert_eq!(reader.pop_match(),"ab");
+        bookmarks.rewind(bookmark,&mut reader);
+        consume(&mut reader,&mut bookmarks,3);
+        assert_eq!(reader.pop_match(),"abc");
+    }
+
+    #[test]
+    fn byte_reader_reads_code_units() {
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = ByteReader::new("aλ");
+        reader.advance_char(&mut bookmarks);
+        assert_eq!(u32::from(reader.character()),u32::from(b'a'));
+        consume(&mut reader,&mut bookmarks,1);
+        assert_eq!(u32::from(reader.character()),0xCE);
+        consume(&mut reader,&mut bookmarks,2);
+        assert!(reader.character().is_eof());
+        assert_eq!(reader.pop_match(),"aλ");
+    }
+
+    #[test]
+    fn slices_of_matches() {
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = StrReader::new("@λa b");
+        reader.advance_char(&mut bookmarks);
+        consume(&mut reader,&mut bookmarks,3);
+        let shared = reader.pop_match();
+        let owned  = MatchText::from("@λa");
+        assert!(shared.slice(1..4).is_shared());
+        assert_eq!(shared.slice(1..4),"λa");
+        assert!(!owned.slice(1..4).is_shared());
+        assert_eq!(owned.slice(1..4),"λa");
+        assert_eq!(shared.slice(4..4),"");
+    }
+
+    #[test]
+    fn pushing_matches() {
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = StrReader::new("ab c");
+        reader.advance_char(&mut bookmarks);
+        consume(&mut reader,&mut bookmarks,2);
+        let first = reader.pop_match();
+        consume(&mut reader,&mut bookmarks,2);
+        let second    = reader.pop_match();
+        let mut text  = MatchText::default();
+        text.push(&first);
+        text.push(&MatchText::default());
+        text.push(&second);
+        assert!(text.is_shared());
+        assert_eq!(text,"ab c");
+        text.push(&first);
+        assert!(!text.is_shared());
+        assert_eq!(text,"ab cab");
+    }
+
+    #[test]
+    fn reader_copies_matches() {
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = Reader::new(&b"ab c"[..],DecoderUTF8());
+        reader.advance_char(&mut bookmarks);
+        consume(&mut reader,&mut bookmarks,2);
+        let first = reader.pop_match();
+        assert!(!first.is_shared());
+        assert_eq!(first,"ab");
+        assert_eq!(String::from(first),"ab");
+    }
+}
@@ -748,6 +748,11 @@
 //!
 //!     /// Code you want to run after lexing finishes.
 //!     fn tear_down(&mut self) {}
+//!
+//!     /// Reset the lexer so that it can lex another input.
+//!     fn reset(&mut self) {
+//!         self.lexer.reset()
+//!     }
 //! }
 //! ```
 //!
@@ -936,11 +941,16 @@
 //! #
 //! #     /// Code you want to run after lexing finishes.
 //! #     fn tear_down(&mut self) {}
+//! #
+//! #     /// Reset the lexer so that it can lex another input.
+//! #     fn reset(&mut self) {
+//! #         self.lexer.reset()
+//! #     }
 //! # }
 //!
 //! impl Lexer {
 //!      pub fn on_first_word<R:LazyReader>(&mut self, _reader:&mut R) {
-//!         let str = self.current_match.clone();
+//!         let str = self.current_match.to_string();
 //!         let ast = Token::Word(str);
 //!         self.output.push(ast);
 //!         let id = self.seen_first_word_state;
@@ -948,13 +958,13 @@
 //!     }
 //!
 //!     pub fn on_spaced_word<R:LazyReader>(&mut self, _reader:&mut R) {
-//!         let str = self.current_match.clone();
+//!         let str = self.current_match.to_string();
 //!         let ast = Token::Word(String::from(str.trim()));
 //!         self.output.push(ast);
 //!     }
 //!
 //!     pub fn on_err_suffix_first_word<R:LazyReader>(&mut self, _reader:&mut R) {
-//!         let ast = Token::Unrecognized(self.current_match.clone());
+//!         let ast = Token::Unrecognized(self.current_match.to_string());
 //!         self.output.push(ast);
 //!     }
 //!
@@ -986,6 +996,12 @@
 //! Both of these, combined, allow the transition functions to manipulate the text being read by the
 //! lexer.
 //!
+//! The text matched by the rule is available in `self.current_match` as a [`input::MatchText`].
+//! When the lexer is run over an in-memory [`input::StrReader`], the match is a slice of the input
+//! and is only copied if the transition function asks for an owned `String`. For streamed input,
+//! such as a [`prelude::Reader`], the match is accumulated as the lexer reads it. The text of a
+//! construct that spans several matches can be kept by [recording](Flexer::begin_recording) it.
+//!
 //! ## Specializing the Lexer
 //!
 //! In order to actually _use_ the lexer that you've defined, you need to specialize it to the rules
@@ -1004,10 +1020,27 @@
 //! 4.  Re-export this output file from your cargo project's `lib.rs`.
 //!
 //! The process of specialization will generate quite a bit of code, but most importantly it will
-//! generate `pub fn run<R:LazyReader>(&mut self, mut reader:R) -> Result<Output>`, where `Output`
+//! generate `pub fn run<R:MatchReader>(&mut self, mut reader:R) -> Result<Output>`, where `Output`
 //! is your lexer's token type. All of these functions are defined on your lexer type (the one whose
 //! name is provided to `specialize()`.
 //!
+//! A lexer can instead be specialized with [`generate::specialize_with_encoding`] and
+//! [`generate::Encoding::Utf8`]. This lowers the code-point ranges of its automata into UTF-8 byte
+//! sequences, so that the generated `run<R:Utf8Reader>` dispatches directly on the bytes of an
+//! [`input::ByteReader`] without decoding them. The rules and their callbacks are unchanged.
+//!
+//! Finer control over the generated code is available through [`generate::specialize_with`],
+//! which takes a set of [`generate::CodegenOptions`]. These select the encoding, whether the lexer
+//! traces its progress through the input, the inlining hints on the generated functions, and the
+//! name and visibility of the `run` function. Turning tracing off removes the per-character
+//! logging calls from the generated code entirely, which is useful for release builds.
+//! Turning coverage on makes the lexer count the hits of each of its rules, which are reported by
+//! its generated `rule_coverage` function as a [`coverage::Report`].
+//! Turning profiling on makes the lexer record the work it does in each group in the
+//! [`profile::Profile`] of the [`Flexer`].
+//! Making it nestable generates a `run_nested` function, through which a rule of another lexer
+//! can [delegate](group::rule::Rule::nested) part of its input to this lexer.
+//!
 //! ## In Summary
 //!
 //! The flexer allows its clients to define highly optimised lexer implementations that are capable
@@ -1017,17 +1050,28 @@ use crate::prelude::*;
 use prelude::logger::*;
 
 use crate::generate::GenError;
+use crate::profile::Profile;
 use prelude::logger::AnyLogger;
 use prelude::reader::BookmarkManager;
+use std::time::Duration;
 
 pub mod automata;
+pub mod coverage;
 pub mod data;
 pub mod generate;
 pub mod group;
+pub mod input;
+pub mod logger;
+pub mod profile;
 
 /// Useful libraries for working with the flexer.
 pub mod prelude {
     pub use crate::generate::GenError;
+    pub use crate::input::ByteReader;
+    pub use crate::input::MatchReader;
+    pub use crate::input::MatchText;
+    pub use crate::input::StrReader;
+    pub use crate::input::Utf8Reader;
     pub use enso_prelude::*;
     pub use lazy_reader::LazyReader;
     pub use lazy_reader::Reader;
@@ -1043,6 +1087,7 @@ pub mod prelude {
         pub use enso_logger::*;
         pub use enso_logger::disabled::Logger as Disabled;
         pub use enso_logger::enabled::Logger as Enabled;
+        pub use crate::logger::Silent;
     }
 }
 
@@ -1056,6 +1101,9 @@ mod constants {
     /// The number of 'frames' to reserve in the state stack, aiming to avoid re-allocation in hot
     /// code paths.
     pub const STATE_STACK_RESERVATION:usize = 1024;
+
+    /// The default maximum number of 'frames' in the state stack.
+    pub const MAX_STATE_STACK_DEPTH:usize = 4096;
 }
 
 
@@ -1079,18 +1127,44 @@ mod constants {
 pub struct Flexer<Definition,Output,Logger> {
     /// The stack of states that are active during lexer execution.
     pub state_stack:NonEmptyVec<group::Identifier>,
+    /// The maximum number of states that may be on the `state_stack` at any one time.
+    pub max_state_depth:usize,
+    /// The error that stopped the lexer, if any.
+    pub error:Option<LexingError>,
     /// The result of the current stage of the DFA.
     pub status:StageStatus,
     /// The tokens that have been lexed.
     pub output:Output,
     /// The text of the current match of the lexer.
-    pub current_match:String,
+    pub current_match:MatchText,
     /// A logger for the flexer, accessible in user definitions.
     pub logger:Logger,
+    /// The work done in each group, recorded only by lexers generated with profiling enabled.
+    pub profile:Profile,
+    /// The character preceding the current position of the reader, or `None` at the start of the
+    /// input.
+    ///
+    /// It is only tracked by lexers with rules whose patterns contain an
+    /// [anchor](automata::pattern::Anchor).
+    pub previous_char:Option<char>,
+    /// Whether the lexer, running nested in another, has finished lexing its part of the input.
+    finished_nested:bool,
+    /// The [recordings](Flexer::begin_recording) in progress, from the outermost to the innermost.
+    recordings:Vec<Recording>,
     /// The definition of the user-provided state for the lexer.
     definition:Definition,
 }
 
+/// The text of the matches made since a lexer began [recording](Flexer::begin_recording).
+#[derive(Clone,Debug,Default)]
+struct Recording {
+    /// The text of the matches preceding the last one.
+    text : MatchText,
+    /// The text of the last match, which is only added to `text` once another match follows it, as
+    /// it may yet be [forgotten](Flexer::forget_match).
+    last_match : MatchText,
+}
+
 impl<Definition,Output,Logger> Flexer<Definition,Output,Logger>
 where Definition : State,
       Logger     : AnyLogger<Owned=Logger>,
@@ -1104,9 +1178,38 @@ where Definition : State,
         let initial_state_id = definition.initial_state();
         let mut state_stack  = NonEmptyVec::singleton(initial_state_id);
         let current_match    = default();
+        let max_state_depth  = constants::MAX_STATE_STACK_DEPTH;
+        let error            = None;
+        let profile          = default();
+        let previous_char    = None;
+        let finished_nested  = false;
+        let recordings       = default();
 
         state_stack.reserve(constants::STATE_STACK_RESERVATION);
-        Flexer{state_stack,status,output,definition,current_match,logger}
+        Flexer{state_stack,max_state_depth,error,status,output,definition,current_match,logger,
+            profile,previous_char,finished_nested,recordings}
+    }
+
+    /// Reset the lexer so that it can be run on a new input.
+    ///
+    /// This clears the state stack, the output, and the user-defined [`State`], but keeps the
+    /// group registry, and so is considerably cheaper than constructing a new lexer. The
+    /// [`Profile`] is kept, so that it covers every input lexed.
+    pub fn reset(&mut self) {
+        debug!(self.logger,"Reset Lexer");
+        let initial_state = self.definition.initial_state();
+        while self.state_stack.pop().is_some() {
+            self.logger.group_end();
+        }
+        *self.state_stack.first_mut() = initial_state;
+        self.status          = default();
+        self.output          = default();
+        self.error           = None;
+        self.current_match   = default();
+        self.previous_char   = None;
+        self.finished_nested = false;
+        self.recordings.clear();
+        self.definition.reset();
     }
 }
 
@@ -1130,13 +1233,36 @@ where Definition : State,
     }
 
     /// Tell the lexer to enter the state described by `state`.
+    ///
+    /// If the state stack is already at its maximum depth, the lexer instead enters the
+    /// [recovery state](State::recovery_state) of the definition, or stops with a
+    /// [`LexingError::StateStackOverflow`] if there is none.
     pub fn push_state(&mut self, state:group::Identifier) {
+        if self.state_stack.len() >= self.max_state_depth {
+            return self.on_state_stack_overflow(state)
+        }
         self.logger.group_begin(
             ||format!("Enter State: {}",self.groups().group(state).name.as_str())
         );
         self.state_stack.push(state);
     }
 
+    /// Handle an attempt to enter `state` while the state stack is at its maximum depth.
+    ///
+    /// The stack is unwound to the initial state before entering the recovery state, so that the
+    /// recovery state is always able to enter further states.
+    fn on_state_stack_overflow(&mut self, state:group::Identifier) {
+        let max_depth = self.max_state_depth;
+        warning!(self.logger,"State stack overflow: Max Depth = {max_depth}");
+        match self.definition.recovery_state().filter(|_| max_depth > 1) {
+            Some(recovery_state) => {
+                while self.pop_state().is_some() {}
+                self.push_state(recovery_state);
+            }
+            None => self.error = Some(LexingError::StateStackOverflow{max_depth,state}),
+        }
+    }
+
     /// End the current state, returning the popped state identifier if one was ended.
     ///
     /// It will never end the initial state of the lexer.
@@ -1178,6 +1304,102 @@ where Definition : State,
     pub fn is_in_state(&self, state:group::Identifier) -> bool {
         self.current_state() == state
     }
+
+    /// Finish lexing the part of the input delegated to this lexer by another lexer, returning
+    /// control to that lexer once the current rule has been executed.
+    ///
+    /// See [`group::rule::Rule::nested`] for how input is delegated to a nested lexer.
+    pub fn finish_nested(&mut self) {
+        self.finished_nested = true;
+    }
+
+    /// Check whether the lexer has [finished](Flexer::finish_nested) lexing the part of the input
+    /// delegated to it, clearing the flag so that it can be delegated to again.
+    pub fn take_finished_nested(&mut self) -> bool {
+        mem::take(&mut self.finished_nested)
+    }
+
+    /// Make the match that the `reader` has just completed the current match, adding it to every
+    /// [recording](Flexer::begin_recording) in progress.
+    pub fn take_match(&mut self, reader:&mut impl MatchReader) {
+        let text = reader.pop_match();
+        for recording in &mut self.recordings {
+            let last_match = mem::replace(&mut recording.last_match,text.clone());
+            recording.text.push(&last_match);
+        }
+        self.current_match = text;
+    }
+
+    /// Begin recording the text of the matches following the current match, until the recording
+    /// is [ended](Flexer::end_recording).
+    ///
+    /// This allows a lexer to keep the text of a construct that spans several matches without
+    /// reading that text twice. Recordings may be nested, and each records all of the matches made
+    /// while it is in progress.
+    pub fn begin_recording(&mut self) {
+        self.recordings.push(default());
+    }
+
+    /// End the innermost recording in progress, returning the text of the matches made since it
+    /// began, up to and including the current match.
+    pub fn end_recording(&mut self) -> MatchText {
+        let Recording{mut text,last_match} = self.recordings.pop().unwrap_or_default();
+        text.push(&last_match);
+        text
+    }
+
+    /// Leave the current match out of every recording in progress.
+    ///
+    /// This must be called when the reader is rewound to the start of the current match, as the
+    /// text of the match is then matched again.
+    pub fn forget_match(&mut self) {
+        for recording in &mut self.recordings {
+            recording.last_match = default();
+        }
+    }
+
+    /// Check whether the current position of the `reader` is at the start of a line.
+    ///
+    /// This is the case at the start of the input, and after a line ending, where a `\r` only ends
+    /// a line if it is not followed by `\n`.
+    pub fn at_line_start(&self, reader:&impl LazyReader) -> bool {
+        match self.previous_char {
+            None       => true,
+            Some('\n') => true,
+            Some('\r') => reader.character().char != Ok('\n'),
+            Some(_)    => false,
+        }
+    }
+
+    /// Check whether the current position of the `reader` is at the end of a line.
+    ///
+    /// This is the case at the end of the input, and before a line ending.
+    pub fn at_line_end(&self, reader:&impl LazyReader) -> bool {
+        match reader.character().char {
+            Ok('\n') | Ok('\r') => true,
+            Ok(_)               => false,
+            Err(_)              => reader.character().is_eof(),
+        }
+    }
+
+    /// Record a step of the DFA taken in `group`, which took `time`.
+    pub fn profile_step(&mut self, group:group::Identifier, time:Duration) {
+        let profile = self.profile.group_mut(group,self.definition.groups());
+        profile.dfa_steps += 1;
+        profile.step_time += time;
+    }
+
+    /// Record a character consumed in `group`.
+    pub fn profile_character(&mut self, group:group::Identifier) {
+        self.profile.group_mut(group,self.definition.groups()).characters += 1;
+    }
+
+    /// Record a rule of `group` firing, with its callback taking `time`.
+    pub fn profile_rule(&mut self, group:group::Identifier, time:Duration) {
+        let profile = self.profile.group_mut(group,self.definition.groups());
+        profile.rules_fired   += 1;
+        profile.callback_time += time;
+    }
 }
 
 // === Trait Impls ===
@@ -1291,13 +1513,16 @@ pub struct LexingResult<T> {
     /// The kind of the result, representing _how_ the lexer completed.
     pub kind:ResultKind,
     /// The tokens that the lexer was able to process.
-    pub tokens:T
+    pub tokens:T,
+    /// The error that caused the lexer to fail, if it failed due to an error.
+    pub error:Option<LexingError>,
 }
 
 impl<T> LexingResult<T> {
     /// Create a new lexer result using the provided `kind` and `tokens`.
     pub fn new(kind:ResultKind,tokens:T) -> LexingResult<T> {
-        LexingResult {kind,tokens}
+        let error = None;
+        LexingResult {kind,tokens,error}
     }
 
     /// Create a new success result, with the provided `tokens`.
@@ -1314,10 +1539,16 @@ impl<T> LexingResult<T> {
     pub fn failure(tokens:T) -> LexingResult<T> {
         LexingResult::new(ResultKind::Failure, tokens)
     }
+
+    /// Create a failure result caused by `error`, with the `tokens` it _did_ manage to consume.
+    pub fn error(error:LexingError, tokens:T) -> LexingResult<T> {
+        let error = Some(error);
+        LexingResult{error,..LexingResult::failure(tokens)}
+    }
 }
 
 /// The kind of lexer result.
-#[derive(Copy,Clone,Debug)]
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
 pub enum ResultKind {
     /// The lexer succeeded, returning the contained token stream.
     Success,
@@ -1327,6 +1558,31 @@ pub enum ResultKind {
     Failure
 }
 
+/// An error that stops the lexer before it has consumed its input.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum LexingError {
+    /// The lexer tried to enter `state` with `max_depth` states already on its state stack, and
+    /// the definition has no [recovery state](State::recovery_state).
+    StateStackOverflow {
+        /// The maximum depth of the state stack.
+        max_depth : usize,
+        /// The state that could not be entered.
+        state : group::Identifier,
+    },
+}
+
+
+// === Trait Impls ===
+
+impl Display for LexingError {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexingError::StateStackOverflow{max_depth,..} =>
+                write!(f,"The lexer state stack exceeded its maximum depth of {}.",max_depth),
+        }
+    }
+}
+
 
 
 // =============
@@ -1352,6 +1608,20 @@ pub trait State {
     fn bookmarks(&self) -> &BookmarkManager;
     /// Get a mutable reference to the bookmark manager for this state.
     fn bookmarks_mut(&mut self) -> &mut BookmarkManager;
+    /// Return the state to enter when the lexer's state stack overflows, if there is one.
+    ///
+    /// The state stack is emptied down to the initial state before the recovery state is entered.
+    /// Without a recovery state, an overflow stops the lexer with an error.
+    fn recovery_state(&self) -> Option<group::Identifier> {
+        None
+    }
+    /// Reset the state so that the lexer can be run on a new input.
+    ///
+    /// The default implementation resets the bookmark manager, and hence discards any bookmarks
+    /// added by the state. States that hold additional lexing data must override it.
+    fn reset(&mut self) {
+        *self.bookmarks_mut() = default();
+    }
     /// Generate code to specialize the flexer for the user's particular lexer definition.
     ///
     /// This function should be implemented as a call to [`generate::specialize`], passing
@@ -1375,4 +1645,9 @@ pub trait Definition {
     fn set_up(&mut self);
     /// Run after lexing has completed.
     fn tear_down(&mut self);
+    /// Reset the lexer so that it can be run on a new input without being redefined.
+    ///
+    /// Definitions that wrap a [`Flexer`] should call [`Flexer::reset`] here, along with resetting
+    /// any data of their own.
+    fn reset(&mut self);
 }
@@ -0,0 +1,49 @@
+//! Loggers provided by the flexer in addition to those of the Enso logging library.
+
+use crate::prelude::*;
+
+
+
+// ==============
+// === Silent ===
+// ==============
+
+/// A logger that discards all messages.
+///
+/// Unlike the [`Disabled`](crate::prelude::logger::Disabled) logger, it holds no shared state, and
+/// hence lexers that use it can be sent between threads.
+#[derive(Clone,Copy,Debug,Default,Eq,PartialEq)]
+pub struct Silent;
+
+
+// === Trait Impls ===
+
+impl AnyLogger for Silent {
+    type Owned = Silent;
+
+    fn new(_path:impl Into<ImString>) -> Self::Owned {
+        Silent
+    }
+
+    fn path(&self) -> &str {
+        ""
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send<T:Send>() {}
+
+    #[test]
+    fn silent_is_send() {
+        assert_send::<Silent>();
+    }
+}
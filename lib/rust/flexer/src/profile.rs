@@ -0,0 +1,142 @@
+//! Profiling of the work that a lexer does in each of its groups.
+//!
+//! Lexers generated with [`crate::generate::CodegenOptions::with_profiling`] record, for each
+//! group, the characters that they consume, the steps of the DFA that they take, and the rules that
+//! fire, along with the time spent in rule callbacks and in the transitions of the DFA. These are
+//! collected in the [`Profile`] of the [`crate::Flexer`].
+
+use crate::prelude::*;
+
+use crate::group;
+
+use std::time::Duration;
+
+
+
+// ====================
+// === GroupProfile ===
+// ====================
+
+/// The work done by a lexer while in a single group.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct GroupProfile {
+    /// The name of the group.
+    pub name : String,
+    /// The number of characters consumed while in the group.
+    pub characters : usize,
+    /// The number of steps of the DFA taken while in the group.
+    pub dfa_steps : usize,
+    /// The number of rules of the group that have fired.
+    pub rules_fired : usize,
+    /// The time spent executing the steps of the DFA, including the rule callbacks.
+    pub step_time : Duration,
+    /// The time spent executing the callbacks of the rules of the group.
+    pub callback_time : Duration,
+}
+
+impl GroupProfile {
+    /// Create an empty profile for the group called `name`.
+    pub fn new(name:impl Into<String>) -> Self {
+        let name = name.into();
+        GroupProfile{name,..default()}
+    }
+
+    /// The time spent in the transitions of the DFA, excluding the rule callbacks.
+    pub fn transition_time(&self) -> Duration {
+        self.step_time.checked_sub(self.callback_time).unwrap_or_default()
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for GroupProfile {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{}: {} characters, {} steps, {} rules, {:?} in callbacks, {:?} in transitions",
+            self.name,self.characters,self.dfa_steps,self.rules_fired,self.callback_time,
+            self.transition_time())
+    }
+}
+
+
+
+// ===============
+// === Profile ===
+// ===============
+
+/// The work done by a lexer in each of its groups.
+///
+/// The profile accumulates over every run of the lexer, including across
+/// [resets](crate::Flexer::reset), until it is [cleared](Profile::clear).
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct Profile {
+    /// The profile of each group of the lexer, indexed by the group's identifier.
+    pub groups : Vec<GroupProfile>,
+}
+
+impl Profile {
+    /// Get the profile of the group called `name`.
+    pub fn group(&self, name:&str) -> Option<&GroupProfile> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    /// Get the profile of the group `group` from `registry`, creating the profiles of all of the
+    /// groups in `registry` if they do not yet exist.
+    pub fn group_mut
+    (&mut self, group:group::Identifier, registry:&group::Registry) -> &mut GroupProfile {
+        if self.groups.is_empty() {
+            let groups  = registry.all().iter();
+            self.groups = groups.map(|group| GroupProfile::new(group.name.as_str())).collect();
+        }
+        let index:usize = group.into();
+        &mut self.groups[index]
+    }
+
+    /// Discard everything recorded in the profile.
+    pub fn clear(&mut self) {
+        self.groups.clear()
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for Profile {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for group in self.groups.iter().filter(|group| group.dfa_steps > 0) {
+            writeln!(f,"{}",group)?;
+        }
+        Ok(())
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_by_name() {
+        let mut registry = group::Registry::default();
+        let root         = registry.define_group("ROOT",None);
+        let suffix       = registry.define_group("SUFFIX",None);
+        let mut profile  = Profile::default();
+        profile.group_mut(suffix,&registry).dfa_steps += 2;
+        profile.group_mut(root,&registry).step_time = Duration::from_millis(5);
+        profile.group_mut(root,&registry).callback_time = Duration::from_millis(2);
+        assert_eq!(profile.groups.len(),2);
+        assert_eq!(profile.group("SUFFIX").map(|group| group.dfa_steps),Some(2));
+        assert_eq!(profile.group("ROOT").unwrap().transition_time(),Duration::from_millis(3));
+        assert_eq!(profile.group("MISSING"),None);
+        let expected = "SUFFIX: 0 characters, 2 steps, 0 rules, 0ns in callbacks, 0ns in \
+            transitions\n";
+        assert_eq!(profile.to_string(),expected);
+        profile.clear();
+        assert!(profile.groups.is_empty());
+    }
+}
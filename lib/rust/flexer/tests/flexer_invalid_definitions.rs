@@ -151,6 +151,10 @@ impl flexer::Definition for Lexer1 {
     fn tear_down(&mut self) {
         unimplemented!()
     }
+
+    fn reset(&mut self) {
+        unimplemented!()
+    }
 }
 
 #[test]
@@ -220,6 +224,10 @@ impl flexer::Definition for Lexer2 {
     fn tear_down(&mut self) {
         unimplemented!()
     }
+
+    fn reset(&mut self) {
+        unimplemented!()
+    }
 }
 
 #[test]
@@ -292,6 +300,10 @@ impl flexer::Definition for Lexer3 {
     fn tear_down(&mut self) {
         unimplemented!()
     }
+
+    fn reset(&mut self) {
+        unimplemented!()
+    }
 }
 
 pub struct LexerState1 {
@@ -398,6 +410,10 @@ impl flexer::Definition for Lexer4 {
     fn tear_down(&mut self) {
         unimplemented!()
     }
+
+    fn reset(&mut self) {
+        unimplemented!()
+    }
 }
 
 pub struct LexerState2 {
@@ -4,18 +4,22 @@ use crate::prelude::*;
 use flexer::*;
 
 use crate::library::token::BlockType;
+use crate::library::token::EscapeStyle;
+use crate::library::token::IndentStyle;
+use crate::library::token::TextStyle;
 use crate::library::token::Token;
 use crate::library::token;
 
 use flexer::automata::pattern::Pattern;
 use flexer::group::Group;
 use flexer::group::Registry;
-use flexer::prelude::logger::Disabled;
+use flexer::group::rule::Rule;
+use flexer::group::rule::Transition;
+use flexer::prelude::logger::Silent;
 use flexer::prelude::reader;
 use flexer::State as FlexerState;
 use flexer;
 use std::collections::VecDeque;
-use std::cmp::Ordering;
 
 
 
@@ -23,7 +27,7 @@ use std::cmp::Ordering;
 // === Type Aliases ===
 // ====================
 
-type Logger = Disabled;
+type Logger = Silent;
 type Flexer = flexer::Flexer<State<Logger>,token::Stream,Logger>;
 
 
@@ -57,6 +61,13 @@ impl EnsoLexer {
         let lexer  = Flexer::new(logger);
         EnsoLexer(lexer)
     }
+
+    /// Construct a new instance of the Enso lexer that recognises the provided `keywords`.
+    pub fn with_keywords(keywords:Keywords) -> Self {
+        let mut lexer  = Self::new();
+        lexer.keywords = keywords;
+        lexer
+    }
 }
 
 
@@ -94,7 +105,7 @@ impl EnsoLexer {
     }
 
     /// Consume the current match and replace it with the empty string.
-    pub fn consume_current(&mut self) -> String {
+    pub fn consume_current(&mut self) -> MatchText {
         debug!(self.logger,"Consume: {self.current_match:?}");
         mem::take(&mut self.current_match)
     }
@@ -104,6 +115,13 @@ impl EnsoLexer {
         debug!(self.logger,"Discard: {self.current_match:?}");
         self.current_match = default();
     }
+
+    /// Rewind the `reader` to the start of the current match, so that its text is matched again.
+    pub fn rewind_current<R:LazyReader>(&mut self, reader:&mut R) {
+        let matched_bookmark = self.bookmarks.matched_bookmark;
+        self.bookmarks.rewind(matched_bookmark,reader);
+        self.forget_match();
+    }
 }
 
 
@@ -128,6 +146,11 @@ impl EnsoLexer {
         Pattern::range('0'..='9')
     }
 
+    /// Match ASCII hexadecimal digits.
+    fn ascii_hex_digit() -> Pattern {
+        EnsoLexer::ascii_digit() | Pattern::range('a'..='f') | Pattern::range('A'..='F')
+    }
+
     /// Match ASCII letters.
     fn ascii_letter() -> Pattern {
         EnsoLexer::lower_ascii_letter() | EnsoLexer::upper_ascii_letter()
@@ -185,22 +208,18 @@ impl EnsoLexer {
 
     /// Create an arbitrary operator that requires no special handling.
     fn on_operator<R:LazyReader>(&mut self, _reader:&mut R) {
-        let op_modifier_check = self.operator_modifier_check;
-        let operator          = self.consume_current();
-        let offset            = self.offset.consume();
-        let token             = Token::Operator(operator,offset);
+        let operator = self.consume_current();
+        let offset   = self.offset.consume();
+        let token    = Token::Operator(operator,offset);
         self.append_token(token);
-        self.push_state(op_modifier_check);
     }
 
     /// Create an operator that cannot have an associated modifier.
     fn on_operator_no_modifier<R:LazyReader>(&mut self, _reader:&mut R) {
-        let op_suffix_check = self.operator_suffix_check;
-        let operator        = self.consume_current();
-        let offset          = self.offset.consume();
-        let token           = Token::Operator(operator,offset);
+        let operator = self.consume_current();
+        let offset   = self.offset.consume();
+        let token    = Token::Operator(operator,offset);
         self.append_token(token);
-        self.push_state(op_suffix_check);
     }
 
     /// Create a grouping operator.
@@ -240,22 +259,30 @@ impl EnsoLexer {
         let ops_in          = l!("in");
         let ops_dot         = dot | comma | l!("..") | l!("...");
         let ops_group       = Pattern::any_of(Self::group_chars().as_str());
-        let ops_comment     = c!('#') | l!("##");
-        let ops_no_modifier = &ops_eq | &ops_dot | &ops_comment | &ops_in;
+        let ops_no_modifier = &ops_eq | &ops_dot | &ops_in;
+
+        let operator_mod_check_id = lexer.operator_modifier_check;
+        let operator_sfx_check_id = lexer.operator_suffix_check;
+        let to_mod_check          = Transition::Push(operator_mod_check_id);
+        let to_sfx_check          = Transition::Push(operator_sfx_check_id);
+        let on_operator           = "self.on_operator(reader)";
+        let on_operator           = Rule::new(operator_body.clone(),on_operator);
+        let on_no_modifier        = "self.on_operator_no_modifier(reader)";
+        let on_no_modifier        = Rule::new(ops_no_modifier,on_no_modifier);
+        let on_error_suffix       = "self.ident_on_error_suffix(reader)";
+        let on_error_suffix       = Rule::new(error_suffix.clone(),on_error_suffix);
 
         let initial_state_id = lexer.initial_state;
         let initial_state    = lexer.group_mut(initial_state_id);
-        initial_state.create_rule(&operator_body,  "self.on_operator(reader)");
-        initial_state.create_rule(&ops_no_modifier,"self.on_operator_no_modifier(reader)");
-        initial_state.create_rule(&ops_group,      "self.on_group(reader)");
+        initial_state.add_rule(on_operator.with_transition(to_mod_check));
+        initial_state.add_rule(on_no_modifier.with_transition(to_sfx_check));
+        initial_state.create_rule(&ops_group,"self.on_group(reader)");
 
-        let operator_mod_check_id = lexer.operator_modifier_check;
-        let operator_mod_check    = lexer.group_mut(operator_mod_check_id);
+        let operator_mod_check = lexer.group_mut(operator_mod_check_id);
         operator_mod_check.create_rule(&equals,"self.on_modifier(reader)");
 
-        let operator_sfx_check_id = lexer.operator_suffix_check;
-        let operator_sfx_check    = lexer.group_mut(operator_sfx_check_id);
-        operator_sfx_check.create_rule(&error_suffix,"self.ident_on_error_suffix(reader)");
+        let operator_sfx_check = lexer.group_mut(operator_sfx_check_id);
+        operator_sfx_check.add_rule(on_error_suffix.with_transition(Transition::Pop));
         operator_sfx_check.create_rule(&Pattern::always(),"self.ident_on_no_error_suffix(reader)");
     }
 }
@@ -267,44 +294,60 @@ impl EnsoLexer {
 #[allow(dead_code)]
 impl EnsoLexer {
 
-    /// Create a variable identifier from the current match.
+    /// Create a variable identifier from the current match, or a keyword if it is one.
     fn on_variable_ident<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token        = Token::Variable(self.consume_current(),self.offset.consume());
-        let suffix_check = self.ident_suffix_check;
+        let name   = self.consume_current();
+        let offset = self.offset.consume();
+        let token  = if self.keywords.contains(&name) {
+            Token::Keyword(name,offset)
+        } else {
+            Token::Variable(name,offset)
+        };
         self.append_token(token);
-        self.push_state(suffix_check);
     }
 
     /// Create a referent identifier from the current match.
     fn on_referent_ident<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token        = Token::Referent(self.consume_current(),self.offset.consume());
-        let suffix_check = self.ident_suffix_check;
+        let token = Token::Referent(self.consume_current(),self.offset.consume());
         self.append_token(token);
-        self.push_state(suffix_check);
     }
 
     /// Create an external identifier from the current match.
     fn on_external_ident<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token        = Token::External(self.consume_current(),self.offset.consume());
-        let suffix_check = self.ident_suffix_check;
+        let token = Token::External(self.consume_current(),self.offset.consume());
         self.append_token(token);
-        self.push_state(suffix_check);
     }
 
     /// Create a blank identifier from the current match.
     fn on_blank<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token        = Token::Blank(self.offset.consume());
-        let suffix_check = self.ident_suffix_check;
+        let token = Token::Blank(self.offset.consume());
         self.discard_current();
         self.append_token(token);
-        self.push_state(suffix_check);
+    }
+
+    /// Create an annotation from the current match.
+    fn ident_on_annotation<R:LazyReader>(&mut self, _reader:&mut R) {
+        let name  = EnsoLexer::annotation_text(self.consume_current());
+        let token = Token::Annotation(name,self.offset.consume());
+        self.append_token(token);
+    }
+
+    /// Create an annotation with an invalid name from the current match.
+    fn ident_on_invalid_annotation<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text  = EnsoLexer::annotation_text(self.consume_current());
+        let token = Token::InvalidAnnotation(text,self.offset.consume());
+        self.append_token(token);
+    }
+
+    /// Get the text that follows the leading `@` of an annotation from its `repr`.
+    fn annotation_text(repr:MatchText) -> MatchText {
+        repr.slice(1..repr.len())
     }
 
     /// Tokenize an unexpected error suffix.
     fn ident_on_error_suffix<R:LazyReader>(&mut self, _reader:&mut R) {
         let token = Token::InvalidSuffix(self.consume_current(),self.offset.consume());
         self.append_token(token);
-        self.pop_state();
     }
 
     /// Submit a non-error identifier.
@@ -323,21 +366,34 @@ impl EnsoLexer {
         let ref_seg        = (EnsoLexer::upper_ascii_letter() | EnsoLexer::ascii_digit()) >> &body_char;
         let external_start = EnsoLexer::ascii_letter() | &underscore;
         let external_body  = EnsoLexer::ascii_alpha_num() | &underscore;
-        let variable_ident = &init_var_seg >> (&underscore >> &var_seg).many() >> &ticks;
-        let referent_ident = &init_ref_seg >> (&underscore >> &ref_seg).many() >> &ticks;
-        let external_ident = &external_start >> external_body.many() >> &ticks;
+        let variable_name  = &init_var_seg >> (&underscore >> &var_seg).many();
+        let referent_name  = &init_ref_seg >> (&underscore >> &ref_seg).many();
+        let external_name  = &external_start >> external_body.many();
+        let variable_ident = &variable_name >> &ticks;
+        let referent_ident = &referent_name >> &ticks;
+        let external_ident = &external_name >> &ticks;
+        let at             = c!('@');
+        let annotation     = &at >> (&variable_name | &referent_name);
+        let bad_annotation = &at >> external_name.opt();
         let error_suffix   = Pattern::none_of(EnsoLexer::break_chars().as_str()).many1();
 
+        let suffix_check_id  = lexer.ident_suffix_check;
+        let to_suffix_check  = Transition::Push(suffix_check_id);
+        let rule             = |pattern:Pattern, callback:&str| {
+            Rule::new(pattern,callback).with_transition(to_suffix_check)
+        };
         let initial_state_id = lexer.initial_state;
         let initial_state    = lexer.group_mut(initial_state_id);
-        initial_state.create_rule(&variable_ident,"self.on_variable_ident(reader)");
-        initial_state.create_rule(&referent_ident,"self.on_referent_ident(reader)");
-        initial_state.create_rule(&underscore,    "self.on_blank(reader)");
-        initial_state.create_rule(&external_ident,"self.on_external_ident(reader)");
-
-        let suffix_check_id = lexer.ident_suffix_check;
+        initial_state.add_rule(rule(variable_ident,"self.on_variable_ident(reader)"));
+        initial_state.add_rule(rule(referent_ident,"self.on_referent_ident(reader)"));
+        initial_state.add_rule(rule(underscore,    "self.on_blank(reader)"));
+        initial_state.add_rule(rule(external_ident,"self.on_external_ident(reader)"));
+        initial_state.add_rule(rule(annotation,    "self.ident_on_annotation(reader)"));
+        initial_state.add_rule(rule(bad_annotation,"self.ident_on_invalid_annotation(reader)"));
+
+        let on_error_suffix = Rule::new(error_suffix,"self.ident_on_error_suffix(reader)");
         let suffix_check    = lexer.group_mut(suffix_check_id);
-        suffix_check.create_rule(&error_suffix,     "self.ident_on_error_suffix(reader)");
+        suffix_check.add_rule(on_error_suffix.with_transition(Transition::Pop));
         suffix_check.create_rule(&Pattern::always(),"self.ident_on_no_error_suffix(reader)");
     }
 }
@@ -359,14 +415,14 @@ impl EnsoLexer {
     /// Triggered when the lexer matches an integer with an implicit base.
     fn on_integer<R:LazyReader>(&mut self, _reader:&mut R) {
         let number_phase_2 = self.number_phase_two;
-        self.number_state.literal = self.consume_current();
+        self.number_state.literal = self.consume_current().into();
         self.push_state(number_phase_2)
     }
 
     /// Triggered when the lexer matches a number annotated with an explicit base.
     fn on_explicit_base<R:LazyReader>(&mut self, _reader:&mut R) {
         let literal               = self.consume_current();
-        self.number_state.literal = literal;
+        self.number_state.literal = literal.into();
         let offset                = self.offset.consume();
         let token                 = self.number_state.consume_token(offset);
         self.append_token(token);
@@ -386,14 +442,35 @@ impl EnsoLexer {
 
     /// Triggered when an explicit decimal number has been seen by the lexer.
     fn on_decimal<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.submit_suffix_checked_number();
+    }
+
+    /// Triggered when a number with an exponent has been seen by the lexer.
+    fn on_exponent<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.submit_suffix_checked_number();
+    }
+
+    /// Submit the current match as a number token, and check that it isn't followed by a suffix.
+    fn submit_suffix_checked_number(&mut self) {
         let decimal_suffix_check  = self.decimal_suffix_check;
-        self.number_state.literal = self.consume_current();
+        self.number_state.literal = self.consume_current().into();
         let offset                = self.offset.consume();
         let token                 = self.number_state.consume_token(offset);
         self.append_token(token);
         self.push_state(decimal_suffix_check);
     }
 
+    /// Triggered when an integer is followed by an exponent marker that has no digits, which is
+    /// reported as an invalid suffix of the integer, along with anything else attached to it.
+    fn on_dangling_exponent<R:LazyReader>(&mut self, _reader:&mut R) {
+        let offset = self.offset.consume();
+        let token  = self.number_state.consume_token(offset);
+        self.append_token(token);
+        let suffix = Token::InvalidSuffix(self.consume_current(),self.offset.consume());
+        self.append_token(suffix);
+        self.pop_state();
+    }
+
     /// Triggered when an explicit base annotation has been seen by the lexer.
     fn seen_base<R:LazyReader>(&mut self, _reader:&mut R) {
         let seen_base_id = self.number_seen_base;
@@ -431,18 +508,26 @@ impl EnsoLexer {
         let point             = c!('.');
         let underscore        = c!('_');
         let decimal           = &digits >> &point >> &digits;
+        let exponent_marker   = c!('e') | c!('E');
+        let exponent          = &exponent_marker >> (c!('+') | c!('-')).opt() >> &digits;
+        let integer_exponent  = &digits >> &exponent;
+        let decimal_exponent  = &decimal >> &exponent;
         let arbitrary_digits  = EnsoLexer::ascii_alpha_num().many1();
         let arbitrary_decimal = &arbitrary_digits >> (&point >> &arbitrary_digits).opt();
         let error_suffix      = Pattern::none_of(EnsoLexer::break_chars().as_str()).many1();
+        let dangling_exponent = &exponent_marker >> error_suffix.opt();
 
         let initial_state_id = lexer.initial_state;
         let initial_state    = lexer.group_mut(initial_state_id);
         initial_state.create_rule(&digits,"self.on_integer(reader)");
         initial_state.create_rule(&decimal,"self.on_decimal(reader)");
+        initial_state.create_rule(&integer_exponent,"self.on_exponent(reader)");
+        initial_state.create_rule(&decimal_exponent,"self.on_exponent(reader)");
 
         let number_phase_2_id = lexer.number_phase_two;
         let number_phase_2    = lexer.groups_mut().group_mut(number_phase_2_id);
         number_phase_2.create_rule(&underscore,       "self.seen_base(reader)");
+        number_phase_2.create_rule(&dangling_exponent,"self.on_dangling_exponent(reader)");
         number_phase_2.create_rule(&Pattern::always(),"self.submit_integer(reader)");
 
         let seen_base_id = lexer.number_seen_base;
@@ -464,9 +549,597 @@ impl EnsoLexer {
 #[allow(dead_code)]
 impl EnsoLexer {
 
+    /// Triggered when the opening quote of an inline raw text literal is seen.
+    fn text_on_raw_line_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_line = self.text_line;
+        self.text_begin(text_line);
+    }
+
+    /// Triggered when the opening quote of an inline interpolated text literal is seen.
+    fn text_on_interpolated_line_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_interpolated_line = self.text_interpolated_line;
+        self.text_begin(text_interpolated_line);
+    }
+
+    /// Triggered on a run of characters in a text literal that need no special handling.
+    ///
+    /// Adjacent raw segments (such as the extra indentation of a text block line followed by its
+    /// text) are merged into a single segment.
+    fn text_on_raw_segment<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text = self.consume_current();
+        match self.output.last_mut() {
+            Some(Token{shape:token::Shape::TextSegmentRaw(repr),length,..}) => {
+                *length += text.chars().count();
+                repr.push(&text);
+            }
+            _ => self.append_token(Token::TextSegmentRaw(text,0)),
+        }
+    }
+
+    /// Triggered on an escape sequence that stands for a single character.
+    fn text_on_simple_escape<R:LazyReader>(&mut self, _reader:&mut R) {
+        let token = Token::TextSegmentEscape(EscapeStyle::Simple,self.consume_current(),0);
+        self.append_token(token);
+    }
+
+    /// Triggered on an escape sequence that stands for a byte.
+    fn text_on_byte_escape<R:LazyReader>(&mut self, _reader:&mut R) {
+        let token = Token::TextSegmentEscape(EscapeStyle::Byte,self.consume_current(),0);
+        self.append_token(token);
+    }
+
+    /// Triggered on an escape sequence that stands for a unicode code point.
+    ///
+    /// Escapes that do not denote a valid unicode scalar value are treated as invalid.
+    fn text_on_unicode_escape<R:LazyReader>(&mut self, _reader:&mut R) {
+        let repr     = self.consume_current();
+        let digits   = repr.trim_start_matches("\\u{").trim_end_matches('}');
+        let is_valid = u32::from_str_radix(digits,16).ok().and_then(std::char::from_u32).is_some();
+        let token    = if is_valid {
+            Token::TextSegmentEscape(EscapeStyle::Unicode,repr,0)
+        } else {
+            Token::InvalidEscape(repr,0)
+        };
+        self.append_token(token);
+    }
+
+    /// Triggered on a backslash that does not begin a valid escape sequence.
+    fn text_on_invalid_escape<R:LazyReader>(&mut self, _reader:&mut R) {
+        let token = Token::InvalidEscape(self.consume_current(),0);
+        self.append_token(token);
+    }
+
+    /// Triggered when the closing quote of an inline raw text literal is seen.
+    fn text_on_raw_line_end<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_line = self.text_line;
+        self.discard_current();
+        let (segments,offset) = self.text_end(text_line);
+        let token             = Token::TextLine(TextStyle::Raw,segments,offset);
+        self.append_token(token);
+    }
+
+    /// Triggered when the closing quote of an inline interpolated text literal is seen.
+    fn text_on_interpolated_line_end<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_interpolated_line = self.text_interpolated_line;
+        self.discard_current();
+        let (segments,offset) = self.text_end(text_interpolated_line);
+        let token             = Token::TextLine(TextStyle::Interpolated,segments,offset);
+        self.append_token(token);
+    }
+
+    /// Triggered when an inline raw text literal reaches the end of its line without being closed.
+    ///
+    /// The line ending itself is not consumed, and is instead lexed as part of the enclosing block.
+    fn text_on_unclosed_raw_line<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_line         = self.text_line;
+        let (segments,offset) = self.text_end(text_line);
+        let token             = Token::UnclosedTextLine(TextStyle::Raw,segments,offset);
+        self.append_token(token);
+    }
+
+    /// Triggered when an inline interpolated text literal reaches the end of its line without
+    /// being closed.
+    ///
+    /// The line ending itself is not consumed, and is instead lexed as part of the enclosing block.
+    fn text_on_unclosed_interpolated_line<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_interpolated_line = self.text_interpolated_line;
+        let (segments,offset)      = self.text_end(text_interpolated_line);
+        let token = Token::UnclosedTextLine(TextStyle::Interpolated,segments,offset);
+        self.append_token(token);
+    }
+
+    /// Triggered when the opening backtick of a splice in an interpolated text literal is seen.
+    ///
+    /// The code in the splice is lexed by the rules of the root state, which the splice state
+    /// inherits.
+    fn text_on_splice_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_splice = self.text_splice;
+        self.text_begin(text_splice);
+    }
+
+    /// Triggered when the closing backtick of a splice is seen.
+    fn text_on_splice_end<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_splice     = self.text_splice;
+        self.discard_current();
+        let trailing_offset = self.offset.consume();
+        let (tokens,_)      = self.text_end(text_splice);
+        let token           = Token::TextSegmentSplice(tokens.into(),trailing_offset,0);
+        self.append_token(token);
+    }
+
+    /// Triggered when a splice reaches the end of its line without being closed.
+    ///
+    /// An inline text literal containing the splice is then unclosed as well, while a line of a
+    /// text block literal simply ends. The line ending itself is not consumed, and is instead
+    /// lexed as part of the enclosing block.
+    fn text_on_unclosed_splice<R:LazyReader>(&mut self, reader:&mut R) {
+        self.rewind_current(reader);
+        self.text_close_unclosed_splice(reader);
+    }
+
+    /// Leave the current splice without it having been closed, appending its token to the
+    /// enclosing literal.
+    fn text_close_unclosed_splice<R:LazyReader>(&mut self, reader:&mut R) {
+        let text_splice            = self.text_splice;
+        let text_interpolated_line = self.text_interpolated_line;
+        self.discard_current();
+        let trailing_offset = self.offset.consume();
+        let (tokens,_)      = self.text_end(text_splice);
+        let token           = Token::UnclosedTextSplice(tokens.into(),trailing_offset,0);
+        self.append_token(token);
+        if self.is_in_state(text_interpolated_line) {
+            self.text_on_unclosed_interpolated_line(reader);
+        }
+    }
+
+    /// Triggered on a backtick that is not part of an interpolated text literal.
+    fn text_on_unmatched_backtick<R:LazyReader>(&mut self, _reader:&mut R) {
+        let token = Token::UnmatchedBacktick(self.offset.consume());
+        self.discard_current();
+        self.append_token(token);
+    }
+
+    /// Triggered when the opening quotes of a raw text block literal are seen.
+    ///
+    /// On a line that begins with `foreign` and the name of a language, the quotes instead begin
+    /// the body of a foreign definition, which is kept as raw text.
+    fn text_on_raw_block_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        let kind = match self.text_foreign_language() {
+            Some(language) => TextBlockKind::Foreign(language),
+            None           => TextBlockKind::Text(TextStyle::Raw),
+        };
+        self.text_block_begin(kind);
+    }
+
+    /// The language of the foreign definition on the current line, if the line is one.
+    ///
+    /// The line is recognised by the text of its first identifier, so that foreign definitions are
+    /// found whether or not `foreign` is in the lexer's table of keywords.
+    fn text_foreign_language(&self) -> Option<String> {
+        match self.output.tokens().as_slice() {
+            [ Token{shape:token::Shape::Keyword(name),..}
+            , Token{shape:token::Shape::Variable(language),..}
+            , ..
+            ]
+            | [ Token{shape:token::Shape::Variable(name),..}
+            , Token{shape:token::Shape::Variable(language),..}
+            , ..
+            ] if name == "foreign" => Some(language.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Triggered when the opening quotes of an interpolated text block literal are seen.
+    fn text_on_interpolated_block_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.text_block_begin(TextBlockKind::Text(TextStyle::Interpolated));
+    }
+
+    /// Triggered when the opening quotes of a raw text block literal are seen within a splice or a
+    /// disable comment.
+    ///
+    /// Neither of these can span multiple lines, so the quotes are lexed as they would be in an
+    /// inline literal: as an empty literal followed by one that is not closed.
+    fn text_on_raw_block_start_in_splice<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.discard_current();
+        let offset = self.offset.consume();
+        self.append_token(Token::TextLine(TextStyle::Raw,vec![],offset));
+        self.append_token(Token::UnclosedTextLine(TextStyle::Raw,vec![],0));
+    }
+
+    /// Triggered when the opening quotes of an interpolated text block literal are seen within a
+    /// splice or a disable comment.
+    ///
+    /// Neither of these can span multiple lines, so the quotes are lexed as they would be in an
+    /// inline literal: as an empty literal followed by one that is not closed.
+    fn text_on_interpolated_block_start_in_splice<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.discard_current();
+        let offset = self.offset.consume();
+        self.append_token(Token::TextLine(TextStyle::Interpolated,vec![],offset));
+        self.append_token(Token::UnclosedTextLine(TextStyle::Interpolated,vec![],0));
+    }
+
+    /// Whether a text block literal or a documentation comment may begin at the current position.
+    fn text_block_may_begin(&self) -> bool {
+        !self.is_in_state(self.text_splice) && !self.is_in_state(self.disable_comment)
+    }
+
+    /// Triggered on a unix-style line ending in a text block literal.
+    fn text_block_on_lf<R:LazyReader>(&mut self, reader:&mut R) {
+        self.text_block_on_line_ending(token::LineEnding::LF,reader);
+    }
+
+    /// Triggered on a windows-style line ending in a text block literal.
+    fn text_block_on_crlf<R:LazyReader>(&mut self, reader:&mut R) {
+        self.text_block_on_line_ending(token::LineEnding::CRLF,reader);
+    }
+
+    /// Common functionality for both styles of line ending in a text block literal.
+    ///
+    /// The line ending that follows the opening quotes ends the line containing the literal, while
+    /// every other line ending ends a line of the literal itself.
+    fn text_block_on_line_ending<R:LazyReader>
+    (&mut self, line_ending:token::LineEnding, _reader:&mut R) {
+        let text_block_indent = self.text_block_indent;
+        if self.text_block_state.in_line {
+            let segments = self.consume_tokens();
+            self.text_block_state.submit_line(segments.into(),line_ending);
+            self.block_state.next_line();
+        } else {
+            self.block_state.push_line_ending(line_ending);
+            self.block_state.seen_newline = true;
+        }
+        self.push_state(text_block_indent);
+    }
+
+    /// Triggered on a blank line in a text block literal that ends in a unix-style line ending.
+    fn text_block_on_empty_lf_line<R:LazyReader>(&mut self, _reader:&mut R) {
+        let offset = self.current_match.chars().count() - 1;
+        self.discard_current();
+        self.text_block_state.push_empty_line(offset,token::LineEnding::LF);
+        self.block_state.next_line();
+    }
+
+    /// Triggered on a blank line in a text block literal that ends in a windows-style line ending.
+    fn text_block_on_empty_crlf_line<R:LazyReader>(&mut self, _reader:&mut R) {
+        let offset = self.current_match.chars().count() - 2;
+        self.discard_current();
+        self.text_block_state.push_empty_line(offset,token::LineEnding::CRLF);
+        self.block_state.next_line();
+    }
+
+    /// Triggered on the indentation of a non-blank line in a text block literal.
+    ///
+    /// The first such line sets the baseline margin of the block, and must be indented further
+    /// than the line containing the literal. As in blocks, each space or tab of the indentation
+    /// counts as a single column. Indentation beyond the margin is kept as raw text, while a line
+    /// indented less than the margin ends the literal.
+    ///
+    /// The body of a foreign definition is never lexed as Enso code, so it only ends at a line
+    /// that is not indented past the line containing the definition. Its margin is instead the
+    /// smallest indentation of any of its lines.
+    fn text_block_on_line<R:LazyReader>(&mut self, reader:&mut R) {
+        let indentation = self.current_match.clone();
+        let indent      = indentation.chars().count();
+        let min_indent  = self.block_state.current().indent + 1;
+        let margin      = self.text_block_state.indent.unwrap_or(indent).max(min_indent);
+        let is_foreign  = match self.text_block_state.kind {
+            TextBlockKind::Foreign(_) => true,
+            _                         => false,
+        };
+        let end_indent  = if is_foreign { min_indent } else { margin };
+        self.discard_current();
+        if indent < end_indent {
+            self.text_block_end(reader);
+            self.rewind_current(reader);
+            self.block_on_line_ending(reader);
+        } else {
+            let line_state = match self.text_block_state.kind {
+                TextBlockKind::Text(TextStyle::Raw)          => self.text_raw_block_line,
+                TextBlockKind::Text(TextStyle::Interpolated) => self.text_interpolated_block_line,
+                TextBlockKind::DocComment                    => self.doc_comment_line,
+                TextBlockKind::Foreign(_)                    => self.foreign_block_line,
+            };
+            if is_foreign {
+                self.text_block_state.begin_foreign_line(indentation);
+            } else {
+                if indent > margin {
+                    let extra_indent = indentation.slice(margin..indentation.len());
+                    self.append_token(Token::TextSegmentRaw(extra_indent,0));
+                }
+                self.text_block_state.begin_line(margin);
+            }
+            self.push_state(line_state);
+        }
+    }
+
+    /// Triggered when the input ends where the next line of a text block literal would begin.
+    fn text_block_on_eof_line<R:LazyReader>(&mut self, reader:&mut R) {
+        self.discard_current();
+        self.text_block_end(reader);
+        self.block_on_line_ending(reader);
+        self.block_in_eof_line(reader);
+    }
+
+    /// Triggered when a line in a text block literal reaches its end.
+    fn text_block_on_line_end<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_block_indent = self.text_block_indent;
+        self.pop_states_including(text_block_indent);
+    }
+
+    /// Triggered when a text block literal ends at the end of the line containing its opening
+    /// quotes, or at the end of the input.
+    fn text_block_on_end<R:LazyReader>(&mut self, reader:&mut R) {
+        self.text_block_end(reader);
+    }
+
+    /// Enter a text block literal of the provided `kind`.
+    fn text_block_begin(&mut self, kind:TextBlockKind) {
+        let text_block_newline = self.text_block_newline;
+        self.text_block_state.begin(kind);
+        self.text_begin(text_block_newline);
+    }
+
+    /// Leave the current text block literal, appending its token to the enclosing line.
+    fn text_block_end<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_block_newline = self.text_block_newline;
+        if self.text_block_state.in_line {
+            let segments = self.consume_tokens();
+            self.text_block_state.submit_line(segments.into(),token::LineEnding::None);
+        }
+        let (_,offset) = self.text_end(text_block_newline);
+        let token      = self.text_block_state.consume_token(offset);
+        self.append_token(token);
+    }
+
+    /// Enter the text `state`, collecting the tokens lexed within it separately.
+    fn text_begin(&mut self, state:group::Identifier) {
+        self.discard_current();
+        self.offset.push();
+        self.push_tokens();
+        self.push_state(state);
+    }
+
+    /// Leave the text `state`, returning the tokens lexed within it and the offset of the token
+    /// that it produces.
+    fn text_end(&mut self, state:group::Identifier) -> (Vec<Token>,usize) {
+        let tokens = self.consume_tokens();
+        self.pop_tokens();
+        self.offset.pop();
+        self.pop_states_including(state);
+        (tokens.into(),self.offset.consume())
+    }
+
+    /// Add the rules for lexing escape sequences to the text `group`.
+    fn add_text_escape_rules(group:&mut Group) {
+        let backslash      = c!('\\');
+        let hex_digit      = EnsoLexer::ascii_hex_digit();
+        let simple_escape  = &backslash >> Pattern::any_of("0abefnrtv\\\"'`");
+        let byte_escape    = &backslash >> c!('x') >> &hex_digit >> &hex_digit;
+        let unicode_escape = &backslash >> l!("u{") >> hex_digit.many1() >> c!('}');
+        let invalid_escape = &backslash >> Pattern::none_of("\r\n").opt();
+        group.create_rule(&simple_escape, "self.text_on_simple_escape(reader)");
+        group.create_rule(&byte_escape,   "self.text_on_byte_escape(reader)");
+        group.create_rule(&unicode_escape,"self.text_on_unicode_escape(reader)");
+        group.create_rule(&invalid_escape,"self.text_on_invalid_escape(reader)");
+    }
+
+    /// Define the rules for lexing inline Enso text literals.
+    fn add_text_line_rules(lexer:&mut EnsoLexer) {
+        let raw_quote            = c!('"');
+        let interpolated_quote   = c!('\'');
+        let backtick             = c!('`');
+        let raw_segment          = Pattern::none_of("\"\\\r\n").many1();
+        let interpolated_segment = Pattern::none_of("'`\\\r\n").many1();
+        let line_end             = Pattern::line_end();
+
+        let initial_state_id   = lexer.initial_state;
+        let initial_state      = lexer.group_mut(initial_state_id);
+        let interpolated_start = "self.text_on_interpolated_line_start(reader)";
+        initial_state.create_rule(&raw_quote,         "self.text_on_raw_line_start(reader)");
+        initial_state.create_rule(&interpolated_quote,interpolated_start);
+
+        let text_line_id = lexer.text_line;
+        let text_line    = lexer.group_mut(text_line_id);
+        text_line.create_rule(&raw_segment,"self.text_on_raw_segment(reader)");
+        EnsoLexer::add_text_escape_rules(text_line);
+        text_line.create_rule(&raw_quote,"self.text_on_raw_line_end(reader)");
+        text_line.create_rule(&line_end, "self.text_on_unclosed_raw_line(reader)");
+
+        let interpolated_id       = lexer.text_interpolated_line;
+        let interpolated          = lexer.group_mut(interpolated_id);
+        let interpolated_end      = "self.text_on_interpolated_line_end(reader)";
+        let interpolated_unclosed = "self.text_on_unclosed_interpolated_line(reader)";
+        interpolated.create_rule(&interpolated_segment,"self.text_on_raw_segment(reader)");
+        EnsoLexer::add_text_escape_rules(interpolated);
+        interpolated.create_rule(&backtick,          "self.text_on_splice_start(reader)");
+        interpolated.create_rule(&interpolated_quote,interpolated_end);
+        interpolated.create_rule(&line_end,          interpolated_unclosed);
+    }
+
+    /// Define the rules for lexing Enso text block literals.
+    fn add_text_block_rules(lexer:&mut EnsoLexer) {
+        let line_end            = Pattern::line_end();
+        let raw_quotes          = l!("\"\"\"") >> &line_end;
+        let interpolated_quotes = l!("'''") >> &line_end;
+        let raw_segment         = Pattern::none_of("\\\r\n").many1();
+        let foreign_segment     = Pattern::none_of("\r\n").many1();
+        let interpolated_seg    = Pattern::none_of("`\\\r\n").many1();
+        let backtick            = c!('`');
+        let blank_chars         = (c!(' ') | c!('\t')).many();
+        let lf                  = c!('\n');
+        let crlf                = l!("\r\n");
+        let empty_lf_line       = &blank_chars >> &lf;
+        let eof_line            = &blank_chars >> Pattern::eof();
+        let empty_crlf_line     = &blank_chars >> &crlf;
+
+        let initial_state_id   = lexer.initial_state;
+        let initial_state      = lexer.group_mut(initial_state_id);
+        let may_begin          = "self.text_block_may_begin()";
+        let raw_start          = "self.text_on_raw_block_start(reader)";
+        let raw_in_splice      = "self.text_on_raw_block_start_in_splice(reader)";
+        let interpolated_start = "self.text_on_interpolated_block_start(reader)";
+        let interpolated_in_splice = "self.text_on_interpolated_block_start_in_splice(reader)";
+        initial_state.create_guarded_rule(&raw_quotes,may_begin,raw_start);
+        initial_state.create_rule(&raw_quotes,raw_in_splice);
+        initial_state.create_guarded_rule(&interpolated_quotes,may_begin,interpolated_start);
+        initial_state.create_rule(&interpolated_quotes,interpolated_in_splice);
+
+        let newline_id = lexer.text_block_newline;
+        let newline    = lexer.group_mut(newline_id);
+        newline.create_rule(&lf,               "self.text_block_on_lf(reader)");
+        newline.create_rule(&crlf,             "self.text_block_on_crlf(reader)");
+        newline.create_rule(&Pattern::always(),"self.text_block_on_end(reader)");
+
+        let indent_id = lexer.text_block_indent;
+        let indent    = lexer.group_mut(indent_id);
+        indent.create_rule(&empty_lf_line,  "self.text_block_on_empty_lf_line(reader)");
+        indent.create_rule(&empty_crlf_line,"self.text_block_on_empty_crlf_line(reader)");
+        indent.create_rule(&eof_line,       "self.text_block_on_eof_line(reader)");
+        indent.create_rule(&blank_chars,    "self.text_block_on_line(reader)");
+
+        let raw_line_id = lexer.text_raw_block_line;
+        let raw_line    = lexer.group_mut(raw_line_id);
+        raw_line.create_rule(&raw_segment,"self.text_on_raw_segment(reader)");
+        EnsoLexer::add_text_escape_rules(raw_line);
+        raw_line.create_rule(&line_end,"self.text_block_on_line_end(reader)");
+
+        let interpolated_line_id = lexer.text_interpolated_block_line;
+        let interpolated_line    = lexer.group_mut(interpolated_line_id);
+        interpolated_line.create_rule(&interpolated_seg,"self.text_on_raw_segment(reader)");
+        EnsoLexer::add_text_escape_rules(interpolated_line);
+        interpolated_line.create_rule(&backtick,"self.text_on_splice_start(reader)");
+        interpolated_line.create_rule(&line_end,"self.text_block_on_line_end(reader)");
+
+        let foreign_line_id = lexer.foreign_block_line;
+        let foreign_line    = lexer.group_mut(foreign_line_id);
+        foreign_line.create_rule(&foreign_segment,"self.text_on_raw_segment(reader)");
+        foreign_line.create_rule(&line_end,"self.text_block_on_line_end(reader)");
+    }
+
+    /// Define the rules for lexing splices in interpolated Enso text literals.
+    fn add_text_splice_rules(lexer:&mut EnsoLexer) {
+        let backtick = c!('`');
+        let lf       = c!('\n');
+        let crlf     = l!("\r\n");
+
+        let initial_state_id = lexer.initial_state;
+        let initial_state    = lexer.group_mut(initial_state_id);
+        initial_state.create_rule(&backtick,"self.text_on_unmatched_backtick(reader)");
+
+        let text_splice_id = lexer.text_splice;
+        let text_splice    = lexer.group_mut(text_splice_id);
+        text_splice.create_rule(&backtick,"self.text_on_splice_end(reader)");
+        text_splice.create_rule(&lf,      "self.text_on_unclosed_splice(reader)");
+        text_splice.create_rule(&crlf,    "self.text_on_unclosed_splice(reader)");
+    }
+
     /// Define the rules for lexing Enso text literals.
-    fn add_text_rules(_lexer:&mut EnsoLexer) {
-        // TODO [AA] Write the lexing rules for text literals.
+    fn add_text_rules(lexer:&mut EnsoLexer) {
+        EnsoLexer::add_text_line_rules(lexer);
+        EnsoLexer::add_text_block_rules(lexer);
+        EnsoLexer::add_text_splice_rules(lexer);
+    }
+}
+
+
+// === Comment Rules ===
+
+/// The set of rules for lexing comments in the Enso language.
+#[allow(dead_code)]
+impl EnsoLexer {
+
+    /// Triggered on the `#` that starts a disable comment, which runs to the end of its line.
+    ///
+    /// The disabled code is lexed by the rules of the root state, which the disable comment state
+    /// inherits, while the text of the comment is recorded as it is lexed.
+    fn comment_on_disable_comment_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        let disable_comment = self.disable_comment;
+        self.begin_recording();
+        self.text_begin(disable_comment);
+    }
+
+    /// Triggered when a disable comment reaches the end of its line.
+    ///
+    /// The line ending itself is not consumed, and is instead lexed as part of the enclosing block.
+    fn comment_on_disable_comment_end<R:LazyReader>(&mut self, reader:&mut R) {
+        self.rewind_current(reader);
+        self.comment_close_disable_comment();
+    }
+
+    /// Leave the current disable comment, appending its token to the enclosing tokens.
+    ///
+    /// The tokens of the disabled code are kept even if they contain errors, as is the case for
+    /// most prose, so that consumers can decide whether to treat the comment as code.
+    fn comment_close_disable_comment(&mut self) {
+        let disable_comment = self.disable_comment;
+        let text            = self.end_recording();
+        self.discard_current();
+        let trailing_offset = self.offset.consume();
+        let (tokens,offset) = self.text_end(disable_comment);
+        let token           = Token::Comment(text,tokens.into(),trailing_offset,offset);
+        self.append_token(token);
+    }
+
+    /// Triggered on the first line of a documentation comment.
+    ///
+    /// The comment continues over the following lines using the same layout rules as a text block
+    /// literal.
+    fn comment_on_doc_comment<R:LazyReader>(&mut self, _reader:&mut R) {
+        let first_line = EnsoLexer::doc_comment_first_line(&self.current_match);
+        self.text_block_begin(TextBlockKind::DocComment);
+        self.text_block_state.push_first_line(first_line);
+    }
+
+    /// Triggered on a documentation comment within a splice or a disable comment.
+    ///
+    /// Neither of these can span multiple lines, so the comment consists of its first line alone.
+    fn comment_on_doc_comment_in_splice<R:LazyReader>(&mut self, _reader:&mut R) {
+        let first_line = EnsoLexer::doc_comment_first_line(&self.current_match);
+        self.discard_current();
+        let token = Token::DocComment(vec![first_line],0,self.offset.consume());
+        self.append_token(token);
+    }
+
+    /// Construct the first line of a documentation comment from the `line` that was matched,
+    /// including its leading `##`.
+    fn doc_comment_first_line(line:&MatchText) -> Token {
+        let text     = line.slice(2..line.len());
+        let segments = if text.is_empty() { vec![] } else { vec![Token::TextSegmentRaw(text,0)] };
+        Token::Line(segments,0,token::LineEnding::None)
+    }
+
+    /// Define the rules for lexing Enso comments.
+    fn add_comment_rules(lexer:&mut EnsoLexer) {
+        let line_text     = Pattern::none_of("\r\n");
+        let disable       = c!('#');
+        let doc_comment   = l!("##") >> line_text.many();
+        let doc_line      = line_text.many1();
+        let line_end      = Pattern::line_end();
+        let lf            = c!('\n');
+        let cr            = c!('\r');
+        let crlf          = l!("\r\n");
+        let may_begin     = "self.text_block_may_begin()";
+        let doc_start     = "self.comment_on_doc_comment(reader)";
+        let doc_splice    = "self.comment_on_doc_comment_in_splice(reader)";
+        let disable_start = "self.comment_on_disable_comment_start(reader)";
+        let disable_end   = "self.comment_on_disable_comment_end(reader)";
+
+        let initial_state_id = lexer.initial_state;
+        let initial_state    = lexer.group_mut(initial_state_id);
+        initial_state.create_guarded_rule(&doc_comment,may_begin,doc_start);
+        initial_state.create_rule(&doc_comment,doc_splice);
+        initial_state.create_rule(&disable,disable_start);
+
+        let disable_comment_id = lexer.disable_comment;
+        let disable_comment    = lexer.group_mut(disable_comment_id);
+        disable_comment.create_rule(&lf,  disable_end);
+        disable_comment.create_rule(&cr,  disable_end);
+        disable_comment.create_rule(&crlf,disable_end);
+
+        let doc_comment_line_id = lexer.doc_comment_line;
+        let doc_comment_line    = lexer.group_mut(doc_comment_line_id);
+        doc_comment_line.create_rule(&doc_line,"self.text_on_raw_segment(reader)");
+        doc_comment_line.create_rule(&line_end,"self.text_block_on_line_end(reader)");
     }
 }
 
@@ -505,25 +1178,65 @@ impl EnsoLexer {
         self.push_state(in_block_line);
     }
 
-    /// Triggered when lexing a non-blank line.
-    fn block_on_non_empty_line<R:LazyReader>(&mut self, reader:&mut R) {
+    /// Triggered on the indentation of a block line that contains tabs.
+    ///
+    /// Each tab counts as a single column, so the line is laid out as if it were indented by spaces
+    /// alone, but an invalid indentation token is pushed at the start of the line.
+    fn block_in_line_with_tabs<R:LazyReader>(&mut self, reader:&mut R) {
+        let indent = &self.current_match;
+        let column = indent.chars().position(|char| char == '\t').unwrap_or_default();
+        let style  = if indent.contains(' ') { IndentStyle::Mixed } else { IndentStyle::Tabs };
+        let line   = self.block_state.line;
+        let token  = Token::InvalidIndentation(style,line,column,0);
+        self.block_state.indentation_error = Some(token);
+        self.block_in_line(reader);
+    }
+
+    /// Push the invalid indentation of the current line, if it has one.
+    fn block_submit_indentation_error(&mut self) {
+        if let Some(token) = self.block_state.indentation_error.take() {
+            self.append_token(token);
+        }
+    }
+
+    /// Whether the current line has the same indentation as the current block.
+    fn block_line_has_block_indent(&self) -> bool {
+        self.offset.current == self.block_state.current().indent
+    }
+
+    /// Whether the current line is indented further than the current block.
+    fn block_line_is_indented(&self) -> bool {
+        self.offset.current > self.block_state.current().indent
+    }
+
+    /// Common functionality for all non-blank lines.
+    fn block_on_non_empty_line<R:LazyReader>(&mut self, _reader:&mut R) {
         let block_newline = self.block_newline;
         self.pop_states_including(block_newline);
+    }
 
-        match self.offset.current.cmp(&self.block_state.current().indent) {
-            Ordering::Equal => {
-                self.offset.consume();
-                self.block_submit_line(reader);
-            },
-            Ordering::Greater => {
-                let new_indent = self.offset.consume();
-                self.begin_block(new_indent,reader);
-            },
-            Ordering::Less => {
-                let new_indent = self.offset.consume();
-                self.on_block_end(new_indent,reader);
-            }
-        }
+    /// Triggered when lexing a non-blank line that continues the current block.
+    fn block_on_line_in_block<R:LazyReader>(&mut self, reader:&mut R) {
+        self.block_on_non_empty_line(reader);
+        self.offset.consume();
+        self.block_submit_line(reader);
+        self.block_submit_indentation_error();
+    }
+
+    /// Triggered when lexing a non-blank line that begins a new block.
+    fn block_on_indented_line<R:LazyReader>(&mut self, reader:&mut R) {
+        self.block_on_non_empty_line(reader);
+        let new_indent = self.offset.consume();
+        self.begin_block(new_indent,reader);
+        self.block_submit_indentation_error();
+    }
+
+    /// Triggered when lexing a non-blank line that ends the current block.
+    fn block_on_dedented_line<R:LazyReader>(&mut self, reader:&mut R) {
+        self.block_on_non_empty_line(reader);
+        let new_indent = self.offset.consume();
+        self.on_block_end(new_indent,reader);
+        self.block_submit_indentation_error();
     }
 
     /// Triggered when lexing a block line that is empty and ends in a unix-style line ending.
@@ -546,7 +1259,11 @@ impl EnsoLexer {
     }
 
     /// Triggered when lexing an empty line in a block.
+    ///
+    /// The indentation of an empty line has no bearing on the layout of the block, so it is not
+    /// reported even if it contains tabs.
     fn block_in_empty_line<R:LazyReader>(&mut self, reader:&mut R) {
+        self.block_state.indentation_error = None;
         self.block_submit_line(reader);
         let offset        = self.offset.consume();
         let block_newline = self.block_newline;
@@ -563,10 +1280,9 @@ impl EnsoLexer {
 
     /// Triggered when beginning a top-level block.
     fn block_begin_top_level<R:LazyReader>(&mut self, reader:&mut R) {
-        let matched_bookmark = self.bookmarks.matched_bookmark;
-        let block_newline    = self.block_newline;
+        let block_newline = self.block_newline;
         let initial_state = self.initial_state;
-        self.bookmarks.rewind(matched_bookmark,reader);
+        self.rewind_current(reader);
         self.offset.push();
         self.pop_states_until(initial_state);
         self.push_state(block_newline);
@@ -644,11 +1360,14 @@ impl EnsoLexer {
 
     /// The rule definitions for lexing blocks in Enso.
     fn add_block_rules(lexer:&mut EnsoLexer) {
-        let spaces     = EnsoLexer::spaces();
-        let lf         = c!('\n');
-        let crlf       = l!("\r\n");
-        let opt_spaces = spaces.opt();
-        let eof_line   = &opt_spaces >> Pattern::eof();
+        let spaces      = EnsoLexer::spaces();
+        let lf          = c!('\n');
+        let crlf        = l!("\r\n");
+        let opt_spaces  = spaces.opt();
+        let tab         = c!('\t');
+        let blank_chars = (c!(' ') | &tab).many();
+        let tab_indent  = &opt_spaces >> &tab >> &blank_chars;
+        let eof_line    = &blank_chars >> Pattern::eof();
 
         let root_state_id = lexer.initial_state;
         let root_state    = lexer.group_mut(root_state_id);
@@ -658,13 +1377,20 @@ impl EnsoLexer {
         let block_newline_id = lexer.block_newline;
         let block_newline    = lexer.group_mut(block_newline_id);
         block_newline.create_rule(&opt_spaces,"self.block_in_line(reader)");
+        block_newline.create_rule(&tab_indent,"self.block_in_line_with_tabs(reader)");
         block_newline.create_rule(&eof_line,  "self.block_in_eof_line(reader)");
 
         let in_block_line_id = lexer.in_block_line;
         let in_block_line    = lexer.group_mut(in_block_line_id);
+        let line_in_block    = "self.block_line_has_block_indent()";
+        let indented_line    = "self.block_line_is_indented()";
         in_block_line.create_rule(&lf,               "self.block_on_empty_lf_line(reader)");
         in_block_line.create_rule(&crlf,             "self.block_on_empty_crlf_line(reader)");
-        in_block_line.create_rule(&Pattern::always(),"self.block_on_non_empty_line(reader)");
+        in_block_line.create_guarded_rule
+            (&Pattern::always(),line_in_block,"self.block_on_line_in_block(reader)");
+        in_block_line.create_guarded_rule
+            (&Pattern::always(),indented_line,"self.block_on_indented_line(reader)");
+        in_block_line.create_rule(&Pattern::always(),"self.block_on_dedented_line(reader)");
 
         let block_module_id = lexer.block_top_level;
         let block_module    = lexer.group_mut(block_module_id);
@@ -688,12 +1414,33 @@ impl EnsoLexer {
 
     /// Triggered on an arbitrary eof character.
     fn on_eof<R:LazyReader>(&mut self, reader:&mut R) {
+        self.close_nested_at_eof(reader);
         self.offset.push();
         self.block_submit_line(reader);
         self.on_block_end(0,reader);
         self.block_end_top_level(reader);
     }
 
+    /// Close the splices and disable comments that are still open at the end of the input, along
+    /// with any literals that contain them.
+    ///
+    /// These states inherit the end of input rule of the root state, so they must be closed here
+    /// rather than by rules of their own.
+    fn close_nested_at_eof<R:LazyReader>(&mut self, reader:&mut R) {
+        loop {
+            if self.is_in_state(self.text_splice) {
+                self.text_close_unclosed_splice(reader);
+            } else if self.is_in_state(self.disable_comment) {
+                self.comment_close_disable_comment();
+            } else if self.is_in_state(self.text_interpolated_block_line) {
+                self.text_block_on_line_end(reader);
+                self.text_block_end(reader);
+            } else {
+                break
+            }
+        }
+    }
+
     /// Triggered on any unrecognized character.
     fn on_unrecognized<R:LazyReader>(&mut self, _reader:&mut R) {
         let token = Token::Unrecognized(self.consume_current(),self.offset.consume());
@@ -726,6 +1473,7 @@ impl flexer::Definition for EnsoLexer {
         EnsoLexer::add_identifier_rules(&mut lexer);
         EnsoLexer::add_number_rules(&mut lexer);
         EnsoLexer::add_text_rules(&mut lexer);
+        EnsoLexer::add_comment_rules(&mut lexer);
         EnsoLexer::add_block_rules(&mut lexer);
         EnsoLexer::add_default_rules(&mut lexer);
 
@@ -742,6 +1490,10 @@ impl flexer::Definition for EnsoLexer {
     }
 
     fn tear_down(&mut self) {}
+
+    fn reset(&mut self) {
+        self.0.reset()
+    }
 }
 
 impl Default for EnsoLexer {
@@ -785,6 +1537,26 @@ pub struct State<Logger> {
     block_newline : group::Identifier,
     /// The state entered when within the line of a block.
     in_block_line : group::Identifier,
+    /// The state entered when within an inline raw text literal.
+    text_line : group::Identifier,
+    /// The state entered when within an inline interpolated text literal.
+    text_interpolated_line : group::Identifier,
+    /// The state entered when within a splice in an interpolated text literal.
+    text_splice : group::Identifier,
+    /// The state entered when a text block literal expects a line ending.
+    text_block_newline : group::Identifier,
+    /// The state entered at the start of a line in a text block literal.
+    text_block_indent : group::Identifier,
+    /// The state entered when within a line of a raw text block literal.
+    text_raw_block_line : group::Identifier,
+    /// The state entered when within a line of an interpolated text block literal.
+    text_interpolated_block_line : group::Identifier,
+    /// The state entered when within a line of a documentation comment.
+    doc_comment_line : group::Identifier,
+    /// The state entered when within a disable comment.
+    disable_comment : group::Identifier,
+    /// The state entered when within a line of the body of a foreign definition.
+    foreign_block_line : group::Identifier,
     /// A stack of token matches.
     tokens_stack : Vec<token::Stream>,
     /// Tracking for the current offset.
@@ -792,7 +1564,11 @@ pub struct State<Logger> {
     /// State specifically for lexing Enso numbers.
     number_state : NumberLexingState<Logger>,
     /// State specifically for lexing Enso blocks.
-    block_state : BlockLexingState<Logger>
+    block_state : BlockLexingState<Logger>,
+    /// State specifically for lexing Enso text blocks and documentation comments.
+    text_block_state : TextBlockLexingState<Logger>,
+    /// The keywords of the version of the language being lexed.
+    keywords : Keywords,
 }
 
 impl<Logger:AnyLogger<Owned=Logger>> State<Logger> {
@@ -826,6 +1602,19 @@ impl<Logger:AnyLogger<Owned=Logger>> flexer::State for State<Logger> {
         let block_top_level     = lexer_states.define_group("BLOCK_MODULE", None);
         let block_newline       = lexer_states.define_group("BLOCK_NEWLINE",None);
         let in_block_line       = lexer_states.define_group("IN_BLOCK_LINE",None);
+        let text_line           = lexer_states.define_group("TEXT_LINE",None);
+        let text_interpolated_line =
+            lexer_states.define_group("TEXT_INTERPOLATED_LINE",None);
+        let text_splice         = lexer_states.define_group("TEXT_SPLICE",Some(initial_state));
+        let text_block_newline  = lexer_states.define_group("TEXT_BLOCK_NEWLINE",None);
+        let text_block_indent   = lexer_states.define_group("TEXT_BLOCK_INDENT",None);
+        let text_raw_block_line = lexer_states.define_group("TEXT_RAW_BLOCK_LINE",None);
+        let text_interpolated_block_line =
+            lexer_states.define_group("TEXT_INTERPOLATED_BLOCK_LINE",None);
+        let doc_comment_line    = lexer_states.define_group("DOC_COMMENT_LINE",None);
+        let disable_comment     =
+            lexer_states.define_group("DISABLE_COMMENT",Some(initial_state));
+        let foreign_block_line  = lexer_states.define_group("FOREIGN_BLOCK_LINE",None);
         let tokens_stack        = Vec::new();
         let offset_logger       = <Logger>::sub(&logger,"Offset");
         let offset              = Offset::new(offset_logger);
@@ -833,6 +1622,9 @@ impl<Logger:AnyLogger<Owned=Logger>> flexer::State for State<Logger> {
         let number_state        = NumberLexingState::new(number_state_logger);
         let block_state_logger  = <Logger>::sub(&logger,"BlockLexingState");
         let block_state         = BlockLexingState::new(block_state_logger);
+        let text_block_logger   = <Logger>::sub(&logger,"TextBlockLexingState");
+        let text_block_state    = TextBlockLexingState::new(text_block_logger);
+        let keywords            = default();
 
         Self
         { logger
@@ -848,10 +1640,22 @@ impl<Logger:AnyLogger<Owned=Logger>> flexer::State for State<Logger> {
         , block_top_level
         , block_newline
         , in_block_line
+        , text_line
+        , text_interpolated_line
+        , text_splice
+        , text_block_newline
+        , text_block_indent
+        , text_raw_block_line
+        , text_interpolated_block_line
+        , doc_comment_line
+        , disable_comment
+        , foreign_block_line
         , tokens_stack
         , offset
         , number_state
         , block_state
+        , text_block_state
+        , keywords
         }
     }
 
@@ -875,6 +1679,15 @@ impl<Logger:AnyLogger<Owned=Logger>> flexer::State for State<Logger> {
         &mut self.bookmarks
     }
 
+    fn reset(&mut self) {
+        self.bookmarks = default();
+        self.tokens_stack.clear();
+        self.offset.reset();
+        self.number_state.reset();
+        self.block_state.reset();
+        self.text_block_state.reset();
+    }
+
     fn specialize(&self) -> Result<String, GenError> {
         generate::specialize(self,"EnsoLexer","token::Stream")
     }
@@ -882,6 +1695,50 @@ impl<Logger:AnyLogger<Owned=Logger>> flexer::State for State<Logger> {
 
 
 
+// ================
+// === Keywords ===
+// ================
+
+/// The table of keywords recognised by the lexer.
+///
+/// The keywords differ between versions of the language, so a lexer can be constructed with the
+/// table for the version of the code that it lexes. A variable identifier is a keyword only if it
+/// matches an entry in full, so ticked names such as `type'` are never keywords.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Keywords {
+    /// The names of the keywords.
+    names : HashSet<String>,
+}
+
+impl Keywords {
+    /// Construct a keyword table containing the provided `names`.
+    pub fn new<S:Str>(names:impl IntoIterator<Item=S>) -> Self {
+        let names = names.into_iter().map(|name| name.into()).collect();
+        Keywords{names}
+    }
+
+    /// The keywords of the current version of the language.
+    pub fn current() -> Self {
+        Self::new(vec!["type","import","from","polyglot","foreign","case","of","here"])
+    }
+
+    /// Whether `name` is a keyword.
+    pub fn contains(&self, name:&str) -> bool {
+        self.names.contains(name)
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Default for Keywords {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+
+
 // =========================
 // === Offset Management ===
 // =========================
@@ -908,6 +1765,13 @@ impl<Logger:AnyLogger> Offset<Logger> {
         Offset{current,stack,logger}
     }
 
+    /// Reset the offset state.
+    pub fn reset(&mut self) {
+        self.current = 0;
+        self.stack.clear();
+        debug!(self.logger,"Reset Offset");
+    }
+
     /// Push the current offset onto the offset stack.
     pub fn push(&mut self) {
         debug!(self.logger,"Push Offset: {self.current}");
@@ -977,9 +1841,41 @@ impl<Logger:AnyLogger> NumberLexingState<Logger> {
     }
 
     /// Convert `self` into a token, resetting the lexing state.
+    ///
+    /// The literal is split into its integral, fractional and exponent parts. If it has an explicit
+    /// base, each of its digits is checked against that base, and an invalid number token is
+    /// produced instead if any of them is not valid in it.
     pub fn consume_token(&mut self, offset:usize) -> Token {
         debug!(self.logger,"Consuming Number: Base = {self.base}, Number = {self.literal}");
-        Token::Number(mem::take(&mut self.base),mem::take(&mut self.literal),offset)
+        let base    = mem::take(&mut self.base);
+        let literal = mem::take(&mut self.literal);
+        if let Some(invalid_digit) = Self::first_invalid_digit(&base,&literal) {
+            return Token::InvalidNumber(base,literal,invalid_digit,offset)
+        }
+        let exponent_parts       = Self::split_at_first(&literal,'e');
+        let exponent_parts       = exponent_parts.or_else(|| Self::split_at_first(&literal,'E'));
+        let (mantissa,exponent)  = match exponent_parts {
+            Some(parts) if base.is_empty() => parts,
+            _                              => (literal.as_str(),""),
+        };
+        let (integer,fractional) = Self::split_at_first(mantissa,'.').unwrap_or((mantissa,""));
+        Token::Number(base,integer,fractional,exponent,offset)
+    }
+
+    /// Split `text` around the first occurrence of `separator`, if it contains one.
+    fn split_at_first(text:&str, separator:char) -> Option<(&str,&str)> {
+        text.find(separator).map(|ix| (&text[..ix],&text[ix + separator.len_utf8()..]))
+    }
+
+    /// Find the index (in characters) of the first digit of `literal` that is not valid in `base`.
+    ///
+    /// Numbers with an implicit base only ever contain decimal digits, while an explicit base
+    /// outside of the range from 2 to 36 has no valid digits at all.
+    fn first_invalid_digit(base:&str, literal:&str) -> Option<usize> {
+        if base.is_empty() { return None }
+        let radix    = base.parse::<u32>().ok().filter(|radix| (2..=36).contains(radix));
+        let is_valid = |digit:char| radix.map(|radix| digit.is_digit(radix)).unwrap_or(false);
+        literal.chars().position(|char| char != '.' && !is_valid(char))
     }
 
     /// Take the `literal` portion of the number lexing state.
@@ -1006,6 +1902,10 @@ pub struct BlockLexingState<Logger> {
     stack : NonEmptyVec<BlockState>,
     /// Whether or not the lexer has seen an explicit newline.
     seen_newline : bool,
+    /// The line of the input being lexed, counting from zero.
+    line : usize,
+    /// The invalid indentation of the line being lexed, if it has one.
+    indentation_error : Option<Token>,
     /// A logger for the lexing state.
     logger : Logger,
 }
@@ -1013,15 +1913,33 @@ pub struct BlockLexingState<Logger> {
 impl<Logger:AnyLogger> BlockLexingState<Logger> {
     /// Construct a new block lexing state.
     pub fn new(logger:Logger) -> Self {
-        let stack        = NonEmptyVec::singleton(default());
-        let seen_newline = false;
-        BlockLexingState{stack,seen_newline,logger}
+        let stack             = NonEmptyVec::singleton(default());
+        let seen_newline      = false;
+        let line              = 0;
+        let indentation_error = None;
+        BlockLexingState{stack,seen_newline,line,indentation_error,logger}
     }
 
-    /// Set the last seen line ending.
+    /// Reset the block lexing state.
+    pub fn reset(&mut self) {
+        self.stack             = NonEmptyVec::singleton(default());
+        self.seen_newline      = false;
+        self.line              = 0;
+        self.indentation_error = None;
+        debug!(self.logger,"Reset Block State");
+    }
+
+    /// Set the last seen line ending, moving on to the next line of the input.
     pub fn push_line_ending(&mut self, line_ending:token::LineEnding) {
         self.current_mut().seen_line_endings.push_back(line_ending);
         debug!(self.logger,"Push Line Ending: {line_ending:?}");
+        self.next_line();
+    }
+
+    /// Move on to the next line of the input.
+    pub fn next_line(&mut self) {
+        self.line += 1;
+        debug!(self.logger,"Next Line: {self.line}");
     }
 
     /// Consume the last seen line ending.
@@ -1082,6 +2000,149 @@ impl<Logger:AnyLogger> BlockLexingState<Logger> {
 
 
 
+// ============================
+// === TextBlockLexingState ===
+// ============================
+
+/// The kinds of construct that are laid out as text blocks in Enso.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum TextBlockKind {
+    /// A text block literal of the provided style.
+    Text(TextStyle),
+    /// A documentation comment.
+    DocComment,
+    /// The body of a foreign definition in the provided language.
+    Foreign(String),
+}
+
+/// The state for lexing a text block literal, a documentation comment or the body of a foreign
+/// definition in Enso.
+#[derive(Clone,Debug,PartialEq)]
+pub struct TextBlockLexingState<Logger> {
+    /// The kind of the text block.
+    pub kind : TextBlockKind,
+    /// The baseline margin of the text block, which is set by its first non-blank line.
+    pub indent : Option<usize>,
+    /// The lines of the text block that have been lexed so far.
+    pub lines : Vec<Token>,
+    /// The indentation of each non-blank line in the body of a foreign definition, which is only
+    /// stripped once the margin of the body is known.
+    pub line_indents : Vec<MatchText>,
+    /// Whether or not the lexer is within a non-blank line of the text block.
+    pub in_line : bool,
+    /// A logger for the text block state.
+    logger : Logger,
+}
+
+impl<Logger:AnyLogger> TextBlockLexingState<Logger> {
+    /// Construct a new text block lexing state.
+    pub fn new(logger:Logger) -> Self {
+        let kind         = TextBlockKind::Text(TextStyle::Raw);
+        let indent       = None;
+        let lines        = default();
+        let line_indents = default();
+        let in_line      = false;
+        TextBlockLexingState{kind,indent,lines,line_indents,in_line,logger}
+    }
+
+    /// Reset the text block lexing state.
+    pub fn reset(&mut self) {
+        self.kind    = TextBlockKind::Text(TextStyle::Raw);
+        self.indent  = None;
+        self.in_line = false;
+        self.lines.clear();
+        self.line_indents.clear();
+        debug!(self.logger,"Reset Text Block State");
+    }
+
+    /// Begin lexing a text block of the provided `kind`.
+    pub fn begin(&mut self, kind:TextBlockKind) {
+        debug!(self.logger,"Begin Text Block: Kind = {kind:?}");
+        self.reset();
+        self.kind = kind;
+    }
+
+    /// Push the first line of a documentation comment, which is not subject to the margin.
+    pub fn push_first_line(&mut self, line:Token) {
+        debug!(self.logger,"Push First Line: {&line:?}");
+        self.lines.push(line);
+    }
+
+    /// Begin a non-blank line in the text block, setting the baseline margin to `indent` if it
+    /// has not yet been set.
+    pub fn begin_line(&mut self, indent:usize) {
+        self.indent  = Some(indent);
+        self.in_line = true;
+    }
+
+    /// Begin a non-blank line with the provided `indentation` in the body of a foreign definition,
+    /// lowering the margin of the body to the width of `indentation` if it is smaller.
+    pub fn begin_foreign_line(&mut self, indentation:MatchText) {
+        let indent   = indentation.chars().count();
+        self.indent  = Some(self.indent.map_or(indent,|margin| margin.min(indent)));
+        self.in_line = true;
+        self.line_indents.push(indentation);
+    }
+
+    /// Submit the non-blank line made up of `segments` to the text block.
+    pub fn submit_line(&mut self, segments:Vec<Token>, trailing_line_ending:token::LineEnding) {
+        debug!(
+            self.logger,
+            "Submit Text Line: Line Ending = {trailing_line_ending:?}, Segments = {&segments:?}"
+        );
+        self.lines.push(Token::Line(segments,0,trailing_line_ending));
+        self.in_line = false;
+    }
+
+    /// Push a blank line into the text block.
+    ///
+    /// The offset here should be the offset from the leftmost column, not from the margin.
+    pub fn push_empty_line(&mut self, offset:usize, trailing_line_ending:token::LineEnding) {
+        self.lines.push(Token::BlankLine(offset,trailing_line_ending));
+        debug!(self.logger,"Push Empty Text Line: Line Ending = {trailing_line_ending:?}");
+    }
+
+    /// Convert the text block into a token, resetting the lexing state.
+    pub fn consume_token(&mut self, offset:usize) -> Token {
+        let lines  = mem::take(&mut self.lines);
+        let indent = self.indent.unwrap_or(0);
+        let token  = match mem::replace(&mut self.kind,TextBlockKind::Text(TextStyle::Raw)) {
+            TextBlockKind::Text(style)       => Token::TextBlock(style,indent,lines,offset),
+            TextBlockKind::DocComment        => Token::DocComment(lines,indent,offset),
+            TextBlockKind::Foreign(language) => {
+                let lines = self.strip_foreign_margin(lines,indent);
+                Token::ForeignBlock(language,indent,lines,offset)
+            }
+        };
+        debug!(self.logger,"Consume Text Block: {&token:?}");
+        self.reset();
+        token
+    }
+
+    /// Strip the `margin` from the indentation of each non-blank line in the body of a foreign
+    /// definition, keeping the rest of the indentation at the start of the raw text of the line.
+    fn strip_foreign_margin(&mut self, lines:Vec<Token>, margin:usize) -> Vec<Token> {
+        let mut line_indents = mem::take(&mut self.line_indents).into_iter();
+        lines.into_iter().map(|line| match line.shape {
+            token::Shape::Line{tokens,trailing_line_ending} => {
+                let indentation = line_indents.next().unwrap_or_default();
+                let end         = indentation.len();
+                let mut text    = indentation.slice(margin.min(end)..end);
+                for token in &tokens {
+                    if let token::Shape::TextSegmentRaw(segment) = &token.shape {
+                        text.push(segment);
+                    }
+                }
+                let segments = vec![Token::TextSegmentRaw(text,0)];
+                Token::Line(segments,line.offset,trailing_line_ending)
+            }
+            _ => line,
+        }).collect()
+    }
+}
+
+
+
 // ==================
 // === BlockState ===
 // ==================
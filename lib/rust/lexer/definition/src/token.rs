@@ -28,13 +28,18 @@ impl Token {
     pub fn source_length(&self) -> usize {
         self.length + self.offset
     }
+
+    /// Check whether the token is an error, or contains one.
+    pub fn has_errors(&self) -> bool {
+        self.shape.is_error() || self.shape.children().iter().any(Token::has_errors)
+    }
 }
 
 /// Constructors for the various forms of token.
 #[allow(non_snake_case)]
 impl Token {
     /// Construct a token representing a referent identifier.
-    pub fn Referent(name:impl Str, offset:usize) -> Token {
+    pub fn Referent(name:impl Into<MatchText>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count();
         let shape  = Shape::Referent(str);
@@ -42,7 +47,7 @@ impl Token {
     }
 
     /// Construct a token representing a variable identifier.
-    pub fn Variable(name:impl Str, offset:usize) -> Token {
+    pub fn Variable(name:impl Into<MatchText>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count();
         let shape  = Shape::Variable(str);
@@ -50,7 +55,7 @@ impl Token {
     }
 
     /// Construct a token representing an external identifier.
-    pub fn External(name:impl Str, offset:usize) -> Token {
+    pub fn External(name:impl Into<MatchText>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count();
         let shape  = Shape::External(str);
@@ -64,8 +69,16 @@ impl Token {
         Token{shape,length,offset}
     }
 
+    /// Construct a token representing a keyword.
+    pub fn Keyword(name:impl Into<MatchText>, offset:usize) -> Token {
+        let str    = name.into();
+        let length = str.chars().count();
+        let shape  = Shape::Keyword(str);
+        Token{shape,length,offset}
+    }
+
     /// Construct a token representing an operator.
-    pub fn Operator(name:impl Str, offset:usize) -> Token {
+    pub fn Operator(name:impl Into<MatchText>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count();
         let shape  = Shape::Operator(str);
@@ -73,23 +86,42 @@ impl Token {
     }
 
     /// Construct a token representing a modifier operator.
-    pub fn Modifier(name:impl Str, offset:usize) -> Token {
+    pub fn Modifier(name:impl Into<MatchText>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count() + 1;
         let shape  = Shape::Modifier(str);
         Token{shape,length,offset}
     }
 
+    /// Construct a token representing an annotation.
+    ///
+    /// The length of the token includes its leading `@`.
+    pub fn Annotation(name:impl Into<MatchText>, offset:usize) -> Token {
+        let str    = name.into();
+        let length = 1 + str.chars().count();
+        let shape  = Shape::Annotation(str);
+        Token{shape,length,offset}
+    }
+
     /// Construct a token representing a number literal.
-    pub fn Number(base:impl Str, num:impl Into<String>, offset:usize) -> Token {
-        let str      = num.into();
-        let base_str = base.into();
-        let length   = if base_str.is_empty() {
-            str.chars().count()
-        } else {
-            base_str.chars().count() + 1 + str.chars().count()
-        };
-        let shape = Shape::Number{base:base_str,number:str};
+    ///
+    /// Each of the `base`, `fractional` and `exponent` parts is empty when it is not present in
+    /// the literal. The length of the token includes the separators between the parts.
+    pub fn Number
+    ( base       : impl Str
+    , integer    : impl Str
+    , fractional : impl Str
+    , exponent   : impl Str
+    , offset     : usize
+    ) -> Token {
+        let base       = base.into();
+        let integer    = integer.into();
+        let fractional = fractional.into();
+        let exponent   = exponent.into();
+        let part_len   = |part:&String| if part.is_empty() { 0 } else { part.chars().count() + 1 };
+        let length     = part_len(&base) + integer.chars().count() + part_len(&fractional)
+            + part_len(&exponent);
+        let shape      = Shape::Number{base,integer,fractional,exponent};
         Token{shape,length,offset}
     }
 
@@ -101,11 +133,124 @@ impl Token {
         Token{shape,length,offset}
     }
 
-    /// Construct a token representing a text literal.
-    pub fn Text(text:impl Str, offset:usize) -> Token {
+    /// Construct a token representing an inline text literal.
+    ///
+    /// The length of the token includes both of its quotes.
+    pub fn TextLine(style:TextStyle, segments:Vec<Token>, offset:usize) -> Token {
+        let length = segments.iter().fold(2,|l,r| l + r.offset + r.length);
+        let shape  = Shape::TextLine{style,segments};
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing a text block literal.
+    ///
+    /// The `indent` is the baseline margin of the block, which is stripped from each of its
+    /// `lines`. The length of the token includes its three opening quotes, but not the line ending
+    /// that follows them, which ends the line containing the literal.
+    pub fn TextBlock
+    ( style  : TextStyle
+    , indent : usize
+    , lines  : Vec<Token>
+    , offset : usize
+    ) -> Token {
+        let length = 3 + lines.iter().map(|line| {
+            let line_length = line.length;
+            let line_offset = line.offset;
+            match line.shape {
+                Shape::Line{..}     => indent + line_offset + line_length,
+                Shape::BlankLine(_) => line_offset + line_length,
+                _ => unreachable_panic!("Tokens in a text block should always be lines."),
+            }
+        }).sum::<usize>();
+        let shape = Shape::TextBlock{style,indent,lines};
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing a run of raw characters in a text literal.
+    pub fn TextSegmentRaw(text:impl Into<MatchText>, offset:usize) -> Token {
         let str    = text.into();
         let length = str.chars().count();
-        let shape  = Shape::Text(str);
+        let shape  = Shape::TextSegmentRaw(str);
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing an escape sequence in a text literal.
+    ///
+    /// The `repr` is the escape sequence as written in the source, including its leading backslash.
+    pub fn TextSegmentEscape
+    (style:EscapeStyle, repr:impl Into<MatchText>, offset:usize) -> Token {
+        let repr   = repr.into();
+        let length = repr.chars().count();
+        let shape  = Shape::TextSegmentEscape{style,repr};
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing a splice in an interpolated text literal.
+    ///
+    /// The length of the token includes both of its backticks, as well as the `trailing_offset`
+    /// between the last token in the splice and its closing backtick.
+    pub fn TextSegmentSplice(tokens:Stream, trailing_offset:usize, offset:usize) -> Token {
+        let length = 2 + tokens.tokens_len() + trailing_offset;
+        let shape  = Shape::TextSegmentSplice{tokens,trailing_offset};
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing a disable comment.
+    ///
+    /// The `text` follows the leading `#` up to the end of the line, and the `trailing_offset` is
+    /// the number of spaces at the end of `text`. The length of the token includes the leading `#`.
+    pub fn Comment
+    (text:impl Into<MatchText>, tokens:Stream, trailing_offset:usize, offset:usize) -> Token {
+        let text   = text.into();
+        let length = 1 + text.chars().count();
+        let shape  = Shape::Comment{text,tokens,trailing_offset};
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing a documentation comment.
+    ///
+    /// The first of the `lines` holds the text on the line of the leading `##`, while the rest are
+    /// the lines that continue the comment, with the baseline margin of `indent` stripped. The
+    /// length of the token includes the leading `##`, but not the line ending that follows its
+    /// first line, which ends the line containing the comment.
+    pub fn DocComment(lines:Vec<Token>, indent:usize, offset:usize) -> Token {
+        let first_line_length = lines.first().map(|line| line.length).unwrap_or(0);
+        let length = 2 + first_line_length + lines.iter().skip(1).map(|line| {
+            let line_length = line.length;
+            let line_offset = line.offset;
+            match line.shape {
+                Shape::Line{..}     => indent + line_offset + line_length,
+                Shape::BlankLine(_) => line_offset + line_length,
+                _ => unreachable_panic!("Tokens in a doc comment should always be lines."),
+            }
+        }).sum::<usize>();
+        let shape = Shape::DocComment{lines,indent};
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing the body of a foreign definition.
+    ///
+    /// The `indent` is the margin of the body, which is the smallest indentation of any of its
+    /// `lines` and is stripped from each of them.
+    /// The length of the token includes its three opening quotes, but not the line ending that
+    /// follows them, which ends the line containing the definition.
+    pub fn ForeignBlock
+    ( language : impl Str
+    , indent   : usize
+    , lines    : Vec<Token>
+    , offset   : usize
+    ) -> Token {
+        let language = language.into();
+        let length   = 3 + lines.iter().map(|line| {
+            let line_length = line.length;
+            let line_offset = line.offset;
+            match line.shape {
+                Shape::Line{..}     => indent + line_offset + line_length,
+                Shape::BlankLine(_) => line_offset + line_length,
+                _ => unreachable_panic!("Tokens in a foreign block should always be lines."),
+            }
+        }).sum::<usize>();
+        let shape = Shape::ForeignBlock{language,indent,lines};
         Token{shape,length,offset}
     }
 
@@ -148,15 +293,86 @@ impl Token {
     }
 
     /// Construct a token representing an invalid suffix.
-    pub fn InvalidSuffix(text:impl Str, offset:usize) -> Token {
+    pub fn InvalidSuffix(text:impl Into<MatchText>, offset:usize) -> Token {
         let str    = text.into();
         let length = str.chars().count();
         let shape  = Shape::InvalidSuffix(str);
         Token{shape,length,offset}
     }
 
+    /// Construct a token representing an inline text literal that is not closed before the end of
+    /// its line.
+    ///
+    /// The length of the token includes its opening quote.
+    pub fn UnclosedTextLine(style:TextStyle, segments:Vec<Token>, offset:usize) -> Token {
+        let length = segments.iter().fold(1,|l,r| l + r.offset + r.length);
+        let shape  = Shape::UnclosedTextLine{style,segments};
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing a splice in an interpolated text literal that is not closed
+    /// before the end of its line.
+    ///
+    /// The length of the token includes its opening backtick.
+    pub fn UnclosedTextSplice(tokens:Stream, trailing_offset:usize, offset:usize) -> Token {
+        let length = 1 + tokens.tokens_len() + trailing_offset;
+        let shape  = Shape::UnclosedTextSplice{tokens,trailing_offset};
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing a backtick that does not close a splice.
+    pub fn UnmatchedBacktick(offset:usize) -> Token {
+        let shape  = Shape::UnmatchedBacktick;
+        let length = 1;
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing an annotation whose name is not a valid annotation name.
+    ///
+    /// The `text` is whatever follows the `@`, and is empty when the `@` is not followed by a name
+    /// at all. The length of the token includes the `@`.
+    pub fn InvalidAnnotation(text:impl Into<MatchText>, offset:usize) -> Token {
+        let str    = text.into();
+        let length = 1 + str.chars().count();
+        let shape  = Shape::InvalidAnnotation(str);
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing a number literal with an explicit base that contains a digit
+    /// that is not valid in that base.
+    ///
+    /// The `invalid_digit` is the index (in characters) of the first such digit in `number`.
+    pub fn InvalidNumber
+    (base:impl Str, number:impl Str, invalid_digit:usize, offset:usize) -> Token {
+        let base   = base.into();
+        let number = number.into();
+        let length = base.chars().count() + 1 + number.chars().count();
+        let shape  = Shape::InvalidNumber{base,number,invalid_digit};
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing the indentation of a block line that contains tabs.
+    ///
+    /// The `line` and `column` of the first tab in the indentation are both counted from zero. The
+    /// token takes up no space in the input, as the indentation itself is accounted for by the
+    /// block containing the line.
+    pub fn InvalidIndentation
+    (style:IndentStyle, line:usize, column:usize, offset:usize) -> Token {
+        let shape  = Shape::InvalidIndentation{style,line,column};
+        let length = 0;
+        Token{shape,length,offset}
+    }
+
+    /// Construct a token representing an invalid escape sequence in a text literal.
+    pub fn InvalidEscape(repr:impl Into<MatchText>, offset:usize) -> Token {
+        let str    = repr.into();
+        let length = str.chars().count();
+        let shape  = Shape::InvalidEscape(str);
+        Token{shape,length,offset}
+    }
+
     /// Construct a token representing an unrecognised lexeme.
-    pub fn Unrecognized(text:impl Str, offset:usize) -> Token {
+    pub fn Unrecognized(text:impl Into<MatchText>, offset:usize) -> Token {
         let str    = text.into();
         let length = str.chars().count();
         let shape  = Shape::Unrecognized(str);
@@ -179,6 +395,55 @@ pub enum BlockType {
     Discontinuous,
 }
 
+
+
+// =================
+// === TextStyle ===
+// =================
+
+/// The style of a text literal.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum TextStyle {
+    /// A raw text literal, delimited by double quotes (`"`).
+    Raw,
+    /// An interpolated text literal, delimited by single quotes (`'`), that may contain splices.
+    Interpolated,
+}
+
+
+
+// ===================
+// === EscapeStyle ===
+// ===================
+
+/// The style of an escape sequence in a text literal.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum EscapeStyle {
+    /// An escape of a single character, such as `\n` or `\"`.
+    Simple,
+    /// A byte given by two hexadecimal digits, such as `\x41`.
+    Byte,
+    /// A unicode code point given by hexadecimal digits in braces, such as `\u{1F600}`.
+    Unicode,
+}
+
+
+
+// ===================
+// === IndentStyle ===
+// ===================
+
+/// The whitespace making up the invalid indentation of a block line.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum IndentStyle {
+    /// Indentation made up of tabs alone.
+    Tabs,
+    /// Indentation made up of both tabs and spaces.
+    Mixed,
+}
+
+
+
 // ===================
 // === NewlineType ===
 // ===================
@@ -224,35 +489,130 @@ impl Default for LineEnding {
 ///
 /// This is a very small set of shapes, because the [`Token`] type only deals with the tokens that
 /// the lexer works with, not the full complexity of Enso's syntax.
+///
+/// Shapes made from the whole text of a single match (such as identifiers, operators and text
+/// segments) hold it as [`MatchText`], so that they share the input buffer of the lexer rather
+/// than copying it whenever the input is held in memory.
 #[allow(missing_docs)]
 #[derive(Clone,Debug,PartialEq,Eq)]
 pub enum Shape {
     // === Identifiers ===
 
     /// An identifier in referent form.
-    Referent(String),
+    Referent(MatchText),
     /// An identifier in variable form.
-    Variable(String),
+    Variable(MatchText),
     /// An identifier not conforming to the Enso identifier rules (e.g. a Java identifier).
-    External(String),
+    External(MatchText),
     /// A blank identifier (`_`).
     Blank,
+    /// A variable identifier that is a keyword of the language (e.g. `type`).
+    Keyword(MatchText),
     /// An operator identifier.
-    Operator(String),
+    Operator(MatchText),
     /// A modifier identifier.
-    Modifier(String),
+    Modifier(MatchText),
+    /// An annotation (e.g. `@Builtin_Method`), holding the name that follows the `@`.
+    Annotation(MatchText),
 
     // === Literals ===
 
     /// A literal number.
-    Number{base:String, number:String},
+    Number{
+        /// The explicit base of the number, which is empty when the base is implicitly ten.
+        base : String,
+        /// The integral part of the number.
+        integer : String,
+        /// The fractional part of the number, which is empty if there is none.
+        fractional : String,
+        /// The exponent of the number, including its sign, which is empty if there is none.
+        exponent : String
+    },
     /// A dangling base from a number literal.
     DanglingBase(String),
-    /// A text literal.
+    /// An inline text literal.
+    TextLine{
+        /// The style of the text literal.
+        style : TextStyle,
+        /// The segments that make up the contents of the literal.
+        segments : Vec<Token>
+    },
+    /// A text block literal.
+    ///
+    /// The lines of the block are [`Shape::Line`]s of text segments and [`Shape::BlankLine`]s.
+    TextBlock{
+        /// The style of the text literal.
+        style : TextStyle,
+        /// The baseline margin of the block, set by its first line.
+        ///
+        /// Any indentation beyond this margin is kept as part of the text of a line.
+        indent : usize,
+        /// The lines in the block.
+        lines : Vec<Token>
+    },
+    /// A run of raw characters in a text literal.
+    TextSegmentRaw(MatchText),
+    /// An escape sequence in a text literal.
+    TextSegmentEscape{
+        /// The style of the escape sequence.
+        style : EscapeStyle,
+        /// The escape sequence as written in the source, including its leading backslash.
+        repr : MatchText
+    },
+    /// A splice of Enso code in an interpolated text literal.
+    TextSegmentSplice{
+        /// The tokens of the code in the splice.
+        tokens : Stream,
+        /// The number of spaces between the last token in the splice and its closing backtick.
+        trailing_offset : usize
+    },
+
+    // === Comments ===
+
+    /// A disable comment, running from a `#` to the end of its line.
+    ///
+    /// The disabled code is still lexed, so that it keeps its token structure. The tokens of a
+    /// comment containing prose rather than code usually contain errors, which can be checked for
+    /// with [`Token::has_errors`].
+    Comment{
+        /// The text following the `#`.
+        text : MatchText,
+        /// The tokens of the disabled code.
+        tokens : Stream,
+        /// The number of spaces between the last token in the comment and the end of its line.
+        trailing_offset : usize
+    },
+    /// A documentation comment.
+    ///
+    /// The lines of the comment are [`Shape::Line`]s of raw text segments and
+    /// [`Shape::BlankLine`]s. The first line holds the text following the leading `##`, and the
+    /// comment continues over the following lines that are indented further than the line that
+    /// contains it.
+    DocComment{
+        /// The lines in the comment.
+        lines : Vec<Token>,
+        /// The baseline margin of the lines following the first, set by the second line.
+        ///
+        /// Any indentation beyond this margin is kept as part of the text of a line.
+        indent : usize
+    },
+
+    // === Foreign Code ===
+
+    /// The body of a foreign definition (e.g. `foreign python foo = """`).
     ///
-    /// This is currently way too simplistic to actually represent text, but it is a good
-    /// placeholder.
-    Text(String),
+    /// The lines of the body are [`Shape::Line`]s, each holding the raw text of its line as a
+    /// single segment, and [`Shape::BlankLine`]s. The body is never lexed as Enso code.
+    ForeignBlock{
+        /// The name of the language that the body is written in.
+        language : String,
+        /// The margin of the body, which is the smallest indentation of any of its lines.
+        ///
+        /// Any indentation beyond this margin is kept as part of the text of a line.
+        indent : usize,
+        /// The lines in the body.
+        lines : Vec<Token>
+    },
 
     // === Lines ===
     /// A line containing tokens.
@@ -287,25 +647,63 @@ pub enum Shape {
 
     // === Errors ===
     /// An invalid suffix.
-    InvalidSuffix(String),
+    InvalidSuffix(MatchText),
+    /// An inline text literal that is not closed before the end of its line.
+    UnclosedTextLine{
+        /// The style of the text literal.
+        style : TextStyle,
+        /// The segments that make up the contents of the literal.
+        segments : Vec<Token>
+    },
+    /// A splice in an interpolated text literal that is not closed before the end of its line.
+    UnclosedTextSplice{
+        /// The tokens of the code in the splice.
+        tokens : Stream,
+        /// The number of spaces after the last token in the splice.
+        trailing_offset : usize
+    },
+    /// A backtick that does not close a splice.
+    UnmatchedBacktick,
+    /// An annotation whose name is not a valid annotation name, holding whatever follows the `@`.
+    InvalidAnnotation(MatchText),
+    /// A number literal containing a digit that is not valid in its explicit base.
+    InvalidNumber{
+        /// The explicit base of the number.
+        base : String,
+        /// The number as written after its base.
+        number : String,
+        /// The index (in characters) of the first invalid digit in `number`.
+        invalid_digit : usize
+    },
+    /// The indentation of a block line that contains tabs.
+    InvalidIndentation{
+        /// The whitespace that makes up the indentation.
+        style : IndentStyle,
+        /// The line of the first tab in the input, counting from zero.
+        line : usize,
+        /// The column of the first tab in the input, counting from zero.
+        column : usize
+    },
+    /// An invalid escape sequence in a text literal.
+    InvalidEscape(MatchText),
     /// An unrecognized token.
-    Unrecognized(String),
+    Unrecognized(MatchText),
 }
 
 impl Shape {
 
     /// Construct an identifier in referent form.
-    pub fn referent(name:impl Into<String>) -> Shape {
+    pub fn referent(name:impl Into<MatchText>) -> Shape {
         Shape::Referent(name.into())
     }
 
     /// Construct an identifier in variable form.
-    pub fn variable(name:impl Into<String>) -> Shape {
+    pub fn variable(name:impl Into<MatchText>) -> Shape {
         Shape::Variable(name.into())
     }
 
     /// Construct an identifier in external form.
-    pub fn external(name:impl Into<String>) -> Shape {
+    pub fn external(name:impl Into<MatchText>) -> Shape {
         Shape::External(name.into())
     }
 
@@ -316,19 +714,38 @@ impl Shape {
         Shape::Blank
     }
 
+    /// Construct a keyword.
+    pub fn keyword(name:impl Into<MatchText>) -> Shape {
+        Shape::Keyword(name.into())
+    }
+
     /// Construct an operator identifier.
-    pub fn operator(opr:impl Into<String>) -> Shape {
+    pub fn operator(opr:impl Into<MatchText>) -> Shape {
         Shape::Operator(opr.into())
     }
 
     /// Construct a modifier identifier.
-    pub fn modifier(opr:impl Into<String>) -> Shape {
+    pub fn modifier(opr:impl Into<MatchText>) -> Shape {
         Shape::Modifier(opr.into())
     }
 
+    /// Construct an annotation.
+    pub fn annotation(name:impl Into<MatchText>) -> Shape {
+        Shape::Annotation(name.into())
+    }
+
     /// Construct a number literal.
-    pub fn number(base:impl Into<String>, num:impl Into<String>) -> Shape {
-        Shape::Number{base:base.into(),number:num.into()}
+    pub fn number
+    ( base       : impl Into<String>
+    , integer    : impl Into<String>
+    , fractional : impl Into<String>
+    , exponent   : impl Into<String>
+    ) -> Shape {
+        let base       = base.into();
+        let integer    = integer.into();
+        let fractional = fractional.into();
+        let exponent   = exponent.into();
+        Shape::Number{base,integer,fractional,exponent}
     }
 
     /// Construct a dangling base literal.
@@ -336,9 +753,39 @@ impl Shape {
         Shape::DanglingBase(base.into())
     }
 
-    /// Construct a text literal.
-    pub fn text(text:impl Into<String>) -> Shape {
-        Shape::Text(text.into())
+    /// Construct an inline text literal.
+    pub fn text_line(style:TextStyle, segments:Vec<Token>) -> Shape {
+        Shape::TextLine{style,segments}
+    }
+
+    /// Construct a text block literal.
+    pub fn text_block(style:TextStyle, indent:usize, lines:Vec<Token>) -> Shape {
+        Shape::TextBlock{style,indent,lines}
+    }
+
+    /// Construct a run of raw characters in a text literal.
+    pub fn text_segment_raw(text:impl Into<MatchText>) -> Shape {
+        Shape::TextSegmentRaw(text.into())
+    }
+
+    /// Construct an escape sequence in a text literal.
+    pub fn text_segment_escape(style:EscapeStyle, repr:impl Into<MatchText>) -> Shape {
+        Shape::TextSegmentEscape{style,repr:repr.into()}
+    }
+
+    /// Construct a disable comment.
+    pub fn comment(text:impl Into<MatchText>, tokens:Stream, trailing_offset:usize) -> Shape {
+        Shape::Comment{text:text.into(),tokens,trailing_offset}
+    }
+
+    /// Construct a documentation comment.
+    pub fn doc_comment(lines:Vec<Token>, indent:usize) -> Shape {
+        Shape::DocComment{lines,indent}
+    }
+
+    /// Construct the body of a foreign definition.
+    pub fn foreign_block(language:impl Into<String>, indent:usize, lines:Vec<Token>) -> Shape {
+        Shape::ForeignBlock{language:language.into(),indent,lines}
     }
 
     /// Construct a line that contains tokens.
@@ -357,14 +804,93 @@ impl Shape {
     }
 
     /// Construct an invalid suffix.
-    pub fn invalid_suffix(text:impl Into<String>) -> Shape {
+    pub fn invalid_suffix(text:impl Into<MatchText>) -> Shape {
         Shape::InvalidSuffix(text.into())
     }
 
+    /// Construct a splice in an interpolated text literal.
+    pub fn text_segment_splice(tokens:Stream, trailing_offset:usize) -> Shape {
+        Shape::TextSegmentSplice{tokens,trailing_offset}
+    }
+
+    /// Construct an inline text literal that is not closed before the end of its line.
+    pub fn unclosed_text_line(style:TextStyle, segments:Vec<Token>) -> Shape {
+        Shape::UnclosedTextLine{style,segments}
+    }
+
+    /// Construct a splice that is not closed before the end of its line.
+    pub fn unclosed_text_splice(tokens:Stream, trailing_offset:usize) -> Shape {
+        Shape::UnclosedTextSplice{tokens,trailing_offset}
+    }
+
+    /// Construct a backtick that does not close a splice.
+    ///
+    /// This is provided as a function for completeness.
+    pub fn unmatched_backtick() -> Shape {
+        Shape::UnmatchedBacktick
+    }
+
+    /// Construct an annotation with an invalid name.
+    pub fn invalid_annotation(text:impl Into<MatchText>) -> Shape {
+        Shape::InvalidAnnotation(text.into())
+    }
+
+    /// Construct a number literal containing a digit that is not valid in its base.
+    pub fn invalid_number
+    (base:impl Into<String>, number:impl Into<String>, invalid_digit:usize) -> Shape {
+        Shape::InvalidNumber{base:base.into(),number:number.into(),invalid_digit}
+    }
+
+    /// Construct the invalid indentation of a block line.
+    pub fn invalid_indentation(style:IndentStyle, line:usize, column:usize) -> Shape {
+        Shape::InvalidIndentation{style,line,column}
+    }
+
+    /// Construct an invalid escape sequence.
+    pub fn invalid_escape(repr:impl Into<MatchText>) -> Shape {
+        Shape::InvalidEscape(repr.into())
+    }
+
     /// Construct an unrecognised token.
-    pub fn unrecognized(text:impl Into<String>) -> Shape {
+    pub fn unrecognized(text:impl Into<MatchText>) -> Shape {
         Shape::Unrecognized(text.into())
     }
+
+    /// Check whether the shape is one of the error shapes.
+    pub fn is_error(&self) -> bool {
+        match self {
+            Shape::InvalidSuffix(_)       => true,
+            Shape::UnclosedTextLine{..}   => true,
+            Shape::UnclosedTextSplice{..} => true,
+            Shape::UnmatchedBacktick      => true,
+            Shape::InvalidAnnotation(_)   => true,
+            Shape::InvalidNumber{..}      => true,
+            Shape::InvalidIndentation{..} => true,
+            Shape::InvalidEscape(_)       => true,
+            Shape::Unrecognized(_)        => true,
+            _                             => false,
+        }
+    }
+
+    /// Get the tokens contained in the shape.
+    ///
+    /// These are the tokens of a line, splice or disable comment, the lines of a block, text block,
+    /// documentation comment or foreign block, and the segments of an inline text literal.
+    pub fn children(&self) -> &[Token] {
+        match self {
+            Shape::Line{tokens,..}               => tokens,
+            Shape::Block{lines,..}               => lines,
+            Shape::TextLine{segments,..}         => segments,
+            Shape::TextBlock{lines,..}           => lines,
+            Shape::DocComment{lines,..}          => lines,
+            Shape::ForeignBlock{lines,..}        => lines,
+            Shape::UnclosedTextLine{segments,..} => segments,
+            Shape::TextSegmentSplice{tokens,..}  => tokens,
+            Shape::UnclosedTextSplice{tokens,..} => tokens,
+            Shape::Comment{tokens,..}            => tokens,
+            _                                    => &[],
+        }
+    }
 }
 
 
@@ -374,7 +900,7 @@ impl Shape {
 // ==============
 
 /// A representation of the Enso token stream.
-#[derive(Clone,Debug,Default,PartialEq)]
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
 pub struct Stream {
     /// The tokens in the token stream.
     tokens:Vec<Token>
@@ -481,6 +1007,13 @@ mod tests {
         assert_length(&token,1);
     }
 
+    #[test]
+    fn construct_keyword_token() {
+        let token = Token::Keyword("type",0);
+        assert_shape(&token,Shape::keyword("type"));
+        assert_length(&token,4);
+    }
+
     #[test]
     fn construct_operator_token() {
         let token = Token::Operator("==>",0);
@@ -495,13 +1028,27 @@ mod tests {
         assert_length(&token,2);
     }
 
+    #[test]
+    fn construct_annotation_token() {
+        let token = Token::Annotation("Builtin_Method",0);
+        assert_shape(&token,Shape::annotation("Builtin_Method"));
+        assert_length(&token,15);
+    }
+
     #[test]
     fn construct_number_token() {
-        let token = Token::Number("","1231",0);
-        assert_shape(&token,Shape::number("","1231"));
+        let token = Token::Number("","1231","","",0);
+        assert_shape(&token,Shape::number("","1231","",""));
         assert_length(&token,4);
     }
 
+    #[test]
+    fn construct_number_token_with_all_parts() {
+        let token = Token::Number("10","12","5","-10",0);
+        assert_shape(&token,Shape::number("10","12","5","-10"));
+        assert_length(&token,11);
+    }
+
     #[test]
     fn construct_dangling_base_token() {
         let token = Token::DanglingBase("15",0);
@@ -510,11 +1057,81 @@ mod tests {
     }
 
     #[test]
-    fn construct_text_token() {
-        let token = Token::Text("some prose goes here",0);
-        assert_shape(&token,Shape::text("some prose goes here"));
+    fn construct_text_line_token() {
+        let segments = vec![Token::TextSegmentRaw("some prose goes here",0)];
+        let token    = Token::TextLine(TextStyle::Raw,segments.clone(),0);
+        assert_shape(&token,Shape::text_line(TextStyle::Raw,segments));
+        assert_length(&token,22);
+    }
+
+    #[test]
+    fn construct_text_block_token() {
+        let lines = vec![
+            Token::Line(vec![Token::TextSegmentRaw("a",0)],0,LineEnding::LF),
+            Token::BlankLine(2,LineEnding::CRLF),
+            Token::Line(vec![Token::TextSegmentRaw("  b",0)],0,LineEnding::None),
+        ];
+        let token = Token::TextBlock(TextStyle::Raw,4,lines.clone(),0);
+        assert_shape(&token,Shape::text_block(TextStyle::Raw,4,lines));
+        assert_length(&token,20);
+    }
+
+    #[test]
+    fn construct_text_segment_raw_token() {
+        let token = Token::TextSegmentRaw("some prose goes here",0);
+        assert_shape(&token,Shape::text_segment_raw("some prose goes here"));
         assert_length(&token,20);
-        // TODO [AA] Make this internally account for length of quotes.
+    }
+
+    #[test]
+    fn construct_text_segment_escape_token() {
+        let token = Token::TextSegmentEscape(EscapeStyle::Unicode,"\\u{1F600}",0);
+        assert_shape(&token,Shape::text_segment_escape(EscapeStyle::Unicode,"\\u{1F600}"));
+        assert_length(&token,9);
+    }
+
+    #[test]
+    fn construct_comment_token() {
+        let tokens = Stream::from(vec![Token::Variable("a",1),Token::Variable("comment",1)]);
+        let token  = Token::Comment(" a comment ",tokens.clone(),1,0);
+        assert_shape(&token,Shape::comment(" a comment ",tokens,1));
+        assert_length(&token,12);
+    }
+
+    #[test]
+    fn tokens_with_errors() {
+        let text   = Token::UnclosedTextLine(TextStyle::Raw,vec![Token::TextSegmentRaw("s",0)],0);
+        let line   = Token::Line(vec![Token::Variable("a",0),text],0,LineEnding::None);
+        let escape = Token::TextLine(TextStyle::Raw,vec![Token::InvalidEscape("\\q",0)],0);
+        let valid  = Token::Line(vec![Token::Variable("a",0)],0,LineEnding::None);
+        assert!(line.has_errors());
+        assert!(escape.has_errors());
+        assert!(Token::UnmatchedBacktick(0).has_errors());
+        assert!(!valid.has_errors());
+    }
+
+    #[test]
+    fn construct_doc_comment_token() {
+        let lines = vec![
+            Token::Line(vec![Token::TextSegmentRaw(" Docs",0)],0,LineEnding::None),
+            Token::Line(vec![Token::TextSegmentRaw("more",0)],0,LineEnding::LF),
+            Token::BlankLine(0,LineEnding::LF),
+        ];
+        let token = Token::DocComment(lines.clone(),3,0);
+        assert_shape(&token,Shape::doc_comment(lines,3));
+        assert_length(&token,16);
+    }
+
+    #[test]
+    fn construct_foreign_block_token() {
+        let lines = vec![
+            Token::Line(vec![Token::TextSegmentRaw("print(1)",0)],0,LineEnding::LF),
+            Token::BlankLine(0,LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("  x",0)],0,LineEnding::None),
+        ];
+        let token = Token::ForeignBlock("python",4,lines.clone(),0);
+        assert_shape(&token,Shape::foreign_block("python",4,lines));
+        assert_length(&token,24);
     }
 
     #[test]
@@ -561,6 +1178,65 @@ mod tests {
         assert_length(&token,3);
     }
 
+    #[test]
+    fn construct_text_segment_splice_token() {
+        let tokens = Stream::from(vec![Token::Variable("a",0),Token::Operator("+",1)]);
+        let token  = Token::TextSegmentSplice(tokens.clone(),1,0);
+        assert_shape(&token,Shape::text_segment_splice(tokens,1));
+        assert_length(&token,6);
+    }
+
+    #[test]
+    fn construct_unclosed_text_line_token() {
+        let segments = vec![Token::TextSegmentRaw("prose",0),Token::InvalidEscape("\\q",0)];
+        let token    = Token::UnclosedTextLine(TextStyle::Raw,segments.clone(),0);
+        assert_shape(&token,Shape::unclosed_text_line(TextStyle::Raw,segments));
+        assert_length(&token,8);
+    }
+
+    #[test]
+    fn construct_unclosed_text_splice_token() {
+        let tokens = Stream::from(vec![Token::Variable("a",1)]);
+        let token  = Token::UnclosedTextSplice(tokens.clone(),0,0);
+        assert_shape(&token,Shape::unclosed_text_splice(tokens,0));
+        assert_length(&token,3);
+    }
+
+    #[test]
+    fn construct_unmatched_backtick_token() {
+        let token = Token::UnmatchedBacktick(0);
+        assert_shape(&token,Shape::unmatched_backtick());
+        assert_length(&token,1);
+    }
+
+    #[test]
+    fn construct_invalid_annotation_token() {
+        let token = Token::InvalidAnnotation("",0);
+        assert_shape(&token,Shape::invalid_annotation(""));
+        assert_length(&token,1);
+    }
+
+    #[test]
+    fn construct_invalid_number_token() {
+        let token = Token::InvalidNumber("2","1012",3,0);
+        assert_shape(&token,Shape::invalid_number("2","1012",3));
+        assert_length(&token,6);
+    }
+
+    #[test]
+    fn construct_invalid_indentation_token() {
+        let token = Token::InvalidIndentation(IndentStyle::Mixed,3,2,0);
+        assert_shape(&token,Shape::invalid_indentation(IndentStyle::Mixed,3,2));
+        assert_length(&token,0);
+    }
+
+    #[test]
+    fn construct_invalid_escape_token() {
+        let token = Token::InvalidEscape("\\q",0);
+        assert_shape(&token,Shape::invalid_escape("\\q"));
+        assert_length(&token,2);
+    }
+
     #[test]
     fn construct_unrecognized_token() {
         let token = Token::Unrecognized("a",0);
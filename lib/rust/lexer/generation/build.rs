@@ -2,31 +2,80 @@ use std::fs::File;
 use lexer_definition::lexer::EnsoLexer;
 use std::io::prelude::*;
 use flexer::Definition;
-use flexer::State;
+use flexer::generate;
+use flexer::generate::CodegenOptions;
+use flexer::generate::Encoding;
 
 
 
-/// Generates the lexer engine and saves the result into the file `src/engine.rs`.
+/// The options for generating the lexer engines.
+///
+/// Release builds do not trace the progress of the lexer, so that they contain no logging code.
+/// The hits of each rule are counted only when the `rule-coverage` feature is enabled, and the work
+/// done in each group is only profiled when the `profiling` feature is enabled.
+fn codegen_options() -> CodegenOptions {
+    let is_release = std::env::var("PROFILE").map(|p| p == "release").unwrap_or(false);
+    let coverage   = std::env::var_os("CARGO_FEATURE_RULE_COVERAGE").is_some();
+    let profiling  = std::env::var_os("CARGO_FEATURE_PROFILING").is_some();
+    let options    = CodegenOptions::default().with_tracing(!is_release);
+    options.with_coverage(coverage).with_profiling(profiling)
+}
+
+/// Generates the lexer engine and saves the result into the file `src/generated/engine.rs`.
 ///
 /// The content of the generated file can be used with the `include!` macro.
 fn generate_engine() -> std::io::Result<()> {
+    let lexer   = EnsoLexer::define();
+    let options = codegen_options();
+    let engine  = generate::specialize_with(&**lexer,"EnsoLexer","token::Stream",&options);
+    let engine  = engine.unwrap();
+    write_engine("src/generated/engine.rs",&engine)
+}
+
+/// Generates the lexer engine running over UTF-8 input, and saves the result into the file
+/// `src/generated/engine_utf8.rs`.
+fn generate_utf8_engine() -> std::io::Result<()> {
+    let lexer   = EnsoLexer::define();
+    let options = codegen_options().with_encoding(Encoding::Utf8);
+    let engine  = generate::specialize_with(&**lexer,"EnsoLexer","token::Stream",&options);
+    let engine  = engine.unwrap();
+    write_engine("src/generated/engine_utf8.rs",&engine)
+}
+
+/// Writes the lexer definition followed by its specialized `engine` to `output_path`.
+fn write_engine(output_path:&str, engine:&str) -> std::io::Result<()> {
     let definition_path  = "../definition/src/lexer.rs";
     let output_directory = "src/generated";
     let _                = std::fs::create_dir(output_directory);
-    let output_path      = "src/generated/engine.rs";
     let definition_error = format!("The lexer definition should exist at {}.",definition_path);
     let output_error     = format!("Cannot open output file at {}.",output_path);
     let mut lexer_def    = File::open(definition_path).expect(definition_error.as_str());
     let mut contents     = String::new();
     let mut file         = File::create(output_path).expect(output_error.as_str());
-    let lexer            = EnsoLexer::define();
-    let engine           = lexer.specialize().unwrap();
     lexer_def.read_to_string(&mut contents).expect("Unable to read lexer definition.");
     file.write_all(contents.as_bytes()).expect("Unable to write lexer definition.");
     file.write_all(engine.as_bytes()).expect("Unable to write lexer specialization.");
     Ok(())
 }
 
+/// Generates the C header for the lexer's foreign function interface, and saves the result into
+/// the file `include/enso_lexer.h`.
+fn generate_header() -> std::io::Result<()> {
+    let ffi_path    = "src/ffi.rs";
+    let config_path = "cbindgen.toml";
+    let output_path = "include/enso_lexer.h";
+    let config      = cbindgen::Config::from_file(config_path).expect("Invalid cbindgen config.");
+    let bindings    = cbindgen::Builder::new().with_config(config).with_src(ffi_path).generate();
+    bindings.expect("Unable to generate the C header.").write_to_file(output_path);
+    println!("cargo:rerun-if-changed={}",ffi_path);
+    println!("cargo:rerun-if-changed={}",config_path);
+    Ok(())
+}
+
 fn main() -> std::io::Result<()> {
-    generate_engine()
+    let target = std::env::var("TARGET").expect("Cargo should set the build target.");
+    println!("cargo:rustc-env=TARGET={}",target);
+    generate_engine()?;
+    generate_utf8_engine()?;
+    generate_header()
 }
@@ -0,0 +1,518 @@
+/* The C interface to the Enso lexer. */
+
+#ifndef ENSO_LEXER_H
+#define ENSO_LEXER_H
+
+/* This file is generated by the build script of the `lexer` crate. Do not edit it. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * The type of a block token.
+ */
+typedef enum {
+  /**
+   * A block made up of arguments to a function.
+   */
+  ENSO_BLOCK_TYPE_CONTINUOUS,
+  /**
+   * A block made up of separate lines.
+   */
+  ENSO_BLOCK_TYPE_DISCONTINUOUS,
+} EnsoBlockType;
+
+/**
+ * The whitespace making up an invalid indentation token.
+ */
+typedef enum {
+  /**
+   * Indentation made up of tabs alone.
+   */
+  ENSO_INDENT_STYLE_TABS,
+  /**
+   * Indentation made up of both tabs and spaces.
+   */
+  ENSO_INDENT_STYLE_MIXED,
+} EnsoIndentStyle;
+
+/**
+ * The kind of the error that stopped the lexer.
+ */
+typedef enum {
+  /**
+   * The lexer did not stop with an error.
+   */
+  ENSO_LEX_ERROR_KIND_NONE,
+  /**
+   * The lexer tried to enter a state with its state stack at its maximum depth.
+   */
+  ENSO_LEX_ERROR_KIND_STATE_STACK_OVERFLOW,
+  /**
+   * The lexer panicked.
+   */
+  ENSO_LEX_ERROR_KIND_PANIC,
+} EnsoLexErrorKind;
+
+/**
+ * The kind of a lexing result.
+ */
+typedef enum {
+  /**
+   * The lexer consumed the whole input.
+   */
+  ENSO_LEX_RESULT_KIND_SUCCESS,
+  /**
+   * The lexer only consumed part of the input.
+   */
+  ENSO_LEX_RESULT_KIND_PARTIAL,
+  /**
+   * The lexer failed.
+   */
+  ENSO_LEX_RESULT_KIND_FAILURE,
+} EnsoLexResultKind;
+
+/**
+ * The line ending of a line token.
+ */
+typedef enum {
+  /**
+   * There is no line ending.
+   */
+  ENSO_LINE_ENDING_NONE,
+  /**
+   * The unix-style line-feed (`'\n'`).
+   */
+  ENSO_LINE_ENDING_LF,
+  /**
+   * The windows-style carriage-return, line-feed (`"\r\n"`).
+   */
+  ENSO_LINE_ENDING_CRLF,
+} EnsoLineEnding;
+
+/**
+ * The kind of the shape of a token.
+ */
+typedef enum {
+  /**
+   * An identifier in referent form.
+   */
+  ENSO_SHAPE_KIND_REFERENT,
+  /**
+   * An identifier in variable form.
+   */
+  ENSO_SHAPE_KIND_VARIABLE,
+  /**
+   * An identifier not conforming to the Enso identifier rules.
+   */
+  ENSO_SHAPE_KIND_EXTERNAL,
+  /**
+   * A blank identifier (`_`).
+   */
+  ENSO_SHAPE_KIND_BLANK,
+  /**
+   * A keyword.
+   */
+  ENSO_SHAPE_KIND_KEYWORD,
+  /**
+   * An operator identifier.
+   */
+  ENSO_SHAPE_KIND_OPERATOR,
+  /**
+   * A modifier identifier.
+   */
+  ENSO_SHAPE_KIND_MODIFIER,
+  /**
+   * An annotation.
+   */
+  ENSO_SHAPE_KIND_ANNOTATION,
+  /**
+   * A literal number.
+   */
+  ENSO_SHAPE_KIND_NUMBER,
+  /**
+   * A dangling base from a number literal.
+   */
+  ENSO_SHAPE_KIND_DANGLING_BASE,
+  /**
+   * An inline text literal.
+   */
+  ENSO_SHAPE_KIND_TEXT_LINE,
+  /**
+   * A text block literal.
+   */
+  ENSO_SHAPE_KIND_TEXT_BLOCK,
+  /**
+   * A run of raw characters in a text literal.
+   */
+  ENSO_SHAPE_KIND_TEXT_SEGMENT_RAW,
+  /**
+   * An escape sequence in a text literal.
+   */
+  ENSO_SHAPE_KIND_TEXT_SEGMENT_ESCAPE,
+  /**
+   * A splice in an interpolated text literal.
+   */
+  ENSO_SHAPE_KIND_TEXT_SEGMENT_SPLICE,
+  /**
+   * A disable comment.
+   */
+  ENSO_SHAPE_KIND_COMMENT,
+  /**
+   * A documentation comment.
+   */
+  ENSO_SHAPE_KIND_DOC_COMMENT,
+  /**
+   * The body of a foreign definition.
+   */
+  ENSO_SHAPE_KIND_FOREIGN_BLOCK,
+  /**
+   * A line containing tokens.
+   */
+  ENSO_SHAPE_KIND_LINE,
+  /**
+   * A blank line.
+   */
+  ENSO_SHAPE_KIND_BLANK_LINE,
+  /**
+   * A block of lines.
+   */
+  ENSO_SHAPE_KIND_BLOCK,
+  /**
+   * An invalid suffix.
+   */
+  ENSO_SHAPE_KIND_INVALID_SUFFIX,
+  /**
+   * An inline text literal that is not closed before the end of its line.
+   */
+  ENSO_SHAPE_KIND_UNCLOSED_TEXT_LINE,
+  /**
+   * A splice that is not closed before the end of its line.
+   */
+  ENSO_SHAPE_KIND_UNCLOSED_TEXT_SPLICE,
+  /**
+   * A backtick that does not close a splice.
+   */
+  ENSO_SHAPE_KIND_UNMATCHED_BACKTICK,
+  /**
+   * An annotation with an invalid name.
+   */
+  ENSO_SHAPE_KIND_INVALID_ANNOTATION,
+  /**
+   * A number literal with a digit that is not valid in its base.
+   */
+  ENSO_SHAPE_KIND_INVALID_NUMBER,
+  /**
+   * The indentation of a block line that contains tabs.
+   */
+  ENSO_SHAPE_KIND_INVALID_INDENTATION,
+  /**
+   * An invalid escape sequence in a text literal.
+   */
+  ENSO_SHAPE_KIND_INVALID_ESCAPE,
+  /**
+   * An unrecognized token.
+   */
+  ENSO_SHAPE_KIND_UNRECOGNIZED,
+} EnsoShapeKind;
+
+/**
+ * The result of lexing a buffer of Enso source code.
+ */
+typedef struct EnsoLexResult EnsoLexResult;
+
+/**
+ * An instance of the Enso lexer.
+ */
+typedef struct EnsoLexer EnsoLexer;
+
+/**
+ * An opaque handle to a token owned by a `LexResult`.
+ *
+ * Values of this type are never constructed. Pointers to it are pointers to [`token::Token`].
+ */
+typedef struct EnsoToken EnsoToken;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Get the kind of the error that stopped the lexer producing `result`.
+ *
+ * # Safety
+ * The `result` must be a valid pointer returned by `enso_lexer_lex`.
+ */
+EnsoLexErrorKind enso_lex_result_error_kind(const EnsoLexResult *result);
+
+/**
+ * Get the maximum depth of the state stack that the lexer exceeded while producing `result`.
+ *
+ * Returns zero if the lexer did not stop with a `STATE_STACK_OVERFLOW` error.
+ *
+ * # Safety
+ * The `result` must be a valid pointer returned by `enso_lexer_lex`.
+ */
+uintptr_t enso_lex_result_error_max_depth(const EnsoLexResult *result);
+
+/**
+ * Get the message of the panic that stopped the lexer producing `result`, writing its length in
+ * bytes to `length`.
+ *
+ * The message is UTF-8 encoded, is not null terminated, and is owned by the `result`. Returns
+ * null if the lexer did not stop with a `PANIC` error.
+ *
+ * # Safety
+ * The `result` must be a valid pointer returned by `enso_lexer_lex`, and `length` must be a valid
+ * pointer.
+ */
+const char *enso_lex_result_error_message(const EnsoLexResult *result, uintptr_t *length);
+
+/**
+ * Free a result returned by `enso_lexer_lex`, along with all of the tokens that it contains.
+ *
+ * Passing a null pointer is a no-op.
+ *
+ * # Safety
+ * The `result` must be null or a pointer returned by `enso_lexer_lex` that has not yet been
+ * freed.
+ */
+void enso_lex_result_free(EnsoLexResult *result);
+
+/**
+ * Get the kind of the lexing `result`.
+ *
+ * # Safety
+ * The `result` must be a valid pointer returned by `enso_lexer_lex`.
+ */
+EnsoLexResultKind enso_lex_result_kind(const EnsoLexResult *result);
+
+/**
+ * Get the top-level token at `index` in the lexing `result`.
+ *
+ * Returns null if `index` is out of bounds. The token is owned by the `result`.
+ *
+ * # Safety
+ * The `result` must be a valid pointer returned by `enso_lexer_lex`.
+ */
+const EnsoToken *enso_lex_result_token(const EnsoLexResult *result, uintptr_t index);
+
+/**
+ * Get the number of top-level tokens in the lexing `result`.
+ *
+ * # Safety
+ * The `result` must be a valid pointer returned by `enso_lexer_lex`.
+ */
+uintptr_t enso_lex_result_token_count(const EnsoLexResult *result);
+
+/**
+ * Free a lexer created by `enso_lexer_new`.
+ *
+ * Passing a null pointer is a no-op.
+ *
+ * # Safety
+ * The `lexer` must be null or a pointer returned by `enso_lexer_new` that has not yet been freed.
+ */
+void enso_lexer_free(EnsoLexer *lexer);
+
+/**
+ * Lex the `length` bytes of UTF-8 encoded source code at `input`.
+ *
+ * Returns null if `lexer` is null, or if the input is not valid UTF-8. If the lexer panics, the
+ * result is a failure with the `PANIC` error kind. The returned result must be freed with
+ * `enso_lex_result_free`.
+ *
+ * # Safety
+ * The `lexer` must be null or a valid pointer returned by `enso_lexer_new`, and `input` must point
+ * to at least `length` readable bytes (it may be null only if `length` is zero).
+ */
+EnsoLexResult *enso_lexer_lex(EnsoLexer *lexer, const char *input, uintptr_t length);
+
+/**
+ * Create a new instance of the Enso lexer.
+ *
+ * The returned lexer must be freed with `enso_lexer_free`.
+ */
+EnsoLexer *enso_lexer_new(void);
+
+/**
+ * Get the indentation of a block, text block, documentation comment or foreign block `token`.
+ *
+ * Returns zero for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+uintptr_t enso_token_block_indent(const EnsoToken *token);
+
+/**
+ * Get the type of a block `token`.
+ *
+ * Returns `BlockType::Discontinuous` for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+EnsoBlockType enso_token_block_type(const EnsoToken *token);
+
+/**
+ * Get the child of `token` at `index`.
+ *
+ * Returns null if `index` is out of bounds.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+const EnsoToken *enso_token_child(const EnsoToken *token, uintptr_t index);
+
+/**
+ * Get the number of children of `token`.
+ *
+ * The children of a line are its tokens, the children of a block, text block, documentation
+ * comment or foreign block are its lines, the children of an inline text literal are its segments,
+ * and the children of a splice or disable comment are its tokens. All other tokens have no
+ * children.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+uintptr_t enso_token_child_count(const EnsoToken *token);
+
+/**
+ * Check whether `token` is an error, or contains one among its descendants.
+ *
+ * The tokens of a disable comment whose text is prose rather than code usually contain errors.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+bool enso_token_has_errors(const EnsoToken *token);
+
+/**
+ * Get the column, counting from zero, of the first tab in an invalid indentation `token`.
+ *
+ * Returns zero for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+uintptr_t enso_token_indentation_column(const EnsoToken *token);
+
+/**
+ * Get the line, counting from zero, of the first tab in an invalid indentation `token`.
+ *
+ * Returns zero for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+uintptr_t enso_token_indentation_line(const EnsoToken *token);
+
+/**
+ * Get the whitespace making up an invalid indentation `token`.
+ *
+ * Returns `IndentStyle::Tabs` for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+EnsoIndentStyle enso_token_indentation_style(const EnsoToken *token);
+
+/**
+ * Get the length of `token` in characters.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+uintptr_t enso_token_length(const EnsoToken *token);
+
+/**
+ * Get the line ending of a line or blank line `token`.
+ *
+ * Returns `LineEnding::None` for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+EnsoLineEnding enso_token_line_ending(const EnsoToken *token);
+
+/**
+ * Get the base of a number, dangling base or invalid number `token`, writing its length in bytes
+ * to `length`.
+ *
+ * The base is empty for numbers without an explicit base. Returns null for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle, and `length` must be a valid pointer.
+ */
+const char *enso_token_number_base(const EnsoToken *token, uintptr_t *length);
+
+/**
+ * Get the exponent of a number `token`, including its sign, writing its length in bytes to
+ * `length`.
+ *
+ * The exponent is empty for numbers without one. Returns null for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle, and `length` must be a valid pointer.
+ */
+const char *enso_token_number_exponent(const EnsoToken *token, uintptr_t *length);
+
+/**
+ * Get the fractional part of a number `token`, writing its length in bytes to `length`.
+ *
+ * The fractional part is empty for numbers without one. Returns null for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle, and `length` must be a valid pointer.
+ */
+const char *enso_token_number_fractional(const EnsoToken *token, uintptr_t *length);
+
+/**
+ * Get the index (in characters) of the first invalid digit in the text of an invalid number
+ * `token`.
+ *
+ * Returns zero for all other tokens.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+uintptr_t enso_token_number_invalid_digit(const EnsoToken *token);
+
+/**
+ * Get the number of trailing spaces after `token` before the next.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+uintptr_t enso_token_offset(const EnsoToken *token);
+
+/**
+ * Get the kind of the shape of `token`.
+ *
+ * # Safety
+ * The `token` must be a valid token handle.
+ */
+EnsoShapeKind enso_token_shape(const EnsoToken *token);
+
+/**
+ * Get the text of `token`, writing its length in bytes to `length`.
+ *
+ * For number tokens this is the integral part of the number, for invalid numbers it is the number
+ * without its base, for escape sequences it is the escape as written in the source, for disable
+ * comments it is the text following the `#`, and for the bodies of foreign definitions it is the
+ * name of their language. The text is UTF-8 encoded, is not null terminated, and is owned by the
+ * result containing `token`. Returns null for tokens that carry no text, such as blanks, lines,
+ * blocks and text literals.
+ *
+ * # Safety
+ * The `token` must be a valid token handle, and `length` must be a valid pointer.
+ */
+const char *enso_token_text(const EnsoToken *token, uintptr_t *length);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* ENSO_LEXER_H */
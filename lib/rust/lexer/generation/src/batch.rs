@@ -0,0 +1,127 @@
+//! Lexing of many source files at once, spread across a pool of worker threads.
+//!
+//! Each worker owns a single [`EnsoLexer`], which it [resets](flexer::Definition::reset) between
+//! files rather than constructing a new lexer for every file.
+
+use crate::prelude::*;
+
+use crate::generated::engine::EnsoLexer;
+use crate::library::token;
+
+use flexer::Definition;
+use flexer::LexingResult;
+use flexer::prelude::StrReader;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+
+
+// =============
+// === Error ===
+// =============
+
+/// The reasons for which a single file of a batch can fail to be lexed.
+#[derive(Debug)]
+pub enum Error {
+    /// The file could not be read, or is not valid UTF-8.
+    Io(io::Error),
+    /// The lexer panicked while lexing the file.
+    Panic(String),
+}
+
+
+// === Trait Impls ===
+
+impl Display for Error {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(error)      => write!(f,"Unable to read the file: {}",error),
+            Error::Panic(message) => write!(f,"The lexer panicked: {}",message),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error:io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+
+
+// ====================
+// === Batch Lexing ===
+// ====================
+
+/// The result of lexing a single file of a batch.
+pub type FileResult = Result<LexingResult<token::Stream>,Error>;
+
+/// Lex each of the files at `paths`, using one worker thread per available core.
+///
+/// The results are returned in the order of `paths`. A file that fails to be lexed does not affect
+/// the results for any other file.
+pub fn lex_many(paths:&[impl AsRef<Path>]) -> Vec<FileResult> {
+    lex_many_with(paths,num_cpus::get())
+}
+
+/// Lex each of the files at `paths` on a pool of at most `workers` threads.
+///
+/// The results are returned in the order of `paths`. A file that fails to be lexed does not affect
+/// the results for any other file.
+pub fn lex_many_with(paths:&[impl AsRef<Path>], workers:usize) -> Vec<FileResult> {
+    let workers   = workers.max(1).min(paths.len().max(1));
+    let paths     = Arc::new(paths.iter().map(|path| path.as_ref().to_path_buf()).collect_vec());
+    let next_file = Arc::new(AtomicUsize::new(0));
+    let handles   = (0..workers).map(|_| {
+        let paths     = paths.clone();
+        let next_file = next_file.clone();
+        thread::spawn(move || {
+            let mut lexer   = EnsoLexer::new();
+            let mut results = Vec::new();
+            loop {
+                let index = next_file.fetch_add(1,Ordering::Relaxed);
+                match paths.get(index) {
+                    Some(path) => results.push((index,lex_file(&mut lexer,path))),
+                    None       => break results,
+                }
+            }
+        })
+    }).collect_vec();
+    let results = handles.into_iter().map(|handle| handle.join());
+    let results = results.flat_map(|results| results.expect("Panics are caught for each file."));
+    let mut results:Vec<(usize,FileResult)> = results.collect();
+    results.sort_by_key(|(index,_)| *index);
+    results.into_iter().map(|(_,result)| result).collect()
+}
+
+/// Lex the file at `path` with `lexer`.
+///
+/// If lexing panics, `lexer` is replaced by a new lexer so that it can be used for further files.
+fn lex_file(lexer:&mut EnsoLexer, path:&Path) -> FileResult {
+    let source = fs::read_to_string(path)?;
+    lexer.reset();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        lexer.run(StrReader::new(source))
+    }));
+    result.map_err(|payload| {
+        *lexer = EnsoLexer::new();
+        Error::Panic(panic_message(payload))
+    })
+}
+
+/// Extract the message from the `payload` of a panic.
+pub(crate) fn panic_message(payload:Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_)      => "Unknown panic.".into(),
+        }
+    }
+}
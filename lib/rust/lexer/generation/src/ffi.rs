@@ -0,0 +1,765 @@
+//! This module exports a C-compatible interface to the generated Enso lexer.
+//!
+//! All of the types exposed through this interface are opaque to C. A lexer is created with
+//! `enso_lexer_new`, and is used to lex a UTF-8 buffer into an `EnsoLexResult`. The structured
+//! token stream in the result is walked through `EnsoToken` handles. These handles are borrowed
+//! from the result that produced them, and hence are only valid until that result is freed.
+//!
+//! The C header for this interface is generated into `include/enso_lexer.h` by the build script
+//! of this crate.
+
+use crate::prelude::*;
+
+use crate::batch::panic_message;
+use crate::generated::engine::EnsoLexer;
+use crate::library::token;
+
+use flexer::LexingError;
+use flexer::LexingResult;
+use flexer::ResultKind;
+use flexer::prelude::StrReader;
+use std::os::raw::c_char;
+use std::panic;
+use std::ptr;
+
+
+
+// =============
+// === Lexer ===
+// =============
+
+/// An instance of the Enso lexer.
+#[derive(Debug)]
+pub struct Lexer {
+    engine : EnsoLexer,
+}
+
+impl Lexer {
+    /// Reset the engine and lex with it through `run`.
+    ///
+    /// A panic while lexing must not unwind into the caller of this interface, and is instead
+    /// turned into a failed result that holds the panic message. The engine is then replaced by a
+    /// new one, so that the lexer can still be used.
+    fn lex_with
+    (&mut self, run:impl FnOnce(&mut EnsoLexer) -> LexingResult<token::Stream>) -> LexResult {
+        self.engine.reset();
+        let engine = &mut self.engine;
+        match panic::catch_unwind(panic::AssertUnwindSafe(|| run(engine))) {
+            Ok(result)   => LexResult{result,panic:None},
+            Err(payload) => {
+                self.engine = EnsoLexer::new();
+                let result  = LexingResult::failure(default());
+                let panic   = Some(panic_message(payload));
+                LexResult{result,panic}
+            }
+        }
+    }
+}
+
+/// Create a new instance of the Enso lexer.
+///
+/// The returned lexer must be freed with `enso_lexer_free`.
+#[no_mangle]
+pub extern "C" fn enso_lexer_new() -> *mut Lexer {
+    let engine = EnsoLexer::new();
+    Box::into_raw(Box::new(Lexer{engine}))
+}
+
+/// Free a lexer created by `enso_lexer_new`.
+///
+/// Passing a null pointer is a no-op.
+///
+/// # Safety
+/// The `lexer` must be null or a pointer returned by `enso_lexer_new` that has not yet been freed.
+#[no_mangle]
+pub unsafe extern "C" fn enso_lexer_free(lexer:*mut Lexer) {
+    if !lexer.is_null() {
+        drop(Box::from_raw(lexer))
+    }
+}
+
+/// Lex the `length` bytes of UTF-8 encoded source code at `input`.
+///
+/// Returns null if `lexer` is null, or if the input is not valid UTF-8. If the lexer panics, the
+/// result is a failure with the `PANIC` error kind. The returned result must be freed with
+/// `enso_lex_result_free`.
+///
+/// # Safety
+/// The `lexer` must be null or a valid pointer returned by `enso_lexer_new`, and `input` must point
+/// to at least `length` readable bytes (it may be null only if `length` is zero).
+#[no_mangle]
+pub unsafe extern "C" fn enso_lexer_lex
+(lexer:*mut Lexer, input:*const c_char, length:usize) -> *mut LexResult {
+    let lexer = match lexer.as_mut() {
+        Some(lexer) => lexer,
+        None        => return ptr::null_mut(),
+    };
+    let bytes = if length == 0 {
+        &[]
+    } else if input.is_null() {
+        return ptr::null_mut()
+    } else {
+        std::slice::from_raw_parts(input as *const u8,length)
+    };
+    match std::str::from_utf8(bytes) {
+        Ok(source) => {
+            let result = lexer.lex_with(|engine| engine.run(StrReader::new(source)));
+            Box::into_raw(Box::new(result))
+        }
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+
+
+// =================
+// === LexResult ===
+// =================
+
+/// The result of lexing a buffer of Enso source code.
+#[derive(Debug)]
+pub struct LexResult {
+    result : LexingResult<token::Stream>,
+    /// The message of the panic that stopped the lexer, if it panicked.
+    panic  : Option<String>,
+}
+
+/// The kind of a lexing result.
+#[repr(C)]
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum LexResultKind {
+    /// The lexer consumed the whole input.
+    Success,
+    /// The lexer only consumed part of the input.
+    Partial,
+    /// The lexer failed.
+    Failure,
+}
+
+
+// === Trait Impls ===
+
+impl From<ResultKind> for LexResultKind {
+    fn from(kind:ResultKind) -> Self {
+        match kind {
+            ResultKind::Success => Self::Success,
+            ResultKind::Partial => Self::Partial,
+            ResultKind::Failure => Self::Failure,
+        }
+    }
+}
+
+/// The kind of the error that stopped the lexer.
+#[repr(C)]
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum LexErrorKind {
+    /// The lexer did not stop with an error.
+    None,
+    /// The lexer tried to enter a state with its state stack at its maximum depth.
+    StateStackOverflow,
+    /// The lexer panicked.
+    Panic,
+}
+
+
+// === API ===
+
+/// Free a result returned by `enso_lexer_lex`, along with all of the tokens that it contains.
+///
+/// Passing a null pointer is a no-op.
+///
+/// # Safety
+/// The `result` must be null or a pointer returned by `enso_lexer_lex` that has not yet been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn enso_lex_result_free(result:*mut LexResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result))
+    }
+}
+
+/// Get the kind of the lexing `result`.
+///
+/// # Safety
+/// The `result` must be a valid pointer returned by `enso_lexer_lex`.
+#[no_mangle]
+pub unsafe extern "C" fn enso_lex_result_kind(result:*const LexResult) -> LexResultKind {
+    (&*result).result.kind.into()
+}
+
+/// Get the kind of the error that stopped the lexer producing `result`.
+///
+/// # Safety
+/// The `result` must be a valid pointer returned by `enso_lexer_lex`.
+#[no_mangle]
+pub unsafe extern "C" fn enso_lex_result_error_kind(result:*const LexResult) -> LexErrorKind {
+    let result = &*result;
+    match (&result.result.error,&result.panic) {
+        (_,Some(_))                                   => LexErrorKind::Panic,
+        (None,None)                                   => LexErrorKind::None,
+        (Some(LexingError::StateStackOverflow{..}),_) => LexErrorKind::StateStackOverflow,
+    }
+}
+
+/// Get the message of the panic that stopped the lexer producing `result`, writing its length in
+/// bytes to `length`.
+///
+/// The message is UTF-8 encoded, is not null terminated, and is owned by the `result`. Returns
+/// null if the lexer did not stop with a `PANIC` error.
+///
+/// # Safety
+/// The `result` must be a valid pointer returned by `enso_lexer_lex`, and `length` must be a valid
+/// pointer.
+#[no_mangle]
+pub unsafe extern "C" fn enso_lex_result_error_message
+(result:*const LexResult, length:*mut usize) -> *const c_char {
+    write_str((&*result).panic.as_ref().map(String::as_str),length)
+}
+
+/// Get the maximum depth of the state stack that the lexer exceeded while producing `result`.
+///
+/// Returns zero if the lexer did not stop with a `STATE_STACK_OVERFLOW` error.
+///
+/// # Safety
+/// The `result` must be a valid pointer returned by `enso_lexer_lex`.
+#[no_mangle]
+pub unsafe extern "C" fn enso_lex_result_error_max_depth(result:*const LexResult) -> usize {
+    match (&*result).result.error {
+        Some(LexingError::StateStackOverflow{max_depth,..}) => max_depth,
+        None                                                => 0,
+    }
+}
+
+/// Get the number of top-level tokens in the lexing `result`.
+///
+/// # Safety
+/// The `result` must be a valid pointer returned by `enso_lexer_lex`.
+#[no_mangle]
+pub unsafe extern "C" fn enso_lex_result_token_count(result:*const LexResult) -> usize {
+    (&*result).result.tokens.len()
+}
+
+/// Get the top-level token at `index` in the lexing `result`.
+///
+/// Returns null if `index` is out of bounds. The token is owned by the `result`.
+///
+/// # Safety
+/// The `result` must be a valid pointer returned by `enso_lexer_lex`.
+#[no_mangle]
+pub unsafe extern "C" fn enso_lex_result_token
+(result:*const LexResult, index:usize) -> *const Token {
+    Token::handle((&*result).result.tokens.get(index))
+}
+
+
+
+// =============
+// === Token ===
+// =============
+
+/// An opaque handle to a token owned by a `LexResult`.
+///
+/// Values of this type are never constructed. Pointers to it are pointers to [`token::Token`].
+#[derive(Clone,Copy,Debug)]
+pub struct Token {
+    _private : (),
+}
+
+impl Token {
+    /// Convert a borrowed token into a handle, using null to represent `None`.
+    fn handle(token:Option<&token::Token>) -> *const Token {
+        token.map_or(ptr::null(),|token| {
+            let pointer:*const token::Token = token;
+            pointer as *const Token
+        })
+    }
+
+    /// Convert a handle back into the token it refers to.
+    ///
+    /// # Safety
+    /// The `handle` must have been produced by [`Token::handle`] from a token that is still alive.
+    unsafe fn get<'a>(handle:*const Token) -> &'a token::Token {
+        &*(handle as *const token::Token)
+    }
+}
+
+/// The kind of the shape of a token.
+#[repr(C)]
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum ShapeKind {
+    /// An identifier in referent form.
+    Referent,
+    /// An identifier in variable form.
+    Variable,
+    /// An identifier not conforming to the Enso identifier rules.
+    External,
+    /// A blank identifier (`_`).
+    Blank,
+    /// A keyword.
+    Keyword,
+    /// An operator identifier.
+    Operator,
+    /// A modifier identifier.
+    Modifier,
+    /// An annotation.
+    Annotation,
+    /// A literal number.
+    Number,
+    /// A dangling base from a number literal.
+    DanglingBase,
+    /// An inline text literal.
+    TextLine,
+    /// A text block literal.
+    TextBlock,
+    /// A run of raw characters in a text literal.
+    TextSegmentRaw,
+    /// An escape sequence in a text literal.
+    TextSegmentEscape,
+    /// A splice in an interpolated text literal.
+    TextSegmentSplice,
+    /// A disable comment.
+    Comment,
+    /// A documentation comment.
+    DocComment,
+    /// The body of a foreign definition.
+    ForeignBlock,
+    /// A line containing tokens.
+    Line,
+    /// A blank line.
+    BlankLine,
+    /// A block of lines.
+    Block,
+    /// An invalid suffix.
+    InvalidSuffix,
+    /// An inline text literal that is not closed before the end of its line.
+    UnclosedTextLine,
+    /// A splice that is not closed before the end of its line.
+    UnclosedTextSplice,
+    /// A backtick that does not close a splice.
+    UnmatchedBacktick,
+    /// An annotation with an invalid name.
+    InvalidAnnotation,
+    /// A number literal with a digit that is not valid in its base.
+    InvalidNumber,
+    /// The indentation of a block line that contains tabs.
+    InvalidIndentation,
+    /// An invalid escape sequence in a text literal.
+    InvalidEscape,
+    /// An unrecognized token.
+    Unrecognized,
+}
+
+/// The line ending of a line token.
+#[repr(C)]
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum LineEnding {
+    /// There is no line ending.
+    None,
+    /// The unix-style line-feed (`'\n'`).
+    LF,
+    /// The windows-style carriage-return, line-feed (`"\r\n"`).
+    CRLF,
+}
+
+/// The whitespace making up an invalid indentation token.
+#[repr(C)]
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum IndentStyle {
+    /// Indentation made up of tabs alone.
+    Tabs,
+    /// Indentation made up of both tabs and spaces.
+    Mixed,
+}
+
+/// The type of a block token.
+#[repr(C)]
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum BlockType {
+    /// A block made up of arguments to a function.
+    Continuous,
+    /// A block made up of separate lines.
+    Discontinuous,
+}
+
+
+// === Trait Impls ===
+
+impl From<&token::Shape> for ShapeKind {
+    fn from(shape:&token::Shape) -> Self {
+        match shape {
+            token::Shape::Referent(_)            => Self::Referent,
+            token::Shape::Variable(_)            => Self::Variable,
+            token::Shape::External(_)            => Self::External,
+            token::Shape::Blank                  => Self::Blank,
+            token::Shape::Keyword(_)             => Self::Keyword,
+            token::Shape::Operator(_)            => Self::Operator,
+            token::Shape::Modifier(_)            => Self::Modifier,
+            token::Shape::Annotation(_)          => Self::Annotation,
+            token::Shape::Number{..}             => Self::Number,
+            token::Shape::DanglingBase(_)        => Self::DanglingBase,
+            token::Shape::TextLine{..}           => Self::TextLine,
+            token::Shape::TextBlock{..}          => Self::TextBlock,
+            token::Shape::TextSegmentRaw(_)      => Self::TextSegmentRaw,
+            token::Shape::TextSegmentEscape{..}  => Self::TextSegmentEscape,
+            token::Shape::TextSegmentSplice{..}  => Self::TextSegmentSplice,
+            token::Shape::Comment{..}            => Self::Comment,
+            token::Shape::DocComment{..}         => Self::DocComment,
+            token::Shape::ForeignBlock{..}       => Self::ForeignBlock,
+            token::Shape::Line{..}               => Self::Line,
+            token::Shape::BlankLine(_)           => Self::BlankLine,
+            token::Shape::Block{..}              => Self::Block,
+            token::Shape::InvalidSuffix(_)       => Self::InvalidSuffix,
+            token::Shape::UnclosedTextLine{..}   => Self::UnclosedTextLine,
+            token::Shape::UnclosedTextSplice{..} => Self::UnclosedTextSplice,
+            token::Shape::UnmatchedBacktick      => Self::UnmatchedBacktick,
+            token::Shape::InvalidAnnotation(_)   => Self::InvalidAnnotation,
+            token::Shape::InvalidNumber{..}      => Self::InvalidNumber,
+            token::Shape::InvalidIndentation{..} => Self::InvalidIndentation,
+            token::Shape::InvalidEscape(_)       => Self::InvalidEscape,
+            token::Shape::Unrecognized(_)        => Self::Unrecognized,
+        }
+    }
+}
+
+impl From<token::LineEnding> for LineEnding {
+    fn from(line_ending:token::LineEnding) -> Self {
+        match line_ending {
+            token::LineEnding::None => Self::None,
+            token::LineEnding::LF   => Self::LF,
+            token::LineEnding::CRLF => Self::CRLF,
+        }
+    }
+}
+
+impl From<token::IndentStyle> for IndentStyle {
+    fn from(style:token::IndentStyle) -> Self {
+        match style {
+            token::IndentStyle::Tabs  => Self::Tabs,
+            token::IndentStyle::Mixed => Self::Mixed,
+        }
+    }
+}
+
+impl From<token::BlockType> for BlockType {
+    fn from(block_type:token::BlockType) -> Self {
+        match block_type {
+            token::BlockType::Continuous    => Self::Continuous,
+            token::BlockType::Discontinuous => Self::Discontinuous,
+        }
+    }
+}
+
+
+// === API ===
+
+/// Get the kind of the shape of `token`.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_shape(token:*const Token) -> ShapeKind {
+    (&Token::get(token).shape).into()
+}
+
+/// Get the length of `token` in characters.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_length(token:*const Token) -> usize {
+    Token::get(token).length
+}
+
+/// Get the number of trailing spaces after `token` before the next.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_offset(token:*const Token) -> usize {
+    Token::get(token).offset
+}
+
+/// Get the text of `token`, writing its length in bytes to `length`.
+///
+/// For number tokens this is the integral part of the number, for invalid numbers it is the number
+/// without its base, for escape sequences it is the escape as written in the source, for disable
+/// comments it is the text following the `#`, and for the bodies of foreign definitions it is the
+/// name of their language. The text is UTF-8 encoded, is not null terminated, and is owned by the
+/// result containing `token`. Returns null for tokens that carry no text, such as blanks, lines,
+/// blocks and text literals.
+///
+/// # Safety
+/// The `token` must be a valid token handle, and `length` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_text(token:*const Token, length:*mut usize) -> *const c_char {
+    let text = match &Token::get(token).shape {
+        token::Shape::Referent(text)             => Some(text.as_str()),
+        token::Shape::Variable(text)             => Some(text.as_str()),
+        token::Shape::External(text)             => Some(text.as_str()),
+        token::Shape::Keyword(text)              => Some(text.as_str()),
+        token::Shape::Operator(text)             => Some(text.as_str()),
+        token::Shape::Modifier(text)             => Some(text.as_str()),
+        token::Shape::Annotation(text)           => Some(text.as_str()),
+        token::Shape::Number{integer,..}         => Some(integer.as_str()),
+        token::Shape::DanglingBase(text)         => Some(text.as_str()),
+        token::Shape::TextSegmentRaw(text)       => Some(text.as_str()),
+        token::Shape::TextSegmentEscape{repr,..} => Some(repr.as_str()),
+        token::Shape::Comment{text,..}           => Some(text.as_str()),
+        token::Shape::ForeignBlock{language,..}  => Some(language.as_str()),
+        token::Shape::InvalidSuffix(text)        => Some(text.as_str()),
+        token::Shape::InvalidAnnotation(text)    => Some(text.as_str()),
+        token::Shape::InvalidNumber{number,..}   => Some(number.as_str()),
+        token::Shape::InvalidEscape(text)        => Some(text.as_str()),
+        token::Shape::Unrecognized(text)         => Some(text.as_str()),
+        _                                        => None,
+    };
+    write_str(text,length)
+}
+
+/// Get the base of a number, dangling base or invalid number `token`, writing its length in bytes
+/// to `length`.
+///
+/// The base is empty for numbers without an explicit base. Returns null for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle, and `length` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_number_base
+(token:*const Token, length:*mut usize) -> *const c_char {
+    let base = match &Token::get(token).shape {
+        token::Shape::Number{base,..}        => Some(base),
+        token::Shape::DanglingBase(base)     => Some(base),
+        token::Shape::InvalidNumber{base,..} => Some(base),
+        _                                    => None,
+    };
+    write_str(base.map(String::as_str),length)
+}
+
+/// Get the fractional part of a number `token`, writing its length in bytes to `length`.
+///
+/// The fractional part is empty for numbers without one. Returns null for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle, and `length` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_number_fractional
+(token:*const Token, length:*mut usize) -> *const c_char {
+    let fractional = match &Token::get(token).shape {
+        token::Shape::Number{fractional,..} => Some(fractional),
+        _                                   => None,
+    };
+    write_str(fractional.map(String::as_str),length)
+}
+
+/// Get the exponent of a number `token`, including its sign, writing its length in bytes to
+/// `length`.
+///
+/// The exponent is empty for numbers without one. Returns null for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle, and `length` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_number_exponent
+(token:*const Token, length:*mut usize) -> *const c_char {
+    let exponent = match &Token::get(token).shape {
+        token::Shape::Number{exponent,..} => Some(exponent),
+        _                                 => None,
+    };
+    write_str(exponent.map(String::as_str),length)
+}
+
+/// Get the index (in characters) of the first invalid digit in the text of an invalid number
+/// `token`.
+///
+/// Returns zero for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_number_invalid_digit(token:*const Token) -> usize {
+    match &Token::get(token).shape {
+        token::Shape::InvalidNumber{invalid_digit,..} => *invalid_digit,
+        _                                             => 0,
+    }
+}
+
+/// Get the whitespace making up an invalid indentation `token`.
+///
+/// Returns `IndentStyle::Tabs` for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_indentation_style(token:*const Token) -> IndentStyle {
+    match &Token::get(token).shape {
+        token::Shape::InvalidIndentation{style,..} => (*style).into(),
+        _                                          => IndentStyle::Tabs,
+    }
+}
+
+/// Get the line, counting from zero, of the first tab in an invalid indentation `token`.
+///
+/// Returns zero for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_indentation_line(token:*const Token) -> usize {
+    match &Token::get(token).shape {
+        token::Shape::InvalidIndentation{line,..} => *line,
+        _                                         => 0,
+    }
+}
+
+/// Get the column, counting from zero, of the first tab in an invalid indentation `token`.
+///
+/// Returns zero for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_indentation_column(token:*const Token) -> usize {
+    match &Token::get(token).shape {
+        token::Shape::InvalidIndentation{column,..} => *column,
+        _                                           => 0,
+    }
+}
+
+/// Get the number of children of `token`.
+///
+/// The children of a line are its tokens, the children of a block, text block, documentation
+/// comment or foreign block are its lines, the children of an inline text literal are its segments,
+/// and the children of a splice or disable comment are its tokens. All other tokens have no
+/// children.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_child_count(token:*const Token) -> usize {
+    Token::get(token).shape.children().len()
+}
+
+/// Get the child of `token` at `index`.
+///
+/// Returns null if `index` is out of bounds.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_child(token:*const Token, index:usize) -> *const Token {
+    Token::handle(Token::get(token).shape.children().get(index))
+}
+
+/// Check whether `token` is an error, or contains one among its descendants.
+///
+/// The tokens of a disable comment whose text is prose rather than code usually contain errors.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_has_errors(token:*const Token) -> bool {
+    Token::get(token).has_errors()
+}
+
+/// Get the line ending of a line or blank line `token`.
+///
+/// Returns `LineEnding::None` for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_line_ending(token:*const Token) -> LineEnding {
+    match &Token::get(token).shape {
+        token::Shape::Line{trailing_line_ending,..} => (*trailing_line_ending).into(),
+        token::Shape::BlankLine(line_ending)        => (*line_ending).into(),
+        _                                           => LineEnding::None,
+    }
+}
+
+/// Get the type of a block `token`.
+///
+/// Returns `BlockType::Discontinuous` for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_block_type(token:*const Token) -> BlockType {
+    match &Token::get(token).shape {
+        token::Shape::Block{block_type,..} => (*block_type).into(),
+        _                                  => BlockType::Discontinuous,
+    }
+}
+
+/// Get the indentation of a block, text block, documentation comment or foreign block `token`.
+///
+/// Returns zero for all other tokens.
+///
+/// # Safety
+/// The `token` must be a valid token handle.
+#[no_mangle]
+pub unsafe extern "C" fn enso_token_block_indent(token:*const Token) -> usize {
+    match &Token::get(token).shape {
+        token::Shape::Block{indent,..}        => *indent,
+        token::Shape::TextBlock{indent,..}    => *indent,
+        token::Shape::DocComment{indent,..}   => *indent,
+        token::Shape::ForeignBlock{indent,..} => *indent,
+        _                                     => 0,
+    }
+}
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Expose `text` as a pointer to its bytes, writing its length to `length`.
+///
+/// # Safety
+/// The `length` must be a valid pointer.
+unsafe fn write_str(text:Option<&str>, length:*mut usize) -> *const c_char {
+    match text {
+        Some(text) => {
+            *length = text.len();
+            text.as_ptr() as *const c_char
+        }
+        None => {
+            *length = 0;
+            ptr::null()
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lex_with_panic() {
+        let lexer = enso_lexer_new();
+        unsafe {
+            let result     = (&mut *lexer).lex_with(|_| panic!("Lexer panicked."));
+            let result     = Box::into_raw(Box::new(result));
+            let mut length = 0;
+            let message    = enso_lex_result_error_message(result,&mut length);
+            assert_eq!(enso_lex_result_kind(result),LexResultKind::Failure);
+            assert_eq!(enso_lex_result_error_kind(result),LexErrorKind::Panic);
+            assert_eq!(enso_lex_result_token_count(result),0);
+            assert_eq!(std::slice::from_raw_parts(message as *const u8,length),b"Lexer panicked.");
+            enso_lex_result_free(result);
+            let input  = "foo";
+            let result = enso_lexer_lex(lexer,input.as_ptr() as *const c_char,input.len());
+            assert_eq!(enso_lex_result_kind(result),LexResultKind::Success);
+            assert_eq!(enso_lex_result_error_kind(result),LexErrorKind::None);
+            assert!(enso_lex_result_error_message(result,&mut length).is_null());
+            enso_lex_result_free(result);
+            enso_lexer_free(lexer);
+        }
+    }
+}
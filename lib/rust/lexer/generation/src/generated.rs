@@ -1,3 +1,4 @@
 //! This module re-exports the generated lexer sources.
 
 pub mod engine;
+pub mod engine_utf8;
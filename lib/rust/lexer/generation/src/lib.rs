@@ -10,6 +10,9 @@
 
 //! This module exports the interface to the generated Enso lexer.
 
+pub mod batch;
+#[allow(unsafe_code)]
+pub mod ffi;
 pub mod generated;
 
 /// Support libraries for the lexer definition.
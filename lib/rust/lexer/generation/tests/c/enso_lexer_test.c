@@ -0,0 +1,152 @@
+/* A test program for the C interface to the Enso lexer.
+ *
+ * It is built against the shared library of the lexer and run by the `enso_lexer_c` test of the
+ * `lexer` crate, as part of `cargo test`.
+ */
+
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "enso_lexer.h"
+
+
+
+/* =================
+ * === Utilities ===
+ * ================= */
+
+static int failures = 0;
+
+#define CHECK(condition)                                                       \
+  do {                                                                         \
+    if (!(condition)) {                                                        \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__,         \
+              #condition);                                                     \
+      failures++;                                                              \
+    }                                                                          \
+  } while (0)
+
+/* Lex the null-terminated `input` with `lexer`. */
+static EnsoLexResult *lex(EnsoLexer *lexer, const char *input) {
+  return enso_lexer_lex(lexer, input, strlen(input));
+}
+
+/* Check that `token` has the provided `shape` and `text`. */
+static void check_text(const EnsoToken *token, EnsoShapeKind shape,
+                       const char *text) {
+  uintptr_t length = 0;
+  const char *actual = enso_token_text(token, &length);
+  CHECK(enso_token_shape(token) == shape);
+  CHECK(actual != NULL);
+  CHECK(length == strlen(text));
+  CHECK(actual != NULL && strncmp(actual, text, length) == 0);
+}
+
+
+
+
+/* =============
+ * === Tests ===
+ * ============= */
+
+static void test_identifiers(EnsoLexer *lexer) {
+  EnsoLexResult *result = lex(lexer, "foo Bar _");
+  CHECK(result != NULL);
+  CHECK(enso_lex_result_kind(result) == ENSO_LEX_RESULT_KIND_SUCCESS);
+  CHECK(enso_lex_result_token_count(result) == 3);
+  const EnsoToken *foo = enso_lex_result_token(result, 0);
+  check_text(foo, ENSO_SHAPE_KIND_VARIABLE, "foo");
+  CHECK(enso_token_length(foo) == 3);
+  CHECK(enso_token_offset(foo) == 0);
+  CHECK(enso_token_child_count(foo) == 0);
+  check_text(enso_lex_result_token(result, 1), ENSO_SHAPE_KIND_REFERENT,
+             "Bar");
+  CHECK(enso_token_offset(enso_lex_result_token(result, 1)) == 1);
+  CHECK(enso_token_shape(enso_lex_result_token(result, 2)) ==
+        ENSO_SHAPE_KIND_BLANK);
+  CHECK(enso_lex_result_token(result, 3) == NULL);
+  enso_lex_result_free(result);
+}
+
+static void test_numbers(EnsoLexer *lexer) {
+  EnsoLexResult *result = lex(lexer, "16_ff");
+  const EnsoToken *number = enso_lex_result_token(result, 0);
+  uintptr_t length = 0;
+  const char *base = enso_token_number_base(number, &length);
+  check_text(number, ENSO_SHAPE_KIND_NUMBER, "ff");
+  CHECK(length == 2 && strncmp(base, "16", length) == 0);
+  enso_lex_result_free(result);
+}
+
+static void test_blocks(EnsoLexer *lexer) {
+  EnsoLexResult *result = lex(lexer, "a\n  b");
+  const EnsoToken *top = enso_lex_result_token(result, 0);
+  CHECK(enso_lex_result_token_count(result) == 1);
+  CHECK(enso_token_shape(top) == ENSO_SHAPE_KIND_BLOCK);
+  CHECK(enso_token_block_type(top) == ENSO_BLOCK_TYPE_CONTINUOUS);
+  CHECK(enso_token_block_indent(top) == 0);
+  const EnsoToken *line = enso_token_child(top, 0);
+  CHECK(enso_token_line_ending(line) == ENSO_LINE_ENDING_LF);
+  CHECK(enso_token_child_count(line) == 2);
+  const EnsoToken *block = enso_token_child(line, 1);
+  CHECK(enso_token_shape(block) == ENSO_SHAPE_KIND_BLOCK);
+  CHECK(enso_token_block_indent(block) == 2);
+  const EnsoToken *nested_line = enso_token_child(block, 0);
+  CHECK(enso_token_line_ending(nested_line) == ENSO_LINE_ENDING_NONE);
+  check_text(enso_token_child(nested_line, 0), ENSO_SHAPE_KIND_VARIABLE, "b");
+  enso_lex_result_free(result);
+}
+
+static void test_errors(EnsoLexer *lexer) {
+  EnsoLexResult *result = lex(lexer, "foo");
+  CHECK(enso_lex_result_error_kind(result) == ENSO_LEX_ERROR_KIND_NONE);
+  CHECK(enso_lex_result_error_max_depth(result) == 0);
+  enso_lex_result_free(result);
+  size_t nesting = 4096;
+  char *input = malloc(2 * nesting + 1);
+  CHECK(input != NULL);
+  for (size_t i = 0; input != NULL && i < nesting; i++) {
+    input[2 * i] = '\'';
+    input[2 * i + 1] = '`';
+  }
+  if (input != NULL) {
+    input[2 * nesting] = '\0';
+    result = lex(lexer, input);
+    CHECK(enso_lex_result_kind(result) == ENSO_LEX_RESULT_KIND_FAILURE);
+    CHECK(enso_lex_result_error_kind(result) ==
+          ENSO_LEX_ERROR_KIND_STATE_STACK_OVERFLOW);
+    CHECK(enso_lex_result_error_max_depth(result) == nesting);
+    enso_lex_result_free(result);
+    free(input);
+  }
+}
+
+static void test_invalid_input(EnsoLexer *lexer) {
+  const char invalid[] = {'a', (char)0xFF};
+  CHECK(enso_lexer_lex(lexer, invalid, sizeof(invalid)) == NULL);
+  CHECK(enso_lexer_lex(NULL, "a", 1) == NULL);
+}
+
+
+
+/* ============
+ * === Main ===
+ * ============ */
+
+int main(void) {
+  EnsoLexer *lexer = enso_lexer_new();
+  test_identifiers(lexer);
+  test_numbers(lexer);
+  test_blocks(lexer);
+  test_errors(lexer);
+  test_invalid_input(lexer);
+  enso_lexer_free(lexer);
+  if (failures > 0) {
+    fprintf(stderr, "%d check(s) failed.\n", failures);
+    return 1;
+  }
+  printf("All checks passed.\n");
+  return 0;
+}
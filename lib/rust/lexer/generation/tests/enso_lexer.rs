@@ -16,11 +16,18 @@ use flexer::*;
 use lexer_definition::library::*;
 
 use flexer::prelude::reader::decoder::DecoderUTF8;
+use flexer::prelude::ByteReader;
 use flexer::prelude::Reader;
+use flexer::prelude::StrReader;
 use lexer::generated::engine::EnsoLexer;
+use lexer::generated::engine::Keywords;
+use lexer::generated::engine_utf8;
 use lexer_definition::library::token::Token;
 use lexer_definition::token::BlockType;
+use lexer_definition::token::EscapeStyle;
+use lexer_definition::token::IndentStyle;
 use lexer_definition::token::LineEnding;
+use lexer_definition::token::TextStyle;
 
 
 
@@ -39,7 +46,13 @@ fn assert_succeeds_as(result:&LexingResult<token::Stream>, expected:token::Strea
 /// Assert that the provided input lexes as `expected`.
 fn assert_lexes(input:impl AsRef<str>, expected:token::Stream) {
     let input_len = input.as_ref().chars().count();
+    let shared    = lex_shared(input.as_ref());
+    let utf8      = lex_utf8(input.as_ref());
+    let reused    = lex_reused(input.as_ref());
     let result    = lex(input);
+    assert_succeeds_as(&shared,expected.clone());
+    assert_succeeds_as(&utf8,expected.clone());
+    assert_succeeds_as(&reused,expected.clone());
     assert_succeeds_as(&result,expected);
     let tokens_vec   : Vec<_> = result.tokens.into();
     let total_length : usize  = tokens_vec.iter().map(|token| token.offset + token.length).sum();
@@ -50,7 +63,48 @@ fn assert_lexes(input:impl AsRef<str>, expected:token::Stream) {
 fn lex(input:impl AsRef<str>) -> LexingResult<token::Stream> {
     let mut lexer = EnsoLexer::new();
     let reader    = Reader::new(input.as_ref().as_bytes(),DecoderUTF8());
-    lexer.run(reader)
+    let result    = lexer.run(reader);
+    #[cfg(feature="rule-coverage")]
+    record_rule_coverage();
+    result
+}
+
+/// Write the rules of the lexer that have not yet fired during this test run to
+/// `enso_lexer_rule_coverage.txt` in the output directory of the build profile, next to the `deps`
+/// directory holding the test binary.
+///
+/// The report is rewritten after every input is lexed, so that once the tests finish it covers all
+/// of them.
+#[cfg(feature="rule-coverage")]
+fn record_rule_coverage() {
+    flexer::prelude::lazy_static! {
+        static ref REPORT_LOCK:std::sync::Mutex<()> = std::sync::Mutex::new(());
+    }
+    let _lock  = REPORT_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+    let binary = std::env::current_exe().unwrap();
+    let path   = binary.parent().and_then(|deps| deps.parent()).unwrap();
+    let report = EnsoLexer::rule_coverage().to_string();
+    std::fs::write(path.join("enso_lexer_rule_coverage.txt"),report).unwrap();
+}
+
+/// Lex the provided string from memory, sharing the text of matches with the input.
+fn lex_shared(input:&str) -> LexingResult<token::Stream> {
+    let mut lexer = EnsoLexer::new();
+    lexer.run(StrReader::new(input))
+}
+
+/// Lex the provided string with the engine that runs directly over its UTF-8 bytes.
+fn lex_utf8(input:&str) -> LexingResult<token::Stream> {
+    let mut lexer = engine_utf8::EnsoLexer::new();
+    lexer.run(ByteReader::new(input))
+}
+
+/// Lex the provided string with a lexer that has been reset after lexing some other input.
+fn lex_reused(input:&str) -> LexingResult<token::Stream> {
+    let mut lexer = EnsoLexer::new();
+    lexer.run(StrReader::new("foo\n    bar (\n  baz 0.12 +"));
+    lexer.reset();
+    lexer.run(StrReader::new(input))
 }
 
 /// Asserts that the input is a block and has a length equal to `length`.
@@ -208,20 +262,6 @@ fn typeset_subtraction_operator() {
     assert_lexes(input,expected);
 }
 
-#[test]
-fn disable_comment() {
-    let input    = "#";
-    let expected = token::Stream::from(vec![Token::Operator("#",0)]);
-    assert_lexes(input,expected);
-}
-
-#[test]
-fn doc_comment() {
-    let input    = "##";
-    let expected = token::Stream::from(vec![Token::Operator("##",0)]);
-    assert_lexes(input,expected);
-}
-
 #[test]
 fn arbitrary_left_operator() {
     let input    = "<!!-";
@@ -340,6 +380,61 @@ fn multi_ticked_referent_ident() {
     assert_lexes(input,expected)
 }
 
+#[test]
+fn keywords() {
+    let input    = "type import from polyglot foreign case of here";
+    let expected = token::Stream::from(vec![
+        Token::Keyword("type",0),
+        Token::Keyword("import",1),
+        Token::Keyword("from",1),
+        Token::Keyword("polyglot",1),
+        Token::Keyword("foreign",1),
+        Token::Keyword("case",1),
+        Token::Keyword("of",1),
+        Token::Keyword("here",1),
+    ]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn ticked_keyword() {
+    let input    = "type'";
+    let expected = token::Stream::from(vec![Token::Variable("type'",0)]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn keyword_prefix() {
+    let input    = "types of_x";
+    let expected = token::Stream::from(vec![
+        Token::Variable("types",0),
+        Token::Variable("of_x",1),
+    ]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn keyword_with_error_suffix() {
+    let input    = "caseД";
+    let expected = token::Stream::from(vec![
+        Token::Keyword("case",0),
+        Token::InvalidSuffix("Д",0),
+    ]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn configured_keywords() {
+    let mut lexer = EnsoLexer::with_keywords(Keywords::new(vec!["here","this"]));
+    let result    = lexer.run(StrReader::new("this type here"));
+    let expected  = token::Stream::from(vec![
+        Token::Keyword("this",0),
+        Token::Variable("type",1),
+        Token::Keyword("here",1),
+    ]);
+    assert_succeeds_as(&result,expected)
+}
+
 #[test]
 fn variable_with_numbers() {
     let input    = "some0_1";
@@ -390,10 +485,79 @@ fn invalid_suffix() {
 
 #[test]
 fn unrecognized_token() {
-    let input    = "some_var`";
+    let input    = "some_var Д";
+    let expected = token::Stream::from(vec![
+        Token::Variable("some_var",0),
+        Token::Unrecognized("Д",1),
+    ]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn annotation() {
+    let input    = "@Builtin_Method \"Foo\"";
+    let expected = token::Stream::from(vec![
+        Token::Annotation("Builtin_Method",0),
+        Token::TextLine(TextStyle::Raw,vec![Token::TextSegmentRaw("Foo",0)],1),
+    ]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn variable_annotation() {
+    let input    = "@tail_call foo";
+    let expected = token::Stream::from(vec![
+        Token::Annotation("tail_call",0),
+        Token::Variable("foo",1),
+    ]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn annotation_with_error_suffix() {
+    let input    = "@Foo'";
+    let expected = token::Stream::from(vec![
+        Token::Annotation("Foo",0),
+        Token::InvalidSuffix("'",0),
+    ]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn annotation_with_external_name() {
+    let input    = "@Builtin_method";
+    let expected = token::Stream::from(vec![Token::InvalidAnnotation("Builtin_method",0)]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn annotation_without_name() {
+    let input    = "@ Foo";
+    let expected = token::Stream::from(vec![
+        Token::InvalidAnnotation("",0),
+        Token::Referent("Foo",1),
+    ]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn annotation_followed_by_operator() {
+    let input    = "a @+ b";
+    let expected = token::Stream::from(vec![
+        Token::Variable("a",0),
+        Token::InvalidAnnotation("",1),
+        Token::Operator("+",0),
+        Token::Variable("b",1),
+    ]);
+    assert_lexes(input,expected)
+}
+
+#[test]
+fn annotation_after_identifier() {
+    let input    = "some_var@";
     let expected = token::Stream::from(vec![
         Token::Variable("some_var",0),
-        Token::Unrecognized("`",0),
+        Token::InvalidAnnotation("",0),
     ]);
     assert_lexes(input,expected)
 }
@@ -410,6 +574,33 @@ fn chained_identifiers() {
     assert_lexes(input,expected)
 }
 
+#[test]
+fn identifiers_share_input() {
+    let result = lex_shared("foo Bar");
+    let tokens = result.tokens.tokens();
+    match (&tokens[0].shape,&tokens[1].shape) {
+        (token::Shape::Variable(foo),token::Shape::Referent(bar)) => {
+            assert!(foo.is_shared());
+            assert!(bar.is_shared());
+        }
+        shapes => panic!("Expected a variable and a referent, found {:?}.",shapes),
+    }
+}
+
+#[test]
+fn annotations_share_input() {
+    let result = lex_shared("@Builtin_Method @");
+    let tokens = result.tokens.tokens();
+    match (&tokens[0].shape,&tokens[1].shape) {
+        (token::Shape::Annotation(name),token::Shape::InvalidAnnotation(text)) => {
+            assert_eq!(name,"Builtin_Method");
+            assert!(name.is_shared());
+            assert!(text.is_shared());
+        }
+        shapes => panic!("Expected an annotation and an invalid one, found {:?}.",shapes),
+    }
+}
+
 
 
 // ===============
@@ -419,14 +610,14 @@ fn chained_identifiers() {
 #[test]
 fn integer() {
     let input    = "13831";
-    let expected = token::Stream::from(vec![Token::Number("","13831",0)]);
+    let expected = token::Stream::from(vec![Token::Number("","13831","","",0)]);
     assert_lexes(input,expected);
 }
 
 #[test]
 fn integer_with_explicit_base() {
     let input    = "10_13831";
-    let expected = token::Stream::from(vec![Token::Number("10","13831",0)]);
+    let expected = token::Stream::from(vec![Token::Number("10","13831","","",0)]);
     assert_lexes(input,expected);
 }
 
@@ -440,21 +631,31 @@ fn dangling_base() {
 #[test]
 fn hex_number() {
     let input    = "16_ff";
-    let expected = token::Stream::from(vec![Token::Number("16","ff",0)]);
+    let expected = token::Stream::from(vec![Token::Number("16","ff","","",0)]);
     assert_lexes(input,expected);
 }
 
 #[test]
 fn decimal() {
     let input    = "2.71828";
-    let expected = token::Stream::from(vec![Token::Number("","2.71828",0)]);
+    let expected = token::Stream::from(vec![Token::Number("","2","71828","",0)]);
     assert_lexes(input,expected);
 }
 
 #[test]
 fn decimal_with_explicit_base() {
     let input    = "10_2.71828";
-    let expected = token::Stream::from(vec![Token::Number("10","2.71828",0)]);
+    let expected = token::Stream::from(vec![Token::Number("10","2","71828","",0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn multi_digit_integer_before_point() {
+    let input    = "10.";
+    let expected = token::Stream::from(vec![
+        Token::Number("","10","","",0),
+        Token::Operator(".",0),
+    ]);
     assert_lexes(input,expected);
 }
 
@@ -462,7 +663,7 @@ fn decimal_with_explicit_base() {
 fn error_base() {
     let input    = "10.2_2";
     let expected = token::Stream::from(vec![
-        Token::Number("","10.2",0),
+        Token::Number("","10","2","",0),
         Token::InvalidSuffix("_2",0),
     ]);
     assert_lexes(input,expected);
@@ -472,156 +673,1305 @@ fn error_base() {
 fn offset_number() {
     let input    = "    10.2";
     let expected = token::Stream::from(vec![
-        Token::Number("","10.2",4),
+        Token::Number("","10","2","",4),
     ]);
     assert_lexes(input,expected);
 }
 
+#[test]
+fn integer_with_exponent() {
+    let input    = "1e10";
+    let expected = token::Stream::from(vec![Token::Number("","1","","10",0)]);
+    assert_lexes(input,expected);
+}
 
+#[test]
+fn decimal_with_negative_exponent() {
+    let input    = "1.5e-10";
+    let expected = token::Stream::from(vec![Token::Number("","1","5","-10",0)]);
+    assert_lexes(input,expected);
+}
 
-// ============
-// === Text ===
-// ============
-
+#[test]
+fn decimal_with_positive_exponent() {
+    let input    = "2.5e+3";
+    let expected = token::Stream::from(vec![Token::Number("","2","5","+3",0)]);
+    assert_lexes(input,expected);
+}
 
+#[test]
+fn exponent_with_error_suffix() {
+    let input    = "1e5x";
+    let expected = token::Stream::from(vec![
+        Token::Number("","1","","5",0),
+        Token::InvalidSuffix("x",0),
+    ]);
+    assert_lexes(input,expected);
+}
 
-// ==============
-// === Blocks ===
-// ==============
+#[test]
+fn integer_with_upper_case_exponent() {
+    let input    = "1E5";
+    let expected = token::Stream::from(vec![Token::Number("","1","","5",0)]);
+    assert_lexes(input,expected);
+}
 
 #[test]
-fn block_function_call() {
-    let input = make_unix_line_endings(
-r#"f
-    argument_1
-    argument_2
-    fn a1 a2 a3
-    argument_4
-    argument_5"#);
-    let block_fn_args =
-        Token::Block(
-            BlockType::Continuous,
-            4,
-            vec![
-                Token::Line(
-                    vec![Token::Variable("argument_1",0)],
-                    0,
-                    LineEnding::LF
-                ),
-                Token::Line(
-                    vec![
-                        Token::Variable("argument_2",0),
-                    ],
-                    0,
-                    LineEnding::LF
-                ),
-                Token::Line(
-                    vec![
-                        Token::Variable("fn",0),
-                        Token::Variable("a1",1),
-                        Token::Variable("a2",1),
-                        Token::Variable("a3",1),
-                    ],
-                    0,
-                    LineEnding::LF
-                ),
-                Token::Line(
-                    vec![
-                        Token::Variable("argument_4",0),
-                    ],
-                    0,
-                    LineEnding::LF
-                ),
-                Token::Line(
-                    vec![
-                        Token::Variable("argument_5",0),
-                    ],
-                    0,
-                    LineEnding::None
-                ),
-            ],
-            0
-        );
-    let top_level_first_line = Token::Line(
-        vec![
-            Token::Variable("f",0),
-            block_fn_args
-        ],
-        0,
-        LineEnding::LF
-    );
-    let top_level_block = token::Stream::from(vec![
-        Token::Block(
-            BlockType::Continuous,
-            0,
-            vec![top_level_first_line],
-            0
-        )
+fn integer_with_dangling_exponent() {
+    let input    = "1e";
+    let expected = token::Stream::from(vec![
+        Token::Number("","1","","",0),
+        Token::InvalidSuffix("e",0),
     ]);
-    assert_lexes(input,top_level_block);
+    assert_lexes(input,expected);
 }
 
-
 #[test]
-fn block_empty_lines() {
-    let input        = "f\r\n    a\n\n    b\n";
-    let nested_block = Token::Block(
-        BlockType::Continuous,
-        4,
-        vec![
-            Token::Line(vec![Token::Variable("a",0)],0,LineEnding::LF),
-            Token::BlankLine(0,LineEnding::LF),
-            Token::Line(vec![Token::Variable("b",0)],0,LineEnding::LF),
-        ],
-        0
-    );
-    let top_line = Token::Line(
-        vec![
-            Token::Variable("f",0),
-            nested_block
-        ],
-        0,
-        LineEnding::CRLF
-    );
+fn decimal_with_dangling_exponent() {
+    let input    = "1.5E";
     let expected = token::Stream::from(vec![
-        Token::Block(
-            BlockType::Continuous,
-            0,
-            vec![top_line],
-            0
-        )
+        Token::Number("","1","5","",0),
+        Token::InvalidSuffix("E",0),
     ]);
     assert_lexes(input,expected);
 }
 
 #[test]
-fn block_top_level() {
-    let input = make_unix_line_endings(
-r#"
+fn hex_number_with_digit_e() {
+    let input    = "16_1e5";
+    let expected = token::Stream::from(vec![Token::Number("16","1e5","","",0)]);
+    assert_lexes(input,expected);
+}
 
-foo
-bar
-baz
-"#);
+#[test]
+fn invalid_binary_digit() {
+    let input    = "2_10120";
+    let expected = token::Stream::from(vec![Token::InvalidNumber("2","10120",3,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn invalid_decimal_digit_with_explicit_base() {
+    let input    = "10_12.3a";
+    let expected = token::Stream::from(vec![Token::InvalidNumber("10","12.3a",4,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn invalid_digit_after_offset() {
+    let input    = "x = 8_778";
     let expected = token::Stream::from(vec![
-        Token::Block(
-            BlockType::Continuous,
-            0,
-            vec![
-                Token::BlankLine(0,LineEnding::LF),
-                Token::BlankLine(0,LineEnding::LF),
-                Token::Line(vec![Token::Variable("foo",0)],0,LineEnding::LF),
-                Token::Line(vec![Token::Variable("bar",0)],0,LineEnding::LF),
-                Token::Line(vec![Token::Variable("baz",0)],0,LineEnding::LF),
-            ],
-            0
-        )
+        Token::Variable("x",0),
+        Token::Operator("=",1),
+        Token::InvalidNumber("8","778",2,1),
     ]);
     assert_lexes(input,expected);
 }
 
 #[test]
-fn block_with_operator() {
+fn base_out_of_range() {
+    let input    = "37_10";
+    let expected = token::Stream::from(vec![Token::InvalidNumber("37","10",0,0)]);
+    assert_lexes(input,expected);
+}
+
+
+
+// ============
+// === Text ===
+// ============
+
+#[test]
+fn raw_text_line() {
+    let input    = r#""Hello, world!""#;
+    let segments = vec![Token::TextSegmentRaw("Hello, world!",0)];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Raw,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn empty_raw_text_line() {
+    let input    = r#""""#;
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Raw,vec![],0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn raw_text_line_with_offset() {
+    let input    = r#"foo   "bar" baz"#;
+    let segments = vec![Token::TextSegmentRaw("bar",0)];
+    let expected = token::Stream::from(vec![
+        Token::Variable("foo",0),
+        Token::TextLine(TextStyle::Raw,segments,3),
+        Token::Variable("baz",1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn raw_text_line_simple_escapes() {
+    let input    = r#""a\nb\t\\\"""#;
+    let segments = vec![
+        Token::TextSegmentRaw("a",0),
+        Token::TextSegmentEscape(EscapeStyle::Simple,r"\n",0),
+        Token::TextSegmentRaw("b",0),
+        Token::TextSegmentEscape(EscapeStyle::Simple,r"\t",0),
+        Token::TextSegmentEscape(EscapeStyle::Simple,r"\\",0),
+        Token::TextSegmentEscape(EscapeStyle::Simple,r#"\""#,0),
+    ];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Raw,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn raw_text_line_byte_escape() {
+    let input    = r#""\x41BC""#;
+    let segments = vec![
+        Token::TextSegmentEscape(EscapeStyle::Byte,r"\x41",0),
+        Token::TextSegmentRaw("BC",0),
+    ];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Raw,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn raw_text_line_unicode_escape() {
+    let input    = r#""smile \u{1F600}""#;
+    let segments = vec![
+        Token::TextSegmentRaw("smile ",0),
+        Token::TextSegmentEscape(EscapeStyle::Unicode,r"\u{1F600}",0),
+    ];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Raw,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn raw_text_line_invalid_escape() {
+    let input    = r#""a\qb\x4""#;
+    let segments = vec![
+        Token::TextSegmentRaw("a",0),
+        Token::InvalidEscape(r"\q",0),
+        Token::TextSegmentRaw("b",0),
+        Token::InvalidEscape(r"\x",0),
+        Token::TextSegmentRaw("4",0),
+    ];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Raw,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn raw_text_line_invalid_unicode_escape() {
+    let input    = r#""\u{110000}""#;
+    let segments = vec![Token::InvalidEscape(r"\u{110000}",0)];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Raw,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_raw_text_line_at_eof() {
+    let input    = r#"foo "bar\"#;
+    let segments = vec![Token::TextSegmentRaw("bar",0),Token::InvalidEscape(r"\",0)];
+    let expected = token::Stream::from(vec![
+        Token::Variable("foo",0),
+        Token::UnclosedTextLine(TextStyle::Raw,segments,1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_raw_text_line_at_line_end() {
+    let input    = make_unix_line_endings("\"bar\nbaz");
+    let segments = vec![Token::TextSegmentRaw("bar",0)];
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(
+                    vec![Token::UnclosedTextLine(TextStyle::Raw,segments,0)],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(vec![Token::Variable("baz",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+
+#[test]
+fn interpolated_text_line() {
+    let input    = "'Age is `person.age`'";
+    let splice   = token::Stream::from(vec![
+        Token::Variable("person",0),
+        Token::Operator(".",0),
+        Token::Variable("age",0),
+    ]);
+    let segments = vec![
+        Token::TextSegmentRaw("Age is ",0),
+        Token::TextSegmentSplice(splice,0,0),
+    ];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Interpolated,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn interpolated_text_line_splice_offsets() {
+    let input    = "'a `  b ` c'";
+    let splice   = token::Stream::from(vec![Token::Variable("b",2)]);
+    let segments = vec![
+        Token::TextSegmentRaw("a ",0),
+        Token::TextSegmentSplice(splice,1,0),
+        Token::TextSegmentRaw(" c",0),
+    ];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Interpolated,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn interpolated_text_line_nested_text() {
+    let input    = r#"'`"a" 'b`c`'`'"#;
+    let inner    = vec![
+        Token::TextSegmentRaw("b",0),
+        Token::TextSegmentSplice(token::Stream::from(vec![Token::Variable("c",0)]),0,0),
+    ];
+    let splice   = token::Stream::from(vec![
+        Token::TextLine(TextStyle::Raw,vec![Token::TextSegmentRaw("a",0)],0),
+        Token::TextLine(TextStyle::Interpolated,inner,1),
+    ]);
+    let segments = vec![Token::TextSegmentSplice(splice,0,0)];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Interpolated,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn interpolated_text_line_escapes() {
+    let input    = r#"'\`"\''"#;
+    let segments = vec![
+        Token::TextSegmentEscape(EscapeStyle::Simple,r"\`",0),
+        Token::TextSegmentRaw("\"",0),
+        Token::TextSegmentEscape(EscapeStyle::Simple,r"\'",0),
+    ];
+    let expected = token::Stream::from(vec![Token::TextLine(TextStyle::Interpolated,segments,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_interpolated_text_line() {
+    let input    = "'abc";
+    let segments = vec![Token::TextSegmentRaw("abc",0)];
+    let expected = token::Stream::from(vec![
+        Token::UnclosedTextLine(TextStyle::Interpolated,segments,0)
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_splice_at_eof() {
+    let input    = "foo 'a `b ";
+    let splice   = token::Stream::from(vec![Token::Variable("b",0)]);
+    let segments = vec![
+        Token::TextSegmentRaw("a ",0),
+        Token::UnclosedTextSplice(splice,1,0),
+    ];
+    let expected = token::Stream::from(vec![
+        Token::Variable("foo",0),
+        Token::UnclosedTextLine(TextStyle::Interpolated,segments,1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_splice_at_line_end() {
+    let input    = make_unix_line_endings("'`a\nb");
+    let splice   = token::Stream::from(vec![Token::Variable("a",0)]);
+    let segments = vec![Token::UnclosedTextSplice(splice,0,0)];
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(
+                    vec![Token::UnclosedTextLine(TextStyle::Interpolated,segments,0)],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(vec![Token::Variable("b",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unmatched_backtick() {
+    let input    = "a ` b";
+    let expected = token::Stream::from(vec![
+        Token::Variable("a",0),
+        Token::UnmatchedBacktick(1),
+        Token::Variable("b",1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn raw_text_block() {
+    let input = make_unix_line_endings(
+r#"x = """
+    part of the string
+        still part of the string
+
+    also part of the string
+not_string_expr"#);
+    let text_block = Token::TextBlock(
+        TextStyle::Raw,
+        4,
+        vec![
+            Token::Line(vec![Token::TextSegmentRaw("part of the string",0)],0,LineEnding::LF),
+            Token::Line(
+                vec![Token::TextSegmentRaw("    still part of the string",0)],
+                0,
+                LineEnding::LF
+            ),
+            Token::BlankLine(0,LineEnding::LF),
+            Token::Line(
+                vec![Token::TextSegmentRaw("also part of the string",0)],
+                0,
+                LineEnding::LF
+            ),
+        ],
+        1
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    text_block,
+                ], 0, LineEnding::LF),
+                Token::Line(vec![Token::Variable("not_string_expr",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn raw_text_block_crlf() {
+    let input      = "\"\"\"\r\n  a \\n\r\n\r\n    b\r\nc";
+    let text_block = Token::TextBlock(
+        TextStyle::Raw,
+        2,
+        vec![
+            Token::Line(vec![
+                Token::TextSegmentRaw("a ",0),
+                Token::TextSegmentEscape(EscapeStyle::Simple,r"\n",0),
+            ], 0, LineEnding::CRLF),
+            Token::BlankLine(0,LineEnding::CRLF),
+            Token::Line(vec![Token::TextSegmentRaw("  b",0)],0,LineEnding::CRLF),
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![text_block],0,LineEnding::CRLF),
+                Token::Line(vec![Token::Variable("c",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_tab_indentation() {
+    let input      = "x = \"\"\"\n\thello 'world\n\t\tagain\n";
+    let text_block = Token::TextBlock(
+        TextStyle::Raw,
+        1,
+        vec![
+            Token::Line(vec![Token::TextSegmentRaw("hello 'world",0)],0,LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("\tagain",0)],0,LineEnding::LF),
+        ],
+        1
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    text_block,
+                ], 0, LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn interpolated_text_block() {
+    let input      = "'''\n    Age is `person.age`\n    'quoted' \"text\"\n";
+    let splice     = token::Stream::from(vec![
+        Token::Variable("person",0),
+        Token::Operator(".",0),
+        Token::Variable("age",0),
+    ]);
+    let text_block = Token::TextBlock(
+        TextStyle::Interpolated,
+        4,
+        vec![
+            Token::Line(vec![
+                Token::TextSegmentRaw("Age is ",0),
+                Token::TextSegmentSplice(splice,0,0),
+            ], 0, LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("'quoted' \"text\"",0)],0,LineEnding::LF),
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![Token::Line(vec![text_block],0,LineEnding::LF)],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn interpolated_text_block_unclosed_splice() {
+    let input      = "'''\n    a `b\n    c";
+    let splice     = token::Stream::from(vec![Token::Variable("b",0)]);
+    let text_block = Token::TextBlock(
+        TextStyle::Interpolated,
+        4,
+        vec![
+            Token::Line(vec![
+                Token::TextSegmentRaw("a ",0),
+                Token::UnclosedTextSplice(splice,0,0),
+            ], 0, LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("c",0)],0,LineEnding::None),
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![Token::Line(vec![text_block],0,LineEnding::LF)],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_in_nested_block() {
+    let input = make_unix_line_endings(
+r#"main =
+    x = """
+        text
+    y"#);
+    let text_block = Token::TextBlock(
+        TextStyle::Raw,
+        8,
+        vec![Token::Line(vec![Token::TextSegmentRaw("text",0)],0,LineEnding::LF)],
+        1
+    );
+    let main_block = Token::Block(
+        BlockType::Discontinuous,
+        4,
+        vec![
+            Token::Line(vec![
+                Token::Variable("x",0),
+                Token::Operator("=",1),
+                text_block,
+            ], 0, LineEnding::LF),
+            Token::Line(vec![Token::Variable("y",0)],0,LineEnding::None),
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("main",0),
+                    Token::Operator("=",1),
+                    main_block,
+                ], 0, LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn empty_text_block() {
+    let input    = "x = \"\"\"\ny";
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    Token::TextBlock(TextStyle::Raw,0,vec![],1),
+                ], 0, LineEnding::LF),
+                Token::Line(vec![Token::Variable("y",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_at_eof() {
+    let input    = "x = '''";
+    let expected = token::Stream::from(vec![
+        Token::Variable("x",0),
+        Token::Operator("=",1),
+        Token::TextBlock(TextStyle::Interpolated,0,vec![],1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_quotes_before_text() {
+    let input    = r#""""a""#;
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::Raw,vec![],0),
+        Token::TextLine(TextStyle::Raw,vec![Token::TextSegmentRaw("a",0)],0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_quotes_in_splice() {
+    let input    = r#"'`""""#;
+    let splice   = token::Stream::from(vec![
+        Token::TextLine(TextStyle::Raw,vec![],0),
+        Token::UnclosedTextLine(TextStyle::Raw,vec![],0),
+    ]);
+    let segments = vec![Token::UnclosedTextSplice(splice,0,0)];
+    let expected = token::Stream::from(vec![
+        Token::UnclosedTextLine(TextStyle::Interpolated,segments,0)
+    ]);
+    assert_lexes(input,expected);
+}
+
+
+// ================
+// === Comments ===
+// ================
+
+#[test]
+fn disable_comment() {
+    let input    = "# x = 1";
+    let tokens   = token::Stream::from(vec![
+        Token::Variable("x",1),
+        Token::Operator("=",1),
+        Token::Number("","1","","",1),
+    ]);
+    let expected = token::Stream::from(vec![Token::Comment(" x = 1",tokens,0,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn disable_comment_trailing_offset() {
+    let input    = "#foo  ";
+    let tokens   = token::Stream::from(vec![Token::Variable("foo",0)]);
+    let expected = token::Stream::from(vec![Token::Comment("foo  ",tokens,2,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn disable_comment_after_code() {
+    let input          = "x = 1 # set x\r\ny";
+    let comment_tokens = token::Stream::from(vec![
+        Token::Variable("set",1),
+        Token::Variable("x",1),
+    ]);
+    let expected       = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    Token::Number("","1","","",1),
+                    Token::Comment(" set x",comment_tokens,0,1),
+                ], 0, LineEnding::CRLF),
+                Token::Line(vec![Token::Variable("y",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn disable_comment_ends_at_carriage_return() {
+    let input    = "#a\rb";
+    let tokens   = token::Stream::from(vec![Token::Variable("a",0)]);
+    let expected = token::Stream::from(vec![
+        Token::Comment("a",tokens,0,0),
+        Token::Unrecognized("\r",0),
+        Token::Variable("b",0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn empty_disable_comment() {
+    let input    = "#";
+    let expected = token::Stream::from(vec![Token::Comment("",token::Stream::default(),0,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn nested_disable_comment() {
+    let input    = "# a # b";
+    let inner    = token::Stream::from(vec![Token::Variable("b",1)]);
+    let inner    = Token::Comment(" b",inner,0,1);
+    let outer    = token::Stream::from(vec![Token::Variable("a",1),inner]);
+    let expected = token::Stream::from(vec![Token::Comment(" a # b",outer,0,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn disable_comment_with_prose() {
+    let input    = "# Don't `lex` this  ";
+    let tokens   = token::Stream::from(vec![
+        Token::Referent("Don'",1),
+        Token::InvalidSuffix("t",0),
+        Token::UnmatchedBacktick(1),
+        Token::Variable("lex",0),
+        Token::UnmatchedBacktick(0),
+        Token::Variable("this",1),
+    ]);
+    let comment  = Token::Comment(" Don't `lex` this  ",tokens,2,0);
+    assert!(comment.has_errors());
+    let expected = token::Stream::from(vec![comment]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn disable_comment_with_unclosed_text() {
+    let input    = "# a \"b\nx";
+    let text     = Token::UnclosedTextLine(TextStyle::Raw,vec![Token::TextSegmentRaw("b",0)],1);
+    let tokens   = token::Stream::from(vec![Token::Variable("a",1),text]);
+    let comment  = Token::Comment(" a \"b",tokens,0,0);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![comment],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("x",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn disable_comment_with_multiline_literals() {
+    let input    = "# ## a\n#'''";
+    let doc      = Token::DocComment(
+        vec![Token::Line(vec![Token::TextSegmentRaw(" a",0)],0,LineEnding::None)],
+        0,
+        1
+    );
+    let quotes   = token::Stream::from(vec![
+        Token::TextLine(TextStyle::Interpolated,vec![],0),
+        Token::UnclosedTextLine(TextStyle::Interpolated,vec![],0),
+    ]);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(
+                    vec![Token::Comment(" ## a",token::Stream::from(vec![doc]),0,0)],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(
+                    vec![Token::Comment("'''",quotes,0,0)],
+                    0,
+                    LineEnding::None
+                ),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn disable_comment_in_splice() {
+    let input    = "x\n'`a # b`";
+    let tokens   = token::Stream::from(vec![Token::Variable("b",1),Token::UnmatchedBacktick(0)]);
+    let comment  = Token::Comment(" b`",tokens,0,1);
+    let splice   = token::Stream::from(vec![Token::Variable("a",0),comment]);
+    let segments = vec![Token::UnclosedTextSplice(splice,0,0)];
+    let text     = Token::UnclosedTextLine(TextStyle::Interpolated,segments,0);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![Token::Variable("x",0)],0,LineEnding::LF),
+                Token::Line(vec![text],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment() {
+    let input    = "## Docs";
+    let lines    = vec![Token::Line(vec![Token::TextSegmentRaw(" Docs",0)],0,LineEnding::None)];
+    let expected = token::Stream::from(vec![Token::DocComment(lines,0,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn empty_doc_comment() {
+    let input    = "##";
+    let lines    = vec![Token::Line(vec![],0,LineEnding::None)];
+    let expected = token::Stream::from(vec![Token::DocComment(lines,0,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn multiline_doc_comment() {
+    let input = make_unix_line_endings(
+r#"## My documentation comment
+   continues all the way down
+
+     with extra indentation
+   until I unindent again.
+foo"#);
+    let doc_comment = Token::DocComment(
+        vec![
+            Token::Line(
+                vec![Token::TextSegmentRaw(" My documentation comment",0)],
+                0,
+                LineEnding::None
+            ),
+            Token::Line(
+                vec![Token::TextSegmentRaw("continues all the way down",0)],
+                0,
+                LineEnding::LF
+            ),
+            Token::BlankLine(0,LineEnding::LF),
+            Token::Line(
+                vec![Token::TextSegmentRaw("  with extra indentation",0)],
+                0,
+                LineEnding::LF
+            ),
+            Token::Line(
+                vec![Token::TextSegmentRaw("until I unindent again.",0)],
+                0,
+                LineEnding::LF
+            ),
+        ],
+        3,
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![doc_comment],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("foo",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment_tab_indentation() {
+    let input       = "## doc\n\tmore\nfoo";
+    let doc_comment = Token::DocComment(
+        vec![
+            Token::Line(vec![Token::TextSegmentRaw(" doc",0)],0,LineEnding::None),
+            Token::Line(vec![Token::TextSegmentRaw("more",0)],0,LineEnding::LF),
+        ],
+        1,
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![doc_comment],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("foo",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment_in_nested_block() {
+    let input = make_unix_line_endings(
+r#"type Foo
+    ## Docs
+       more docs
+    bar"#);
+    let doc_comment = Token::DocComment(
+        vec![
+            Token::Line(vec![Token::TextSegmentRaw(" Docs",0)],0,LineEnding::None),
+            Token::Line(vec![Token::TextSegmentRaw("more docs",0)],0,LineEnding::LF),
+        ],
+        7,
+        0
+    );
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        4,
+        vec![
+            Token::Line(vec![doc_comment],0,LineEnding::LF),
+            Token::Line(vec![Token::Variable("bar",0)],0,LineEnding::None),
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Keyword("type",0),
+                    Token::Referent("Foo",1),
+                    nested_block,
+                ], 0, LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment_at_eof_with_line_ending() {
+    let input       = "## a\n  b\n";
+    let doc_comment = Token::DocComment(
+        vec![
+            Token::Line(vec![Token::TextSegmentRaw(" a",0)],0,LineEnding::None),
+            Token::Line(vec![Token::TextSegmentRaw("b",0)],0,LineEnding::LF),
+        ],
+        2,
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![Token::Line(vec![doc_comment],0,LineEnding::LF)],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment_in_splice() {
+    let input    = "'`## a`";
+    let lines    = vec![Token::Line(vec![Token::TextSegmentRaw(" a`",0)],0,LineEnding::None)];
+    let splice   = token::Stream::from(vec![Token::DocComment(lines,0,0)]);
+    let segments = vec![Token::UnclosedTextSplice(splice,0,0)];
+    let expected = token::Stream::from(vec![
+        Token::UnclosedTextLine(TextStyle::Interpolated,segments,0)
+    ]);
+    assert_lexes(input,expected);
+}
+
+
+// ====================
+// === Foreign Code ===
+// ====================
+
+#[test]
+fn foreign_block() {
+    let input = make_unix_line_endings(
+r#"foreign python foo a = """
+    import os
+
+      return "\q" `a` # not a comment
+bar"#);
+    let foreign_block = Token::ForeignBlock(
+        "python",
+        4,
+        vec![
+            Token::Line(vec![Token::TextSegmentRaw("import os",0)],0,LineEnding::LF),
+            Token::BlankLine(0,LineEnding::LF),
+            Token::Line(
+                vec![Token::TextSegmentRaw("  return \"\\q\" `a` # not a comment",0)],
+                0,
+                LineEnding::LF
+            ),
+        ],
+        1
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Keyword("foreign",0),
+                    Token::Variable("python",1),
+                    Token::Variable("foo",1),
+                    Token::Variable("a",1),
+                    Token::Operator("=",1),
+                    foreign_block,
+                ], 0, LineEnding::LF),
+                Token::Line(vec![Token::Variable("bar",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn foreign_block_in_nested_block() {
+    let input = make_unix_line_endings(
+r#"type Foo
+    foreign js bar = """
+        return 1;
+"#);
+    let foreign_block = Token::ForeignBlock(
+        "js",
+        8,
+        vec![Token::Line(vec![Token::TextSegmentRaw("return 1;",0)],0,LineEnding::LF)],
+        1
+    );
+    let type_block = Token::Block(
+        BlockType::Continuous,
+        4,
+        vec![
+            Token::Line(vec![
+                Token::Keyword("foreign",0),
+                Token::Variable("js",1),
+                Token::Variable("bar",1),
+                Token::Operator("=",1),
+                foreign_block,
+            ], 0, LineEnding::LF),
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Keyword("type",0),
+                    Token::Referent("Foo",1),
+                    type_block,
+                ], 0, LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn foreign_block_tab_indentation() {
+    let input         = "foreign python foo = \"\"\"\n\tx = 1\n\tif y: return `z`\n";
+    let foreign_block = Token::ForeignBlock(
+        "python",
+        1,
+        vec![
+            Token::Line(vec![Token::TextSegmentRaw("x = 1",0)],0,LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("if y: return `z`",0)],0,LineEnding::LF),
+        ],
+        1
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Keyword("foreign",0),
+                    Token::Variable("python",1),
+                    Token::Variable("foo",1),
+                    Token::Operator("=",1),
+                    foreign_block,
+                ], 0, LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn foreign_block_mixed_indentation() {
+    let input         = "foreign js f = \"\"\"\n    a\n\t  b\n\tc\nd";
+    let foreign_block = Token::ForeignBlock(
+        "js",
+        1,
+        vec![
+            Token::Line(vec![Token::TextSegmentRaw("   a",0)],0,LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("  b",0)],0,LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("c",0)],0,LineEnding::LF),
+        ],
+        1
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Keyword("foreign",0),
+                    Token::Variable("js",1),
+                    Token::Variable("f",1),
+                    Token::Operator("=",1),
+                    foreign_block,
+                ], 0, LineEnding::LF),
+                Token::Line(vec![Token::Variable("d",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn foreign_block_without_foreign_keyword() {
+    let mut lexer = EnsoLexer::with_keywords(Keywords::new(vec!["type"]));
+    let result    = lexer.run(StrReader::new("foreign js f = \"\"\"\n    return 1;"));
+    let foreign_block = Token::ForeignBlock(
+        "js",
+        4,
+        vec![Token::Line(vec![Token::TextSegmentRaw("return 1;",0)],0,LineEnding::None)],
+        1
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("foreign",0),
+                    Token::Variable("js",1),
+                    Token::Variable("f",1),
+                    Token::Operator("=",1),
+                    foreign_block,
+                ], 0, LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_succeeds_as(&result,expected)
+}
+
+#[test]
+fn foreign_keyword_not_at_line_start() {
+    let input = make_unix_line_endings(
+r#"x foreign js = """
+    a"#);
+    let text_block = Token::TextBlock(
+        TextStyle::Raw,
+        4,
+        vec![Token::Line(vec![Token::TextSegmentRaw("a",0)],0,LineEnding::None)],
+        1
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Keyword("foreign",1),
+                    Token::Variable("js",1),
+                    Token::Operator("=",1),
+                    text_block,
+                ], 0, LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+
+
+// ==============
+// === Blocks ===
+// ==============
+
+#[test]
+fn block_function_call() {
+    let input = make_unix_line_endings(
+r#"f
+    argument_1
+    argument_2
+    fn a1 a2 a3
+    argument_4
+    argument_5"#);
+    let block_fn_args =
+        Token::Block(
+            BlockType::Continuous,
+            4,
+            vec![
+                Token::Line(
+                    vec![Token::Variable("argument_1",0)],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(
+                    vec![
+                        Token::Variable("argument_2",0),
+                    ],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(
+                    vec![
+                        Token::Variable("fn",0),
+                        Token::Variable("a1",1),
+                        Token::Variable("a2",1),
+                        Token::Variable("a3",1),
+                    ],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(
+                    vec![
+                        Token::Variable("argument_4",0),
+                    ],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(
+                    vec![
+                        Token::Variable("argument_5",0),
+                    ],
+                    0,
+                    LineEnding::None
+                ),
+            ],
+            0
+        );
+    let top_level_first_line = Token::Line(
+        vec![
+            Token::Variable("f",0),
+            block_fn_args
+        ],
+        0,
+        LineEnding::LF
+    );
+    let top_level_block = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![top_level_first_line],
+            0
+        )
+    ]);
+    assert_lexes(input,top_level_block);
+}
+
+
+#[test]
+fn block_empty_lines() {
+    let input        = "f\r\n    a\n\n    b\n";
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        4,
+        vec![
+            Token::Line(vec![Token::Variable("a",0)],0,LineEnding::LF),
+            Token::BlankLine(0,LineEnding::LF),
+            Token::Line(vec![Token::Variable("b",0)],0,LineEnding::LF),
+        ],
+        0
+    );
+    let top_line = Token::Line(
+        vec![
+            Token::Variable("f",0),
+            nested_block
+        ],
+        0,
+        LineEnding::CRLF
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![top_line],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn block_top_level() {
+    let input = make_unix_line_endings(
+r#"
+
+foo
+bar
+baz
+"#);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::BlankLine(0,LineEnding::LF),
+                Token::BlankLine(0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("foo",0)],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("bar",0)],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("baz",0)],0,LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn block_with_operator() {
     let input = make_unix_line_endings(
 r#"x ->
     foo x 1
@@ -633,7 +1983,7 @@ r#"x ->
             Token::Line(vec![
                 Token::Variable("foo",0),
                 Token::Variable("x",1),
-                Token::Number("","1",1),
+                Token::Number("","1","","",1),
             ], 0, LineEnding::LF)
         ],
         0
@@ -752,8 +2102,190 @@ fn block_length_mixed() {
     assert_block_has_length(input,20);
 }
 
+#[test]
+fn block_tab_indentation() {
+    let input        = "f\n\ta\n\tb";
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        1,
+        vec![
+            Token::Line(vec![
+                Token::InvalidIndentation(IndentStyle::Tabs,1,0,0),
+                Token::Variable("a",0),
+            ],0,LineEnding::LF),
+            Token::Line(vec![
+                Token::InvalidIndentation(IndentStyle::Tabs,2,0,0),
+                Token::Variable("b",0),
+            ],0,LineEnding::None),
+        ],
+        0
+    );
+    let top_line = Token::Line(vec![Token::Variable("f",0),nested_block],0,LineEnding::LF);
+    let expected = token::Stream::from(vec![
+        Token::Block(BlockType::Continuous,0,vec![top_line],0)
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn block_mixed_indentation() {
+    let input        = "f\r\n    a\r\n  \t b";
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        4,
+        vec![
+            Token::Line(vec![Token::Variable("a",0)],0,LineEnding::CRLF),
+            Token::Line(vec![
+                Token::InvalidIndentation(IndentStyle::Mixed,2,2,0),
+                Token::Variable("b",0),
+            ],0,LineEnding::None),
+        ],
+        0
+    );
+    let top_line = Token::Line(vec![Token::Variable("f",0),nested_block],0,LineEnding::CRLF);
+    let expected = token::Stream::from(vec![
+        Token::Block(BlockType::Continuous,0,vec![top_line],0)
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn block_tab_indented_blank_line() {
+    let input    = "a\n\t \nb\n";
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![Token::Variable("a",0)],0,LineEnding::LF),
+                Token::BlankLine(2,LineEnding::LF),
+                Token::Line(vec![Token::Variable("b",0)],0,LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn block_tab_indentation_after_text_block() {
+    let input      = "x = \"\"\"\n    one\n\n    two\ny\n\tz";
+    let text_block = Token::TextBlock(
+        TextStyle::Raw,
+        4,
+        vec![
+            Token::Line(vec![Token::TextSegmentRaw("one",0)],0,LineEnding::LF),
+            Token::BlankLine(0,LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("two",0)],0,LineEnding::LF),
+        ],
+        1
+    );
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        1,
+        vec![
+            Token::Line(vec![
+                Token::InvalidIndentation(IndentStyle::Tabs,5,0,0),
+                Token::Variable("z",0),
+            ],0,LineEnding::None),
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    text_block,
+                ],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("y",0),nested_block],0,LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn block_length_tabs() {
+    let input = "a\n\tb\n\tc";
+    assert_block_has_length(input,7);
+}
+
 
 
 // ================
 // === Combined ===
 // ================
+
+
+
+// ==============
+// === Groups ===
+// ==============
+
+#[test]
+fn suffix_checks_reachable_from_root() {
+    let lexer        = EnsoLexer::define();
+    let groups       = lexer.groups();
+    let reachable    = groups.reachable_from(lexer.initial_state());
+    let names:Vec<_> = reachable.into_iter().map(|id| groups.group(id).name.as_str()).collect();
+    let expected     = vec!["ROOT","OPERATOR_MODIFIER_CHECK","OPERATOR_SUFFIX_CHECK","IDENT_SFX_CHECK"];
+    assert_eq!(names,expected);
+}
+
+
+
+// ==================
+// === Benchmarks ===
+// ==================
+
+extern crate test;
+use test::Bencher;
+
+/// An input made up of many identifiers and operators.
+fn bench_input() -> String {
+    "foo_bar + Baz_quux * some_var_name - __externalName ".repeat(1000)
+}
+
+#[bench]
+fn bench_lex_streamed(bencher:&mut Bencher) {
+    let input = bench_input();
+    bencher.iter(|| lex(&input));
+}
+
+#[bench]
+fn bench_lex_shared(bencher:&mut Bencher) {
+    let input = bench_input();
+    bencher.iter(|| lex_shared(&input));
+}
+
+#[bench]
+fn bench_lex_utf8(bencher:&mut Bencher) {
+    let input = bench_input();
+    bencher.iter(|| lex_utf8(&input));
+}
+
+/// Many small inputs, as when lexing each file of a project.
+fn bench_files() -> Vec<String> {
+    (0..1000).map(|ix| format!("foo_{} = bar + {}\n    baz",ix,ix)).collect()
+}
+
+#[bench]
+fn bench_lex_files_new(bencher:&mut Bencher) {
+    let files = bench_files();
+    bencher.iter(|| files.iter().map(|file| lex_shared(file)).collect::<Vec<_>>());
+}
+
+#[bench]
+fn bench_lex_files_reused(bencher:&mut Bencher) {
+    let files     = bench_files();
+    let mut lexer = EnsoLexer::new();
+    bencher.iter(|| files.iter().map(|file| {
+        lexer.reset();
+        lexer.run(StrReader::new(file.as_str()))
+    }).collect::<Vec<_>>());
+}
@@ -0,0 +1,166 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for lexing batches of Enso files across threads, as well as
+//! benchmarks of lexing the standard library.
+
+use flexer::*;
+
+use flexer::prelude::ByteReader;
+use flexer::prelude::Reader;
+use flexer::prelude::StrReader;
+use flexer::prelude::reader::decoder::DecoderUTF8;
+use lexer::batch;
+use lexer::generated::engine::EnsoLexer;
+use lexer::generated::engine_utf8;
+use std::path::PathBuf;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Assert that `T` can be sent between threads.
+fn assert_send<T:Send>() {}
+
+/// Get the paths of the Enso source files in the standard library.
+fn std_lib_files() -> Vec<PathBuf> {
+    let root      = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../../../distribution");
+    let mut files = Vec::new();
+    let mut dirs  = vec![root.join("std-lib")];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path)
+            } else if path.extension().map(|ext| ext == "enso").unwrap_or(false) {
+                files.push(path)
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Read the sources of the Enso files in the standard library.
+fn std_lib_sources() -> Vec<String> {
+    std_lib_files().iter().map(|path| std::fs::read_to_string(path).unwrap()).collect()
+}
+
+/// Lex the file at `path` on the current thread, with a new lexer.
+fn lex_file(path:&PathBuf) -> LexingResult<lexer_definition::token::Stream> {
+    let source = std::fs::read_to_string(path).unwrap();
+    EnsoLexer::new().run(StrReader::new(source))
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn lexers_are_send() {
+    assert_send::<EnsoLexer>();
+    assert_send::<engine_utf8::EnsoLexer>();
+}
+
+#[test]
+fn batch_matches_sequential() {
+    let files = std_lib_files();
+    assert!(!files.is_empty());
+    let results = batch::lex_many_with(&files,4);
+    assert_eq!(results.len(),files.len());
+    for (path,result) in files.iter().zip(results) {
+        let result   = result.unwrap();
+        let expected = lex_file(path);
+        assert_eq!(result.kind,expected.kind,"{}",path.display());
+        assert_eq!(result.tokens,expected.tokens,"{}",path.display());
+    }
+}
+
+#[test]
+fn failures_are_isolated() {
+    let mut files = std_lib_files();
+    files.truncate(3);
+    files.insert(1,PathBuf::from("does/not/exist.enso"));
+    let results = batch::lex_many(&files);
+    assert_eq!(results.len(),4);
+    match results[1] {
+        Err(batch::Error::Io(_)) => (),
+        _                        => panic!("Reading a missing file should fail."),
+    }
+    for (path,result) in files.iter().zip(results).filter(|(_,result)| result.is_ok()) {
+        assert_eq!(result.unwrap().tokens,lex_file(path).tokens);
+    }
+}
+
+#[test]
+fn empty_batch() {
+    let files:Vec<PathBuf> = Vec::new();
+    assert!(batch::lex_many(&files).is_empty());
+}
+
+#[test]
+#[cfg(feature="profiling")]
+fn profile_std_lib() {
+    let mut lexer = EnsoLexer::new();
+    for path in std_lib_files() {
+        lexer.reset();
+        lexer.run(StrReader::new(std::fs::read_to_string(path).unwrap()));
+    }
+    let root = lexer.profile.group("ROOT").unwrap();
+    assert!(root.characters > 0 && root.rules_fired > 0);
+    for group in lexer.profile.groups.iter() {
+        assert!(group.rules_fired <= group.dfa_steps);
+        assert!(group.callback_time <= group.step_time);
+    }
+    assert!(lexer.profile.to_string().contains(&root.to_string()));
+}
+
+
+
+// ==================
+// === Benchmarks ===
+// ==================
+
+extern crate test;
+use test::Bencher;
+
+#[bench]
+fn bench_std_lib_streamed(bencher:&mut Bencher) {
+    let sources   = std_lib_sources();
+    let mut lexer = EnsoLexer::new();
+    bencher.iter(|| sources.iter().map(|source| {
+        lexer.reset();
+        lexer.run(Reader::new(source.as_bytes(),DecoderUTF8()))
+    }).collect::<Vec<_>>());
+}
+
+#[bench]
+fn bench_std_lib_shared(bencher:&mut Bencher) {
+    let sources   = std_lib_sources();
+    let mut lexer = EnsoLexer::new();
+    bencher.iter(|| sources.iter().map(|source| {
+        lexer.reset();
+        lexer.run(StrReader::new(source.as_str()))
+    }).collect::<Vec<_>>());
+}
+
+#[bench]
+fn bench_std_lib_utf8(bencher:&mut Bencher) {
+    let sources   = std_lib_sources();
+    let mut lexer = engine_utf8::EnsoLexer::new();
+    bencher.iter(|| sources.iter().map(|source| {
+        lexer.reset();
+        lexer.run(ByteReader::new(source.as_str()))
+    }).collect::<Vec<_>>());
+}
@@ -0,0 +1,71 @@
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file builds the C test program for the C interface to the Enso lexer against the lexer's
+//! shared library, and runs it.
+
+#![cfg(target_os="linux")]
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// The directory containing the shared library of the lexer.
+///
+/// Cargo builds the shared library into the same directory as the test executables.
+fn library_directory() -> PathBuf {
+    let executable = std::env::current_exe().expect("The test executable should have a path.");
+    let directory  = executable.parent().expect("The test executable should be in a directory.");
+    directory.to_path_buf()
+}
+
+/// Compile the C test program against the shared library in `library`, returning the path to
+/// the resulting executable.
+fn compile_test_program(library:&Path) -> PathBuf {
+    let crate_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let output     = library.join("enso_lexer_test");
+    let compiler   = cc::Build::new()
+        .cargo_metadata(false)
+        .opt_level(0)
+        .target(env!("TARGET"))
+        .host(env!("TARGET"))
+        .get_compiler();
+    let status = compiler.to_command()
+        .arg("-I").arg(crate_root.join("include"))
+        .arg(crate_root.join("tests").join("c").join("enso_lexer_test.c"))
+        .arg("-L").arg(library)
+        .arg("-llexer")
+        .arg("-o").arg(&output)
+        .status()
+        .expect("The C compiler should run.");
+    assert!(status.success(),"The C test program failed to compile.");
+    output
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn run_c_test_program() {
+    let library = library_directory();
+    let program = compile_test_program(&library);
+    let output  = Command::new(&program).env("LD_LIBRARY_PATH",&library).output();
+    let output  = output.expect("The C test program should run.");
+    let stderr  = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(),"The C test program failed:\n{}",stderr);
+}
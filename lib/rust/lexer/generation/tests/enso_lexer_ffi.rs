@@ -0,0 +1,241 @@
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![allow(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for the C interface to the Enso lexer.
+
+use lexer::ffi::*;
+
+use std::os::raw::c_char;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Lex `input` with `lexer`.
+fn lex(lexer:*mut Lexer, input:&str) -> *mut LexResult {
+    unsafe { enso_lexer_lex(lexer,input.as_ptr() as *const c_char,input.len()) }
+}
+
+/// Get the text of `token` as a string, if it has any.
+fn text(token:*const Token) -> Option<String> {
+    unsafe {
+        let mut length = 0;
+        let pointer    = enso_token_text(token,&mut length);
+        if pointer.is_null() {
+            None
+        } else {
+            let bytes = std::slice::from_raw_parts(pointer as *const u8,length);
+            Some(String::from_utf8(bytes.to_vec()).unwrap())
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn lex_identifiers() {
+    let lexer  = enso_lexer_new();
+    let result = lex(lexer,"foo Bar");
+    unsafe {
+        assert!(!result.is_null());
+        assert_eq!(enso_lex_result_kind(result),LexResultKind::Success);
+        assert_eq!(enso_lex_result_token_count(result),2);
+        let foo = enso_lex_result_token(result,0);
+        let bar = enso_lex_result_token(result,1);
+        assert_eq!(enso_token_shape(foo),ShapeKind::Variable);
+        assert_eq!(text(foo),Some("foo".into()));
+        assert_eq!(enso_token_shape(bar),ShapeKind::Referent);
+        assert_eq!(enso_token_offset(bar),1);
+        assert_eq!(enso_token_length(bar),3);
+        assert!(enso_lex_result_token(result,2).is_null());
+        enso_lex_result_free(result);
+        enso_lexer_free(lexer);
+    }
+}
+
+#[test]
+fn lex_number() {
+    let lexer  = enso_lexer_new();
+    let result = lex(lexer,"16_ff");
+    unsafe {
+        let number     = enso_lex_result_token(result,0);
+        let mut length = 0;
+        let base       = enso_token_number_base(number,&mut length);
+        assert_eq!(enso_token_shape(number),ShapeKind::Number);
+        assert_eq!(text(number),Some("ff".into()));
+        assert_eq!(std::slice::from_raw_parts(base as *const u8,length),b"16");
+        enso_lex_result_free(result);
+        enso_lexer_free(lexer);
+    }
+}
+
+#[test]
+fn lex_number_parts() {
+    let lexer  = enso_lexer_new();
+    let result = lex(lexer,"1.5e-10");
+    unsafe {
+        let number         = enso_lex_result_token(result,0);
+        let mut length     = 0;
+        let fractional     = enso_token_number_fractional(number,&mut length);
+        let fractional     = std::slice::from_raw_parts(fractional as *const u8,length).to_vec();
+        let exponent       = enso_token_number_exponent(number,&mut length);
+        let exponent       = std::slice::from_raw_parts(exponent as *const u8,length).to_vec();
+        assert_eq!(text(number),Some("1".into()));
+        assert_eq!(fractional,b"5");
+        assert_eq!(exponent,b"-10");
+        enso_lex_result_free(result);
+        enso_lexer_free(lexer);
+    }
+}
+
+#[test]
+fn lex_invalid_number() {
+    let lexer  = enso_lexer_new();
+    let result = lex(lexer,"2_1021");
+    unsafe {
+        let number = enso_lex_result_token(result,0);
+        assert_eq!(enso_token_shape(number),ShapeKind::InvalidNumber);
+        assert_eq!(text(number),Some("1021".into()));
+        assert_eq!(enso_token_number_invalid_digit(number),2);
+        enso_lex_result_free(result);
+        enso_lexer_free(lexer);
+    }
+}
+
+#[test]
+fn lex_block() {
+    let lexer  = enso_lexer_new();
+    let result = lex(lexer,"a\n  b");
+    unsafe {
+        let top   = enso_lex_result_token(result,0);
+        let line  = enso_token_child(top,0);
+        let block = enso_token_child(line,1);
+        assert_eq!(enso_token_shape(top),ShapeKind::Block);
+        assert_eq!(enso_token_block_type(top),BlockType::Continuous);
+        assert_eq!(enso_token_shape(line),ShapeKind::Line);
+        assert_eq!(enso_token_line_ending(line),LineEnding::LF);
+        assert_eq!(enso_token_child_count(line),2);
+        assert_eq!(text(line),None);
+        assert_eq!(enso_token_shape(block),ShapeKind::Block);
+        assert_eq!(enso_token_block_indent(block),2);
+        assert_eq!(text(enso_token_child(enso_token_child(block,0),0)),Some("b".into()));
+        enso_lex_result_free(result);
+        enso_lexer_free(lexer);
+    }
+}
+
+#[test]
+fn lex_foreign_block() {
+    let lexer  = enso_lexer_new();
+    let result = lex(lexer,"foreign python f = \"\"\"\n  pass");
+    unsafe {
+        let line    = enso_token_child(enso_lex_result_token(result,0),0);
+        let foreign = enso_token_child(line,4);
+        assert_eq!(enso_token_shape(enso_token_child(line,0)),ShapeKind::Keyword);
+        assert_eq!(enso_token_shape(foreign),ShapeKind::ForeignBlock);
+        assert_eq!(text(foreign),Some("python".into()));
+        assert_eq!(enso_token_block_indent(foreign),2);
+        assert_eq!(enso_token_child_count(foreign),1);
+        assert_eq!(text(enso_token_child(enso_token_child(foreign,0),0)),Some("pass".into()));
+        enso_lex_result_free(result);
+        enso_lexer_free(lexer);
+    }
+}
+
+#[test]
+fn lex_disable_comment() {
+    let lexer  = enso_lexer_new();
+    let code   = lex(lexer,"# x = 1");
+    let prose  = lex(lexer,"# don't `x`");
+    unsafe {
+        let comment = enso_lex_result_token(code,0);
+        assert_eq!(enso_token_shape(comment),ShapeKind::Comment);
+        assert_eq!(text(comment),Some(" x = 1".into()));
+        assert_eq!(enso_token_child_count(comment),3);
+        assert_eq!(text(enso_token_child(comment,0)),Some("x".into()));
+        assert!(!enso_token_has_errors(comment));
+        let comment = enso_lex_result_token(prose,0);
+        assert_eq!(text(comment),Some(" don't `x`".into()));
+        assert_eq!(enso_token_child_count(comment),5);
+        assert!(enso_token_has_errors(comment));
+        enso_lex_result_free(code);
+        enso_lex_result_free(prose);
+        enso_lexer_free(lexer);
+    }
+}
+
+#[test]
+fn lex_invalid_indentation() {
+    let lexer  = enso_lexer_new();
+    let result = lex(lexer,"a\n \tb");
+    unsafe {
+        let line        = enso_token_child(enso_lex_result_token(result,0),0);
+        let block       = enso_token_child(line,1);
+        let indentation = enso_token_child(enso_token_child(block,0),0);
+        assert_eq!(enso_token_shape(indentation),ShapeKind::InvalidIndentation);
+        assert_eq!(enso_token_length(indentation),0);
+        assert_eq!(enso_token_indentation_style(indentation),IndentStyle::Mixed);
+        assert_eq!(enso_token_indentation_line(indentation),1);
+        assert_eq!(enso_token_indentation_column(indentation),1);
+        enso_lex_result_free(result);
+        enso_lexer_free(lexer);
+    }
+}
+
+#[test]
+fn lex_errors() {
+    let lexer  = enso_lexer_new();
+    let result = lex(lexer,"foo");
+    unsafe {
+        assert_eq!(enso_lex_result_error_kind(result),LexErrorKind::None);
+        assert_eq!(enso_lex_result_error_max_depth(result),0);
+        enso_lex_result_free(result);
+    }
+    let result = lex(lexer,&"'`".repeat(4096));
+    unsafe {
+        assert_eq!(enso_lex_result_kind(result),LexResultKind::Failure);
+        assert_eq!(enso_lex_result_error_kind(result),LexErrorKind::StateStackOverflow);
+        assert_eq!(enso_lex_result_error_max_depth(result),4096);
+        enso_lex_result_free(result);
+        enso_lexer_free(lexer);
+    }
+}
+
+#[test]
+fn reuse_lexer() {
+    let lexer = enso_lexer_new();
+    for input in &["foo Bar _","16_ff"] {
+        let result = lex(lexer,input);
+        unsafe {
+            assert_eq!(enso_lex_result_kind(result),LexResultKind::Success);
+            assert!(enso_lex_result_token_count(result) > 0);
+            enso_lex_result_free(result);
+        }
+    }
+    unsafe { enso_lexer_free(lexer) }
+}
+
+#[test]
+fn reject_invalid_input() {
+    let lexer = enso_lexer_new();
+    let bytes = [b'a',0xFF];
+    unsafe {
+        assert!(enso_lexer_lex(lexer,bytes.as_ptr() as *const c_char,bytes.len()).is_null());
+        assert!(enso_lexer_lex(std::ptr::null_mut(),bytes.as_ptr() as *const c_char,1).is_null());
+        enso_lexer_free(lexer);
+        enso_lexer_free(std::ptr::null_mut());
+        enso_lex_result_free(std::ptr::null_mut());
+    }
+}